@@ -0,0 +1,3 @@
+-- 分批止盈：多条平仓记录共享交易 ID，并记录平仓原因
+ALTER TABLE trades ADD COLUMN trade_id INTEGER NOT NULL DEFAULT 0; -- 内存中的交易 ID，分批平仓时多条记录共享
+ALTER TABLE trades ADD COLUMN close_reason TEXT NOT NULL DEFAULT 'StopLoss'; -- 平仓原因 ('StopLoss', 'TakeProfit', 'Manual', 'KillSwitch')
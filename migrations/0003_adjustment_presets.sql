@@ -0,0 +1,9 @@
+-- 止损阶梯预设表
+CREATE TABLE IF NOT EXISTS adjustment_presets (
+    id INTEGER PRIMARY KEY AUTOINCREMENT, -- 自增主键
+    owner_id INTEGER,                     -- 所属用户，NULL 表示系统预设
+    name TEXT NOT NULL,                   -- 预设名称
+    adjustments TEXT NOT NULL,            -- 阶梯配置（JSON）
+    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+    updated_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+);
@@ -0,0 +1,14 @@
+-- 加仓记录表
+CREATE TABLE IF NOT EXISTS trade_legs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT, -- 自增主键
+    trade_id INTEGER NOT NULL,           -- 内存中的交易 ID
+    owner_id TEXT NOT NULL,              -- 所属用户
+    symbol TEXT NOT NULL,                -- 交易品种符号
+    order_id INTEGER NOT NULL,           -- 加仓订单 ID
+    price TEXT NOT NULL,                 -- 本次成交均价
+    quantity TEXT NOT NULL,              -- 本次成交数量
+    entry_price TEXT NOT NULL,           -- 加仓后的加权平均开仓价
+    total_quantity TEXT NOT NULL,        -- 加仓后的总数量
+    reanchor TEXT NOT NULL,              -- 止损重新锚定方式
+    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+);
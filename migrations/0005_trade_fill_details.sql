@@ -0,0 +1,6 @@
+-- 按成交明细记录已实现盈亏、手续费和滑点
+ALTER TABLE trades ADD COLUMN trigger_price TEXT NOT NULL DEFAULT '0'; -- 平仓触发价
+ALTER TABLE trades ADD COLUMN realized_pnl TEXT; -- 交易所返回的已实现盈亏（未扣手续费），成交明细缺失时为空
+ALTER TABLE trades ADD COLUMN commission TEXT; -- 平仓手续费加按数量分摊的开仓手续费
+ALTER TABLE trades ADD COLUMN commission_asset TEXT; -- 手续费计价资产
+ALTER TABLE trades ADD COLUMN slippage TEXT; -- 滑点成本：成交价劣于触发价时为正
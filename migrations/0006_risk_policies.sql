@@ -0,0 +1,9 @@
+-- 平仓记录按用户统计当日亏损
+ALTER TABLE trades ADD COLUMN owner_id TEXT NOT NULL DEFAULT ''; -- 所属用户
+
+CREATE TABLE IF NOT EXISTS risk_policies (
+    owner_id INTEGER PRIMARY KEY,        -- 所属用户，每个用户一条
+    policy TEXT NOT NULL,                -- 风控策略 JSON，字段为空表示不限制
+    kill_switch INTEGER NOT NULL DEFAULT 0, -- 熔断开关，开启后禁止开仓和加仓
+    updated_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+);
@@ -0,0 +1,25 @@
+-- 模拟盘账户表
+CREATE TABLE IF NOT EXISTS paper_accounts (
+    owner_id INTEGER PRIMARY KEY,        -- 所属用户，每个用户一条
+    enabled INTEGER NOT NULL DEFAULT 0,  -- 开启后未指定 paper 的开仓请求默认走模拟盘
+    initial_balance TEXT NOT NULL,       -- 初始虚拟余额（USDT）
+    fee_rate TEXT NOT NULL,              -- 模拟成交的 taker 费率
+    updated_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+);
+
+-- 模拟盘平仓记录表，与实盘 trades 表分开存放
+CREATE TABLE IF NOT EXISTS paper_trades (
+    id INTEGER PRIMARY KEY AUTOINCREMENT, -- 自增主键
+    trade_id INTEGER NOT NULL,           -- 内存中的交易 ID，分批平仓时多条记录共享
+    owner_id TEXT NOT NULL,              -- 所属用户
+    symbol TEXT NOT NULL,                -- 交易品种符号
+    entry_price TEXT NOT NULL,           -- 入场价格
+    close_price TEXT NOT NULL,           -- 模拟成交价
+    direction TEXT NOT NULL,             -- 交易方向 ('Long' or 'Short')
+    quantity TEXT NOT NULL,              -- 本次平仓数量
+    leverage TEXT NOT NULL,              -- 杠杆倍
+    close_reason TEXT NOT NULL,          -- 平仓原因
+    realized_pnl TEXT NOT NULL,          -- 已实现盈亏（未扣手续费）
+    commission TEXT NOT NULL,            -- 开平仓手续费
+    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+);
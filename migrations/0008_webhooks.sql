@@ -0,0 +1,17 @@
+-- Webhook 密钥表
+CREATE TABLE IF NOT EXISTS webhook_secrets (
+    owner_id INTEGER PRIMARY KEY,        -- 所属用户，每个用户一条
+    secret_hash TEXT NOT NULL,           -- 密钥的 SHA-256，明文只在生成时返回一次
+    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+);
+
+-- Webhook 信号记录表，同一用户的 signal_id 只处理一次
+CREATE TABLE IF NOT EXISTS webhook_signals (
+    id INTEGER PRIMARY KEY AUTOINCREMENT, -- 自增主键
+    owner_id INTEGER NOT NULL,           -- 所属用户
+    signal_id TEXT NOT NULL,             -- 信号 ID，未提供时为请求体的 SHA-256
+    action TEXT NOT NULL,                -- 信号类型 ('Open', 'Close', 'Flatten')
+    status INTEGER,                      -- 处理结果的 HTTP 状态码，处理中为空
+    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+    UNIQUE(owner_id, signal_id)
+);
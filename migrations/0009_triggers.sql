@@ -0,0 +1,16 @@
+-- 价格触发器表
+CREATE TABLE IF NOT EXISTS triggers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT, -- 自增主键
+    owner_id TEXT NOT NULL,              -- 所属用户
+    symbol TEXT NOT NULL,                -- 交易品种符号
+    source TEXT NOT NULL,                -- 比较价格 ('Ask', 'Bid', 'Mid', 'Last', 'Mark')
+    condition TEXT NOT NULL,             -- 触发条件（JSON）
+    action TEXT NOT NULL,                -- 触发动作（JSON）：开仓模板或通知
+    reference_price TEXT NOT NULL,       -- 创建时的价格，百分比条件以此为基准
+    status TEXT NOT NULL DEFAULT 'Armed', -- 状态 ('Armed', 'Fired', 'Failed', 'Expired', 'Cancelled')
+    expires_at INTEGER,                  -- 过期时间，NULL 表示不过期
+    fired_price TEXT,                    -- 触发价格
+    fired_at INTEGER,                    -- 触发时间
+    result TEXT,                         -- 开仓结果或错误信息
+    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+);
@@ -0,0 +1,22 @@
+-- 网格机器人表，网格状态以 JSON 保存，重启后继续轮询挂单
+CREATE TABLE IF NOT EXISTS grids (
+    id INTEGER PRIMARY KEY AUTOINCREMENT, -- 自增主键
+    owner_id TEXT NOT NULL,              -- 所属用户
+    symbol TEXT NOT NULL,                -- 交易品种符号
+    direction TEXT NOT NULL,             -- 网格方向 ('Long', 'Short')
+    lower_price TEXT NOT NULL,           -- 区间下沿
+    upper_price TEXT NOT NULL,           -- 区间上沿
+    grid_count INTEGER NOT NULL,         -- 网格数量
+    investment TEXT NOT NULL,            -- 投入保证金
+    leverage TEXT NOT NULL,              -- 杠杆倍数
+    quantity TEXT NOT NULL,              -- 每格下单数量
+    fee_rate TEXT NOT NULL,              -- 估算手续费使用的费率
+    levels TEXT NOT NULL,                -- 各网格状态（JSON）
+    status TEXT NOT NULL DEFAULT 'Running', -- 状态 ('Running', 'Paused', 'Stopped')
+    stop_reason TEXT,                    -- 停止原因
+    realized_profit TEXT NOT NULL DEFAULT '0', -- 已实现利润（已扣手续费）
+    fees TEXT NOT NULL DEFAULT '0',      -- 累计手续费
+    round_trips INTEGER NOT NULL DEFAULT 0, -- 完成的买卖次数
+    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+    updated_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+);
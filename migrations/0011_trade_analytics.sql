@@ -0,0 +1,3 @@
+-- 统计持仓时长和按阶梯预设筛选
+ALTER TABLE trades ADD COLUMN opened_at INTEGER; -- 开仓成交时间，用于统计持仓时长
+ALTER TABLE trades ADD COLUMN adjustment_id INTEGER; -- 使用的阶梯预设，非阶梯策略为空
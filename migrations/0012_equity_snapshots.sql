@@ -0,0 +1,13 @@
+-- 账户权益快照表，定时任务按用户写入，用于绘制权益曲线
+CREATE TABLE IF NOT EXISTS equity_snapshots (
+    id INTEGER PRIMARY KEY AUTOINCREMENT, -- 自增主键
+    owner_id TEXT NOT NULL,              -- 所属用户
+    wallet_balance TEXT NOT NULL,        -- USDT 钱包余额，不含未实现盈亏
+    unrealized_pnl TEXT NOT NULL,        -- 持仓未实现盈亏合计
+    margin_used TEXT NOT NULL,           -- 持仓和挂单占用的初始保证金
+    open_trades INTEGER NOT NULL,        -- 非零持仓数量
+    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_equity_snapshots_owner_time
+    ON equity_snapshots (owner_id, created_at);
@@ -0,0 +1,7 @@
+-- 启用的交易对，启动时订阅其中已启用的交易对，运行时通过管理接口增删
+CREATE TABLE IF NOT EXISTS symbols (
+    symbol TEXT PRIMARY KEY,             -- 交易对，小写，如 'adausdt'
+    enabled INTEGER NOT NULL DEFAULT 1,  -- 是否启用
+    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+    updated_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+);
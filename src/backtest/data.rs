@@ -0,0 +1,105 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::{binance::market::Kline, trade::price::PriceBook};
+
+// 历史行情，时间为毫秒
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub enum MarketTick {
+    BookTicker {
+        time: u64,
+        bid: Decimal,
+        ask: Decimal,
+    },
+    AggTrade {
+        time: u64,
+        price: Decimal,
+    },
+    Kline {
+        time: u64, // 开盘时间
+        open: Decimal,
+        high: Decimal,
+        low: Decimal,
+        close: Decimal,
+    },
+}
+
+impl MarketTick {
+    pub fn time(&self) -> u64 {
+        match self {
+            MarketTick::BookTicker { time, .. }
+            | MarketTick::AggTrade { time, .. }
+            | MarketTick::Kline { time, .. } => *time,
+        }
+    }
+
+    // 展开为依次生效的盘口快照。成交价和 K 线没有买卖盘，所有价格来源取同一价格；
+    // 盘口数据的最新价和标记价格取中间价。K 线按阳线 开-低-高-收、阴线 开-高-低-收 的路径展开
+    pub fn quotes(&self) -> Vec<PriceBook> {
+        let flat = |price: Decimal| PriceBook {
+            ask: price,
+            bid: price,
+            last: price,
+            mark: price,
+        };
+        match *self {
+            MarketTick::BookTicker { bid, ask, .. } => {
+                let mid = (bid + ask) / Decimal::TWO;
+                vec![PriceBook {
+                    ask,
+                    bid,
+                    last: mid,
+                    mark: mid,
+                }]
+            }
+            MarketTick::AggTrade { price, .. } => vec![flat(price)],
+            MarketTick::Kline {
+                open,
+                high,
+                low,
+                close,
+                ..
+            } => {
+                let path = if close >= open {
+                    [open, low, high, close]
+                } else {
+                    [open, high, low, close]
+                };
+                path.into_iter().map(flat).collect()
+            }
+        }
+    }
+
+    // 吊灯止损计算 ATR 用的 K 线
+    pub fn as_kline(&self) -> Option<Kline> {
+        match *self {
+            MarketTick::Kline {
+                time,
+                open,
+                high,
+                low,
+                close,
+            } => Some(Kline {
+                open_time: time,
+                open,
+                high,
+                low,
+                close,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl From<&Kline> for MarketTick {
+    fn from(kline: &Kline) -> Self {
+        MarketTick::Kline {
+            time: kline.open_time,
+            open: kline.open,
+            high: kline.high,
+            low: kline.low,
+            close: kline.close,
+        }
+    }
+}
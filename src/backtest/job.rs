@@ -0,0 +1,74 @@
+use std::{
+    collections::BTreeMap,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use serde::Serialize;
+use tokio::sync::Mutex;
+
+use super::BacktestReport;
+use crate::utils::unix_timestamp;
+
+// 内存中最多保留的任务数，超出后丢弃最早的任务
+const MAX_JOBS: usize = 100;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status")]
+pub enum JobState {
+    Running,
+    Done { report: BacktestReport },
+    Failed { error: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BacktestJob {
+    pub id: u64,
+    #[serde(skip)]
+    pub owner_id: String,
+    pub created_at: u32,
+    #[serde(flatten)]
+    pub state: JobState,
+}
+
+// HTTP 回测任务，只保存在内存中，重启后丢失
+#[derive(Debug, Default)]
+pub struct BacktestJobs {
+    next_id: AtomicU64,
+    jobs: Mutex<BTreeMap<u64, BacktestJob>>,
+}
+
+impl BacktestJobs {
+    pub async fn create(&self, owner_id: &str) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let mut jobs = self.jobs.lock().await;
+        jobs.insert(
+            id,
+            BacktestJob {
+                id,
+                owner_id: owner_id.to_string(),
+                created_at: unix_timestamp(),
+                state: JobState::Running,
+            },
+        );
+        while jobs.len() > MAX_JOBS {
+            jobs.pop_first();
+        }
+        id
+    }
+
+    pub async fn finish(&self, id: u64, state: JobState) {
+        if let Some(job) = self.jobs.lock().await.get_mut(&id) {
+            job.state = state;
+        }
+    }
+
+    // 只返回属于该用户的任务
+    pub async fn get(&self, id: u64, owner_id: &str) -> Option<BacktestJob> {
+        self.jobs
+            .lock()
+            .await
+            .get(&id)
+            .filter(|job| job.owner_id == owner_id)
+            .cloned()
+    }
+}
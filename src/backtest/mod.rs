@@ -0,0 +1,483 @@
+pub mod data;
+pub mod job;
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::Receiver;
+
+use crate::{
+    binance::{leverage::SymbolFilter, market::Kline},
+    models::trade_model::TakeProfitRequest,
+    trade::{
+        build_take_profits,
+        event::{EventBus, TradeEvent, TradeEventKind},
+        exchange::{Exchange, SimExchange},
+        price::{PriceBook, PriceSource},
+        record::slippage_cost,
+        strategy::{build_stop_strategy, StopStrategyConfig},
+        Adjustment, CloseReason, Trade, TradeDirection, TradeParams,
+    },
+    utils::round_to_step,
+};
+use data::MarketTick;
+
+// 回测中交易的所有者
+const BACKTEST_OWNER: &str = "backtest";
+
+// 开仓时机，同一时间最多持有一笔交易
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub enum EntrySchedule {
+    // 第一个行情开仓，平仓后结束
+    #[default]
+    Once,
+    // 平仓后在下一个行情重新开仓
+    Reenter,
+    // 在指定时间（毫秒）之后的第一个行情开仓，已有持仓时跳过
+    At {
+        times: Vec<u64>,
+    },
+}
+
+// 回测参数，与开仓请求一致；手续费和滑点由模拟撮合收取
+#[derive(Debug, Clone)]
+pub struct BacktestConfig {
+    pub symbol: String,
+    pub direction: TradeDirection,
+    pub leverage: Decimal,
+    pub margin: Decimal,
+    pub stop_loss_percent: Decimal,
+    pub stop_strategy: StopStrategyConfig,
+    pub adjustments: Vec<Adjustment>, // 阶梯策略使用
+    pub take_profits: Vec<TakeProfitRequest>,
+    pub price_source: PriceSource,
+    pub fee_rate: Decimal,
+    pub slippage: Decimal,
+    pub entry: EntrySchedule,
+    pub filter: SymbolFilter,
+}
+
+impl BacktestConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.leverage < Decimal::ONE
+            || self.margin <= Decimal::ZERO
+            || self.stop_loss_percent <= Decimal::ZERO
+        {
+            return Err(
+                "leverage must be >= 1, margin and stop_loss_percent must be positive".to_string(),
+            );
+        }
+        if self.fee_rate < Decimal::ZERO || self.slippage < Decimal::ZERO {
+            return Err("fee_rate and slippage must not be negative".to_string());
+        }
+        if self
+            .take_profits
+            .iter()
+            .any(|tp| tp.fraction <= Decimal::ZERO || tp.fraction > Decimal::ONE)
+        {
+            return Err("take profit fraction must be within (0, 1]".to_string());
+        }
+        // 交易所托管的止损在交易所撮合，无法按本地逻辑回放
+        if matches!(
+            self.stop_strategy,
+            StopStrategyConfig::ExchangeTrailing { .. }
+        ) {
+            return Err("ExchangeTrailing cannot be backtested".to_string());
+        }
+        self.stop_strategy.validate()
+    }
+}
+
+// 单笔回测交易，分批平仓合并为一条
+#[derive(Debug, Clone, Serialize)]
+pub struct BacktestTrade {
+    pub id: usize,
+    pub entry_time: u64,
+    pub entry_price: Decimal, // 开仓成交价，含滑点
+    pub quantity: Decimal,
+    pub exit_time: u64,
+    pub exit_price: Decimal,      // 平仓成交均价
+    pub exit_reason: CloseReason, // 最后一笔平仓的原因
+    pub stop_moves: u32,
+    pub gross_pnl: Decimal,
+    pub fees: Decimal,     // 开平仓手续费
+    pub slippage: Decimal, // 开平仓滑点成本
+    pub net_pnl: Decimal,
+    pub roi: Decimal, // 净盈亏 / 保证金
+    #[serde(skip)]
+    closed_quantity: Decimal,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BacktestSummary {
+    pub trades: usize,
+    pub wins: usize,
+    pub losses: usize,
+    pub win_rate: Decimal,
+    pub gross_pnl: Decimal,
+    pub fees: Decimal,
+    pub net_pnl: Decimal,
+    pub avg_net_pnl: Decimal,
+    pub best: Decimal,
+    pub worst: Decimal,
+    pub profit_factor: Option<Decimal>, // 没有亏损交易时为空
+    pub max_drawdown: Decimal,          // 累计净盈亏从高点的最大回撤
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BacktestReport {
+    pub trades: Vec<BacktestTrade>,
+    pub summary: BacktestSummary,
+}
+
+// 回放历史行情：开仓由模拟撮合成交，之后每个行情经过与实盘相同的 Trade::update_price，
+// 止损触发后按触发价平仓。不读取时钟和网络，同一数据集的结果完全一致
+pub async fn run_backtest(
+    config: &BacktestConfig,
+    ticks: &[MarketTick],
+) -> Result<BacktestReport, String> {
+    config.validate()?;
+    if matches!(config.stop_strategy, StopStrategyConfig::Chandelier { .. })
+        && !ticks.iter().any(|t| t.as_kline().is_some())
+    {
+        return Err("Chandelier requires kline data".to_string());
+    }
+
+    let exchange = SimExchange::new(config.fee_rate, config.slippage);
+    let events = EventBus::new();
+    let mut receiver = events.subscribe();
+    let mut history: Vec<Kline> = Vec::new();
+    let mut entry_times = match &config.entry {
+        EntrySchedule::At { times } => {
+            let mut times = times.clone();
+            times.sort_unstable();
+            times
+        }
+        _ => Vec::new(),
+    }
+    .into_iter()
+    .peekable();
+    let mut entered = false;
+    let mut open: Option<(Trade, BacktestTrade)> = None;
+    let mut trades = Vec::new();
+    let mut last_book = PriceBook::default();
+
+    for tick in ticks {
+        let time = tick.time();
+        for book in tick.quotes() {
+            last_book = book.clone();
+            if open.is_none() {
+                let due = match config.entry {
+                    EntrySchedule::Once => !entered,
+                    EntrySchedule::Reenter => true,
+                    EntrySchedule::At { .. } => {
+                        let mut due = false;
+                        while entry_times.next_if(|t| *t <= time).is_some() {
+                            due = true;
+                        }
+                        due
+                    }
+                };
+                if due {
+                    entered = true;
+                    open = Some(
+                        open_trade(config, &exchange, &book, &history, trades.len() + 1, time)
+                            .await?,
+                    );
+                    continue;
+                }
+            }
+
+            let Some((trade, record)) = open.as_mut() else {
+                continue;
+            };
+            let actions = trade.update_price(&book, &events);
+            for order in actions.take_profits {
+                if let Some(fill) = exchange
+                    .close_position(trade, order.quantity, order.price)
+                    .await
+                {
+                    trade.settle_take_profit(&order, &fill, &events);
+                }
+            }
+            if let Some(price) = actions.stop {
+                // 与实盘 close_with_retry 相同：按触发价市价平掉剩余数量
+                let quantity = trade.remaining_quantity;
+                if let Some(fill) = exchange.close_position(trade, quantity, price).await {
+                    trade.reduce_position(quantity);
+                    events.publish(
+                        trade,
+                        trade.close_event(&fill, quantity, CloseReason::StopLoss),
+                    );
+                }
+            }
+            apply_events(&mut receiver, record, &exchange, config, time);
+            if trade.is_closed() {
+                trades.push(record.clone());
+                open = None;
+            }
+        }
+        if let Some(kline) = tick.as_kline() {
+            // K 线走完后才算收盘，与实盘一样在收盘时更新止损策略
+            if let Some((trade, _)) = open.as_mut() {
+                trade.on_kline_close(&kline, &events);
+            }
+            history.push(kline);
+        }
+    }
+
+    // 数据结束时仍有持仓，按最后价格平仓并记为手动平仓
+    if let Some((mut trade, mut record)) = open {
+        let time = ticks.last().map(|t| t.time()).unwrap_or_default();
+        let quantity = trade.remaining_quantity;
+        let price = last_book
+            .exit_price(&trade.direction)
+            .unwrap_or(trade.entry_price);
+        if let Some(fill) = exchange.close_position(&trade, quantity, price).await {
+            trade.reduce_position(quantity);
+            events.publish(
+                &trade,
+                trade.close_event(&fill, quantity, CloseReason::Manual),
+            );
+        }
+        apply_events(&mut receiver, &mut record, &exchange, config, time);
+        trades.push(record);
+    }
+
+    let summary = summarize(&trades);
+    Ok(BacktestReport { trades, summary })
+}
+
+async fn open_trade(
+    config: &BacktestConfig,
+    exchange: &SimExchange,
+    book: &PriceBook,
+    history: &[Kline],
+    id: usize,
+    time: u64,
+) -> Result<(Trade, BacktestTrade), String> {
+    let quote = book
+        .entry_price(&config.direction)
+        .ok_or("Invalid price in data")?;
+    let quantity = round_to_step(
+        config.margin * config.leverage / quote,
+        config.filter.step_size,
+    );
+    if quantity <= Decimal::ZERO || quantity < config.filter.min_qty {
+        return Err(format!(
+            "Quantity {} below minimum {}",
+            quantity, config.filter.min_qty
+        ));
+    }
+    let fill = exchange.fill_entry(id, &config.direction, quote, quantity);
+
+    let take_profits = build_take_profits(
+        &config.take_profits,
+        &config.direction,
+        config.leverage,
+        fill.price,
+        &config.filter,
+    );
+    let stop_strategy =
+        build_stop_strategy(&config.stop_strategy, config.adjustments.clone(), history);
+    let trade = Trade::new(
+        TradeParams {
+            id,
+            owner_id: BACKTEST_OWNER.to_string(),
+            order_id: fill.order_id,
+            symbol: config.symbol.clone(),
+            entry_price: fill.price,
+            direction: config.direction.clone(),
+            quantity,
+            leverage: config.leverage,
+            stop_loss_percent: config.stop_loss_percent,
+            stop_strategy,
+            take_profits,
+            price_source: config.price_source,
+            api_key: String::new(),
+            api_secret: String::new(),
+        },
+        &config.filter,
+    )
+    .await;
+
+    let record = BacktestTrade {
+        id,
+        entry_time: time,
+        entry_price: fill.price,
+        quantity,
+        exit_time: time,
+        exit_price: Decimal::ZERO,
+        exit_reason: CloseReason::StopLoss,
+        stop_moves: 0,
+        gross_pnl: Decimal::ZERO,
+        fees: fill.fee,
+        slippage: (fill.price - quote).abs() * quantity,
+        net_pnl: -fill.fee,
+        roi: Decimal::ZERO,
+        closed_quantity: Decimal::ZERO,
+    };
+    Ok((trade, record))
+}
+
+// 按交易事件累计回测记录：止损移动次数以及每笔平仓的盈亏、手续费和滑点
+fn apply_events(
+    receiver: &mut Receiver<TradeEvent>,
+    record: &mut BacktestTrade,
+    exchange: &SimExchange,
+    config: &BacktestConfig,
+    time: u64,
+) {
+    while let Ok(event) = receiver.try_recv() {
+        let (order_id, price, trigger_price, quantity, reason) = match event.kind {
+            TradeEventKind::StopMoved { .. } => {
+                record.stop_moves += 1;
+                continue;
+            }
+            TradeEventKind::PartialClose {
+                order_id,
+                price,
+                trigger_price,
+                quantity,
+                reason,
+                ..
+            }
+            | TradeEventKind::Closed {
+                order_id,
+                price,
+                trigger_price,
+                quantity,
+                reason,
+            } => (order_id, price, trigger_price, quantity, reason),
+            _ => continue,
+        };
+
+        let closed = record.closed_quantity + quantity;
+        record.exit_price =
+            (record.exit_price * record.closed_quantity + price * quantity) / closed;
+        record.closed_quantity = closed;
+        record.exit_time = time;
+        record.exit_reason = reason;
+        let pnl = match config.direction {
+            TradeDirection::Long => (price - record.entry_price) * quantity,
+            TradeDirection::Short => (record.entry_price - price) * quantity,
+        };
+        let fee = exchange
+            .fill_by_order(order_id)
+            .map(|f| f.fee)
+            .unwrap_or_default();
+        record.gross_pnl += pnl;
+        record.fees += fee;
+        record.slippage += slippage_cost(&config.direction, trigger_price, price, quantity);
+        record.net_pnl = record.gross_pnl - record.fees;
+        record.roi = record.net_pnl / config.margin;
+    }
+}
+
+pub fn summarize(trades: &[BacktestTrade]) -> BacktestSummary {
+    let mut summary = BacktestSummary {
+        trades: trades.len(),
+        ..Default::default()
+    };
+    let mut gross_profit = Decimal::ZERO;
+    let mut gross_loss = Decimal::ZERO;
+    let mut equity = Decimal::ZERO;
+    let mut peak = Decimal::ZERO;
+    for (i, t) in trades.iter().enumerate() {
+        if t.net_pnl > Decimal::ZERO {
+            summary.wins += 1;
+            gross_profit += t.net_pnl;
+        } else {
+            summary.losses += 1;
+            gross_loss -= t.net_pnl;
+        }
+        summary.gross_pnl += t.gross_pnl;
+        summary.fees += t.fees;
+        summary.net_pnl += t.net_pnl;
+        if i == 0 || t.net_pnl > summary.best {
+            summary.best = t.net_pnl;
+        }
+        if i == 0 || t.net_pnl < summary.worst {
+            summary.worst = t.net_pnl;
+        }
+        equity += t.net_pnl;
+        peak = peak.max(equity);
+        summary.max_drawdown = summary.max_drawdown.max(peak - equity);
+    }
+    if !trades.is_empty() {
+        let count = Decimal::from(trades.len());
+        summary.win_rate = Decimal::from(summary.wins) / count;
+        summary.avg_net_pnl = summary.net_pnl / count;
+    }
+    summary.profit_factor = (gross_loss > Decimal::ZERO).then(|| gross_profit / gross_loss);
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn config(entry: EntrySchedule) -> BacktestConfig {
+        BacktestConfig {
+            symbol: "filusdt".to_string(),
+            direction: TradeDirection::Long,
+            leverage: dec!(10),
+            margin: dec!(10),
+            stop_loss_percent: dec!(0.5),
+            stop_strategy: StopStrategyConfig::FixedTrail { percent: dec!(0.5) },
+            adjustments: Vec::new(),
+            take_profits: Vec::new(),
+            price_source: PriceSource::Last,
+            fee_rate: dec!(0.001),
+            slippage: Decimal::ZERO,
+            entry,
+            filter: SymbolFilter {
+                tick_size: dec!(0.01),
+                step_size: dec!(0.001),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn ticks(prices: &[Decimal]) -> Vec<MarketTick> {
+        prices
+            .iter()
+            .enumerate()
+            .map(|(i, &price)| MarketTick::AggTrade {
+                time: i as u64 * 1000,
+                price,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_backtest_replay() {
+        // 100 开仓，涨到 120 后止损跟随到 114，回落到 100 时止损
+        let data = ticks(&[dec!(100), dec!(110), dec!(120), dec!(100), dec!(105)]);
+        let report = run_backtest(&config(EntrySchedule::Once), &data)
+            .await
+            .unwrap();
+        assert_eq!(report.trades.len(), 1);
+        let trade = &report.trades[0];
+        assert_eq!(trade.exit_reason, CloseReason::StopLoss);
+        assert_eq!(trade.exit_time, 3000);
+        assert_eq!(trade.stop_moves, 2);
+        assert_eq!(trade.gross_pnl, Decimal::ZERO);
+        assert_eq!(trade.fees, dec!(0.2));
+        assert_eq!(report.summary.net_pnl, dec!(-0.2));
+
+        // 平仓后重新开仓，数据结束时按最后价格平仓
+        let report = run_backtest(&config(EntrySchedule::Reenter), &data)
+            .await
+            .unwrap();
+        assert_eq!(report.trades.len(), 2);
+        assert_eq!(report.trades[1].exit_reason, CloseReason::Manual);
+
+        // 同一数据集结果一致
+        let again = run_backtest(&config(EntrySchedule::Reenter), &data)
+            .await
+            .unwrap();
+        assert_eq!(format!("{:?}", report), format!("{:?}", again));
+    }
+}
@@ -109,10 +109,69 @@ pub async fn get_risk(key: &str, secret: &str) -> Result<Vec<Position>> {
     super::request(&url, Method::GET, key).await
 }
 
+// 账户权益，只解析用到的字段
+#[derive(Debug, Deserialize)]
+pub struct AccountBalance {
+    #[serde(rename = "totalMarginBalance")]
+    pub total_margin_balance: Decimal, // 保证金余额（含未实现盈亏），即账户权益
+
+    #[serde(rename = "availableBalance")]
+    pub available_balance: Decimal, // 可用于开仓的余额
+}
+
+pub async fn get_account_balance(key: &str, secret: &str) -> Result<AccountBalance> {
+    let endpoint = format!("{}/fapi/v3/account", super::BASE_URL);
+
+    let timestamp = super::create_timestamp();
+    let query_string = format!("timestamp={}", timestamp);
+    let signature = super::create_signature(secret, &query_string);
+    let url = format!("{}?{}&signature={}", endpoint, query_string, signature);
+
+    super::request(&url, Method::GET, key).await
+}
+
+// 单个资产的合约钱包余额，只解析用到的字段
+#[derive(Debug, Deserialize)]
+pub struct AssetBalance {
+    pub asset: String, // 资产名称
+
+    pub balance: Decimal, // 钱包余额，不含未实现盈亏
+}
+
+pub async fn get_balance(key: &str, secret: &str) -> Result<Vec<AssetBalance>> {
+    let endpoint = format!("{}/fapi/v3/balance", super::BASE_URL);
+
+    let timestamp = super::create_timestamp();
+    let query_string = format!("timestamp={}", timestamp);
+    let signature = super::create_signature(secret, &query_string);
+    let url = format!("{}?{}&signature={}", endpoint, query_string, signature);
+
+    super::request(&url, Method::GET, key).await
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CommissionRate {
+    #[serde(rename = "takerCommissionRate")]
+    pub taker_commission_rate: Decimal,
+}
+
+// 查询用户在该交易对的手续费率
+pub async fn get_commission_rate(symbol: &str, key: &str, secret: &str) -> Result<CommissionRate> {
+    let endpoint = format!("{}/fapi/v1/commissionRate", super::BASE_URL);
+
+    let timestamp = super::create_timestamp();
+    let query_string = format!("symbol={}&timestamp={}", symbol.to_uppercase(), timestamp);
+    let signature = super::create_signature(secret, &query_string);
+    let url = format!("{}?{}&signature={}", endpoint, query_string, signature);
+
+    super::request(&url, Method::GET, key).await
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct BiannceOrder {
     pub avgPrice: String,
     pub executedQty: String,
+    pub status: String,
 }
 
 pub async fn get_order_api(
@@ -1,7 +1,8 @@
 use std::collections::HashMap;
 
-use crate::error::{Error, Result};
+use crate::error::Result;
 use reqwest::Method;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -14,6 +15,8 @@ pub struct LeverageResponse {
 pub async fn change_leverage(
     symbol: &str,  // 交易对符号，例如 "BTCUSDT"
     leverage: u32, // 杠杆倍数，范围 1 到 125
+    key: &str,
+    secret: &str,
 ) -> Result<LeverageResponse> {
     let endpoint = format!("{}/fapi/v1/leverage", super::BASE_URL);
 
@@ -23,19 +26,104 @@ pub async fn change_leverage(
     // 构建请求参数
     let query_string = format!(
         "symbol={}&leverage={}&timestamp={}",
-        symbol, leverage, timestamp
+        symbol.to_uppercase(),
+        leverage,
+        timestamp
     );
 
     // 生成签名
-    let signature = super::create_signature(&super::API_SECRET, &query_string);
+    let signature = super::create_signature(secret, &query_string);
     let url = format!("{}?{}&signature={}", endpoint, query_string, signature);
 
     // 使用 post_request 发送请求
-    let response = super::request::<LeverageResponse>(&url, Method::POST, &super::API_KEY).await?;
+    let response = super::request::<LeverageResponse>(&url, Method::POST, key).await?;
 
     Ok(response)
 }
 
+#[derive(Deserialize, Debug)]
+pub struct SymbolBrackets {
+    pub symbol: String,
+    pub brackets: Vec<LeverageBracket>,
+}
+
+// 杠杆分层：名义价值越大，允许的最高杠杆越低
+#[derive(Deserialize, Debug)]
+pub struct LeverageBracket {
+    #[serde(rename = "initialLeverage")]
+    pub initial_leverage: u32, // 该层允许的最高杠杆
+    #[serde(rename = "notionalCap")]
+    pub notional_cap: Decimal, // 该层名义价值上限
+    #[serde(rename = "notionalFloor", default)]
+    pub notional_floor: Decimal, // 该层名义价值下限
+    #[serde(rename = "maintMarginRatio", default)]
+    pub maint_margin_ratio: Decimal, // 维持保证金率
+    #[serde(default)]
+    pub cum: Decimal, // 维持保证金速算额
+}
+
+pub async fn get_leverage_brackets(
+    symbol: &str,
+    key: &str,
+    secret: &str,
+) -> Result<Vec<SymbolBrackets>> {
+    let endpoint = format!("{}/fapi/v1/leverageBracket", super::BASE_URL);
+
+    let timestamp = super::create_timestamp();
+    let query_string = format!("symbol={}&timestamp={}", symbol.to_uppercase(), timestamp);
+    let signature = super::create_signature(secret, &query_string);
+    let url = format!("{}?{}&signature={}", endpoint, query_string, signature);
+
+    super::request(&url, Method::GET, key).await
+}
+
+// 给定杠杆下允许的最大名义价值，杠杆超过所有分层时返回 None
+pub fn max_notional_for_leverage(brackets: &[LeverageBracket], leverage: u32) -> Option<Decimal> {
+    brackets
+        .iter()
+        .filter(|b| b.initial_leverage >= leverage)
+        .map(|b| b.notional_cap)
+        .max()
+}
+
+// 名义价值所在的分层，超过所有分层时取最高一层
+pub fn bracket_for_notional(
+    brackets: &[LeverageBracket],
+    notional: Decimal,
+) -> Option<&LeverageBracket> {
+    brackets
+        .iter()
+        .find(|b| notional >= b.notional_floor && notional < b.notional_cap)
+        .or_else(|| brackets.iter().max_by_key(|b| b.notional_cap))
+}
+
+// 保证金模式
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum MarginType {
+    #[serde(rename = "ISOLATED")]
+    Isolated,
+    #[serde(rename = "CROSSED")]
+    Cross,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SymbolConfig {
+    pub symbol: String,
+    #[serde(rename = "marginType")]
+    pub margin_type: MarginType,
+}
+
+pub async fn get_symbol_config(symbol: &str, key: &str, secret: &str) -> Result<Vec<SymbolConfig>> {
+    let endpoint = format!("{}/fapi/v1/symbolConfig", super::BASE_URL);
+
+    let timestamp = super::create_timestamp();
+    let query_string = format!("symbol={}&timestamp={}", symbol.to_uppercase(), timestamp);
+    let signature = super::create_signature(secret, &query_string);
+    let url = format!("{}?{}&signature={}", endpoint, query_string, signature);
+
+    super::request(&url, Method::GET, key).await
+}
+
 #[derive(Deserialize)]
 pub struct ExchangeInfo {
     pub symbols: Vec<SymbolInfo>,
@@ -45,16 +133,117 @@ pub struct ExchangeInfo {
 pub struct SymbolInfo {
     pub symbol: String,
     pub quantityPrecision: u8,
+    #[serde(default)]
+    pub filters: Vec<ExchangeFilter>,
+    #[serde(default)]
+    pub status: String, // 'TRADING', 'SETTLING', 'PENDING_TRADING', 'CLOSE' 等
+    #[serde(rename = "contractType", default)]
+    pub contract_type: String, // 'PERPETUAL' 或交割合约类型
+    #[serde(rename = "quoteAsset", default)]
+    pub quote_asset: String,
+    #[serde(rename = "deliveryDate", default)]
+    pub delivery_date: u64, // 交割（下架）时间，毫秒
+}
+
+// exchangeInfo 中的交易规则，只解析用到的字段
+#[derive(Deserialize, Debug)]
+#[serde(tag = "filterType")]
+pub enum ExchangeFilter {
+    #[serde(rename = "PRICE_FILTER")]
+    Price {
+        #[serde(rename = "tickSize")]
+        tick_size: Decimal,
+    },
+    #[serde(rename = "LOT_SIZE")]
+    LotSize {
+        #[serde(rename = "stepSize")]
+        step_size: Decimal,
+        #[serde(rename = "minQty")]
+        min_qty: Decimal,
+    },
+    #[serde(rename = "MARKET_LOT_SIZE")]
+    MarketLotSize {
+        #[serde(rename = "maxQty")]
+        max_qty: Decimal,
+    },
+    #[serde(rename = "MIN_NOTIONAL")]
+    MinNotional { notional: Decimal },
+    #[serde(other)]
+    Other,
+}
+
+// 未安排交割的永续合约 deliveryDate 固定为 2100-12-25
+pub const PERPETUAL_DELIVERY_DATE: u64 = 4_133_404_800_000;
+
+// 交易对的精度与下单限制
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SymbolFilter {
+    pub quantity_precision: u8,
+    pub tick_size: Decimal,      // 价格最小变动单位
+    pub step_size: Decimal,      // 数量最小变动单位
+    pub min_qty: Decimal,        // 最小下单数量
+    pub min_notional: Decimal,   // 最小名义价值
+    pub max_market_qty: Decimal, // 市价单最大数量，0 表示未知
+    pub status: String,          // 交易所的交易状态
+    pub contract_type: String,
+    pub delivery_date: u64, // 毫秒
 }
 
-pub async fn get_quantity_precision(symbols: &Vec<String>) -> Result<HashMap<String, u8>> {
+impl SymbolFilter {
+    pub fn from_info(info: &SymbolInfo) -> Self {
+        // 缺少过滤器时退回到 quantityPrecision 推算的步长
+        let mut filter = SymbolFilter {
+            quantity_precision: info.quantityPrecision,
+            tick_size: Decimal::ZERO,
+            step_size: Decimal::new(1, info.quantityPrecision as u32),
+            min_qty: Decimal::ZERO,
+            min_notional: Decimal::ZERO,
+            max_market_qty: Decimal::ZERO,
+            status: info.status.clone(),
+            contract_type: info.contract_type.clone(),
+            delivery_date: info.delivery_date,
+        };
+        for f in &info.filters {
+            match f {
+                ExchangeFilter::Price { tick_size } => filter.tick_size = *tick_size,
+                ExchangeFilter::LotSize { step_size, min_qty } => {
+                    filter.step_size = *step_size;
+                    filter.min_qty = *min_qty;
+                }
+                ExchangeFilter::MarketLotSize { max_qty } => filter.max_market_qty = *max_qty,
+                ExchangeFilter::MinNotional { notional } => filter.min_notional = *notional,
+                ExchangeFilter::Other => {}
+            }
+        }
+        filter
+    }
+
+    // 不能开新仓的原因：暂停交易、结算、非永续合约或已安排交割下架
+    pub fn halt_reason(&self) -> Option<String> {
+        if self.status != "TRADING" {
+            return Some(format!("status {}", self.status));
+        }
+        if self.contract_type != "PERPETUAL" {
+            return Some(format!("contract type {}", self.contract_type));
+        }
+        if self.delivery_date < PERPETUAL_DELIVERY_DATE {
+            return Some(format!("delivery scheduled at {}", self.delivery_date));
+        }
+        None
+    }
+}
+
+pub async fn get_exchange_info() -> Result<ExchangeInfo> {
     let endpoint = format!("{}/fapi/v1/exchangeInfo", super::BASE_URL);
+    super::request::<ExchangeInfo>(&endpoint, Method::GET, &super::API_KEY).await
+}
 
-    // 发起GET请求
-    let response = super::request::<ExchangeInfo>(&endpoint, Method::GET, &super::API_KEY).await?;
+// 交易所找不到的交易对跳过，不影响其他交易对
+pub async fn get_symbol_filters(symbols: &[String]) -> Result<HashMap<String, SymbolFilter>> {
+    let response = get_exchange_info().await?;
 
     // 构建结果 HashMap
-    let mut precision_map = HashMap::new();
+    let mut filter_map = HashMap::new();
 
     for symbol in symbols {
         let symbol_uppercase = symbol.to_uppercase();
@@ -63,11 +252,11 @@ pub async fn get_quantity_precision(symbols: &Vec<String>) -> Result<HashMap<Str
             .iter()
             .find(|s| s.symbol == *symbol_uppercase)
         {
-            precision_map.insert(symbol.to_string(), symbol_info.quantityPrecision);
+            filter_map.insert(symbol.to_string(), SymbolFilter::from_info(symbol_info));
         } else {
-            return Err(Error::SystemError(format!("未找到交易对：{}", symbol)));
+            eprintln!("未找到交易对：{}", symbol);
         }
     }
 
-    Ok(precision_map)
+    Ok(filter_map)
 }
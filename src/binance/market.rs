@@ -0,0 +1,93 @@
+use crate::error::{Error, Result};
+use reqwest::Method;
+use rust_decimal::Decimal;
+use serde_json::Value;
+
+// K 线数据
+#[derive(Debug, Clone)]
+pub struct Kline {
+    pub open_time: u64,
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
+}
+
+pub async fn get_klines(symbol: &str, interval: &str, limit: u32) -> Result<Vec<Kline>> {
+    let endpoint = format!("{}/fapi/v1/klines", super::BASE_URL);
+
+    // 公共接口，无需签名
+    let url = format!(
+        "{}?symbol={}&interval={}&limit={}",
+        endpoint,
+        symbol.to_uppercase(),
+        interval,
+        limit
+    );
+
+    // 返回格式为二维数组: [openTime, open, high, low, close, volume, closeTime, ...]
+    fetch_klines(&url).await
+}
+
+// 单次请求的最大 K 线数量
+const KLINE_PAGE_LIMIT: u32 = 1500;
+
+// 按开盘时间区间 [start_time, end_time] 分页拉取历史 K 线，时间为毫秒
+pub async fn get_klines_between(
+    symbol: &str,
+    interval: &str,
+    start_time: u64,
+    end_time: u64,
+) -> Result<Vec<Kline>> {
+    let endpoint = format!("{}/fapi/v1/klines", super::BASE_URL);
+
+    let mut klines: Vec<Kline> = Vec::new();
+    let mut from = start_time;
+    while from <= end_time {
+        let url = format!(
+            "{}?symbol={}&interval={}&startTime={}&endTime={}&limit={}",
+            endpoint,
+            symbol.to_uppercase(),
+            interval,
+            from,
+            end_time,
+            KLINE_PAGE_LIMIT
+        );
+        let page = fetch_klines(&url).await?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        from = last.open_time + 1;
+        let full = page.len() as u32 == KLINE_PAGE_LIMIT;
+        klines.extend(page);
+        if !full {
+            break;
+        }
+    }
+    Ok(klines)
+}
+
+async fn fetch_klines(url: &str) -> Result<Vec<Kline>> {
+    let rows = super::request::<Vec<Vec<Value>>>(url, Method::GET, &super::API_KEY).await?;
+
+    let mut klines = Vec::with_capacity(rows.len());
+    for row in &rows {
+        match parse_kline(row) {
+            Some(kline) => klines.push(kline),
+            None => return Err(Error::SystemError(format!("invalid kline: {:?}", row))),
+        }
+    }
+    Ok(klines)
+}
+
+fn parse_kline(row: &[Value]) -> Option<Kline> {
+    let field = |i: usize| row.get(i)?.as_str()?.parse::<Decimal>().ok();
+
+    Some(Kline {
+        open_time: row.first()?.as_u64()?,
+        open: field(1)?,
+        high: field(2)?,
+        low: field(3)?,
+        close: field(4)?,
+    })
+}
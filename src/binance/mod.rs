@@ -1,6 +1,7 @@
 // mod account;
 pub mod account;
 pub mod leverage;
+pub mod market;
 pub mod order;
 pub mod record_api;
 
@@ -24,6 +24,15 @@ pub struct OrderResponse {
     // 添加其他需要的字段
 }
 
+// 下单的可选参数
+#[derive(Default)]
+pub struct OrderOptions<'a> {
+    pub time_in_force: Option<&'a str>,    // 有效方式，限价单默认 GTC
+    pub callback_rate: Option<&'a str>,    // 跟踪止损回调比例，单位 %
+    pub activation_price: Option<&'a str>, // 跟踪止损激活价格
+    pub working_type: Option<&'a str>,     // 触发价格类型 MARK_PRICE / CONTRACT_PRICE
+}
+
 pub async fn create_order(
     symbol: &str,
     side: &str,          // 买入或卖出： "BUY" 或 "SELL"
@@ -34,6 +43,34 @@ pub async fn create_order(
     stop_price: Option<&str>,
     key: &str,
     secret: &str,
+) -> Result<OrderResponse> {
+    create_order_with_options(
+        symbol,
+        side,
+        position_side,
+        order_type,
+        quantity,
+        price,
+        stop_price,
+        OrderOptions::default(),
+        key,
+        secret,
+    )
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn create_order_with_options(
+    symbol: &str,
+    side: &str,
+    position_side: &str,
+    order_type: &str,
+    quantity: &str,
+    price: Option<&str>,
+    stop_price: Option<&str>,
+    options: OrderOptions<'_>,
+    key: &str,
+    secret: &str,
 ) -> Result<OrderResponse> {
     let endpoint = format!("{}/fapi/v1/order", super::BASE_URL);
 
@@ -47,18 +84,28 @@ pub async fn create_order(
     );
 
     if let Some(p) = price {
-        query_string.push_str(&format!("&price={}&timeInForce={}", p, "GTC"));
+        let time_in_force = options.time_in_force.unwrap_or("GTC");
+        query_string.push_str(&format!("&price={}&timeInForce={}", p, time_in_force));
     }
     if let Some(sp) = stop_price {
         query_string.push_str(&format!("&stopPrice={}", sp));
     }
+    if let Some(rate) = options.callback_rate {
+        query_string.push_str(&format!("&callbackRate={}", rate));
+    }
+    if let Some(ap) = options.activation_price {
+        query_string.push_str(&format!("&activationPrice={}", ap));
+    }
+    if let Some(wt) = options.working_type {
+        query_string.push_str(&format!("&workingType={}", wt));
+    }
 
     // 生成签名
-    let signature = super::create_signature(&super::API_SECRET, &query_string);
+    let signature = super::create_signature(secret, &query_string);
     let url = format!("{}?{}&signature={}", endpoint, query_string, signature);
 
     // 使用 post_request 发送带 body 的请求
-    let response = super::request::<OrderResponse>(&url, Method::POST, &super::API_KEY).await?;
+    let response = super::request::<OrderResponse>(&url, Method::POST, key).await?;
 
     Ok(response)
 }
@@ -82,11 +129,17 @@ pub async fn get_orders(symbol: &str) -> Result<Vec<OrderResponse>> {
 
 #[derive(Debug, Deserialize)]
 pub struct CancelOrderResponse {
-    // 根据 API 文档定义响应字段
+    // 根据 API 文档定义响应字段；撤单目前只检查请求是否成功
+    #[allow(dead_code)]
     orderId: u64,
 }
 
-pub async fn cancel_order(symbol: &str, order_id: u64) -> Result<CancelOrderResponse> {
+pub async fn cancel_order(
+    symbol: &str,
+    order_id: u64,
+    key: &str,
+    secret: &str,
+) -> Result<CancelOrderResponse> {
     let endpoint = format!("{}/fapi/v1/order", super::BASE_URL);
 
     // 获取当前时间戳
@@ -97,11 +150,11 @@ pub async fn cancel_order(symbol: &str, order_id: u64) -> Result<CancelOrderResp
         "symbol={}&orderId={}&timestamp={}",
         symbol, order_id, timestamp
     );
-    let signature = super::create_signature(&super::API_SECRET, &query_string);
+    let signature = super::create_signature(secret, &query_string);
 
     // 完整请求 URL，包含签名
     let url = format!("{}?{}&signature={}", endpoint, query_string, signature);
 
     // 调用 get_request 发起请求并解析为 AccountInfo
-    super::request::<CancelOrderResponse>(&url, Method::DELETE, &super::API_KEY).await
+    super::request::<CancelOrderResponse>(&url, Method::DELETE, key).await
 }
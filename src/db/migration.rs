@@ -0,0 +1,130 @@
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, DbErr, Statement, TransactionTrait};
+
+// 按版本顺序执行的迁移脚本，版本号为序号加 1。已发布的脚本不再修改，表结构变更只追加新脚本
+const MIGRATIONS: [&str; 13] = [
+    include_str!("../../migrations/0001_init.sql"),
+    include_str!("../../migrations/0002_trade_take_profits.sql"),
+    include_str!("../../migrations/0003_adjustment_presets.sql"),
+    include_str!("../../migrations/0004_trade_legs.sql"),
+    include_str!("../../migrations/0005_trade_fill_details.sql"),
+    include_str!("../../migrations/0006_risk_policies.sql"),
+    include_str!("../../migrations/0007_paper_trading.sql"),
+    include_str!("../../migrations/0008_webhooks.sql"),
+    include_str!("../../migrations/0009_triggers.sql"),
+    include_str!("../../migrations/0010_grids.sql"),
+    include_str!("../../migrations/0011_trade_analytics.sql"),
+    include_str!("../../migrations/0012_equity_snapshots.sql"),
+    include_str!("../../migrations/0013_symbols.sql"),
+];
+
+// 启动时执行尚未应用的迁移，每个脚本在一个事务中完成并记录版本
+pub async fn run_migrations(db: &DatabaseConnection) -> Result<(), DbErr> {
+    db.execute_unprepared(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            applied_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+        )",
+    )
+    .await?;
+    let applied = db
+        .query_one(Statement::from_string(
+            DbBackend::Sqlite,
+            "SELECT COALESCE(MAX(version), 0) AS version FROM schema_migrations",
+        ))
+        .await?
+        .map(|row| row.try_get::<i64>("", "version"))
+        .transpose()?
+        .unwrap_or_default();
+
+    for (version, script) in (1..).zip(MIGRATIONS).filter(|(v, _)| *v > applied) {
+        let txn = db.begin().await?;
+        for statement in statements(script) {
+            match txn.execute_unprepared(statement).await {
+                Ok(_) => {}
+                // 由旧版 init.sql 建成的库已有这些列，视为已应用
+                Err(e) if e.to_string().contains("duplicate column name") => {}
+                Err(e) => return Err(e),
+            }
+        }
+        txn.execute(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            "INSERT INTO schema_migrations (version) VALUES (?)",
+            [version.into()],
+        ))
+        .await?;
+        txn.commit().await?;
+    }
+    Ok(())
+}
+
+// 按分号拆分脚本，跳过只有注释的片段
+fn statements(script: &str) -> impl Iterator<Item = &str> {
+    script.split(';').filter(|statement| {
+        statement.lines().any(|line| {
+            let line = line.trim();
+            !line.is_empty() && !line.starts_with("--")
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::Database;
+
+    use super::*;
+
+    async fn columns(db: &DatabaseConnection, table: &str) -> Vec<String> {
+        db.query_all(Statement::from_string(
+            DbBackend::Sqlite,
+            format!("PRAGMA table_info({})", table),
+        ))
+        .await
+        .unwrap()
+        .iter()
+        .map(|row| row.try_get::<String>("", "name").unwrap())
+        .collect()
+    }
+
+    #[tokio::test]
+    async fn test_migrations_upgrade_existing_database() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        // 只执行过初始 init.sql 的旧库
+        for statement in statements(MIGRATIONS[0]) {
+            db.execute_unprepared(statement).await.unwrap();
+        }
+        db.execute_unprepared(
+            "INSERT INTO trades (symbol, entry_price, close_price, direction, quantity, leverage)
+             VALUES ('adausdt', '1', '1.1', 'Long', '10', '5')",
+        )
+        .await
+        .unwrap();
+
+        run_migrations(&db).await.unwrap();
+        // 再次执行不会重复应用
+        run_migrations(&db).await.unwrap();
+
+        let trade_columns = columns(&db, "trades").await;
+        for column in [
+            "trade_id",
+            "owner_id",
+            "close_reason",
+            "slippage",
+            "adjustment_id",
+        ] {
+            assert!(trade_columns.iter().any(|c| c == column), "{}", column);
+        }
+        assert!(!columns(&db, "symbols").await.is_empty());
+        let row = db
+            .query_one(Statement::from_string(
+                DbBackend::Sqlite,
+                "SELECT close_reason FROM trades",
+            ))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            row.try_get::<String>("", "close_reason").unwrap(),
+            "StopLoss"
+        );
+    }
+}
@@ -1,5 +1,11 @@
+pub mod migration;
+
 use sea_orm::{Database, DatabaseConnection, DbErr};
 
 pub async fn connect_db(uri: &str) -> Result<DatabaseConnection, DbErr> {
+    // 数据库文件不再纳入版本库，未指定打开方式时自动创建，表结构由迁移生成
+    if uri.starts_with("sqlite:") && !uri.contains('?') && !uri.contains(":memory:") {
+        return Database::connect(format!("{}?mode=rwc", uri)).await;
+    }
     Database::connect(uri).await
 }
@@ -0,0 +1,588 @@
+pub mod runner;
+
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use rust_decimal::Decimal;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::{
+    binance::leverage::SymbolFilter,
+    orm::grids,
+    trade::TradeDirection,
+    utils::{parse_decimal, round_to_step, round_to_tick, unix_timestamp},
+};
+
+// 网格数量上限，避免一次挂出过多订单
+pub const MAX_GRID_COUNT: u32 = 100;
+
+// 网格参数
+#[derive(Debug, Clone)]
+pub struct GridConfig {
+    pub symbol: String,
+    pub direction: TradeDirection,
+    pub lower: Decimal,
+    pub upper: Decimal,
+    pub grid_count: u32,
+    pub investment: Decimal, // 投入保证金
+    pub leverage: Decimal,
+}
+
+impl GridConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.lower <= Decimal::ZERO || self.upper <= self.lower {
+            return Err("upper_price must be greater than lower_price > 0".to_string());
+        }
+        if !(2..=MAX_GRID_COUNT).contains(&self.grid_count) {
+            return Err(format!(
+                "grid_count must be between 2 and {}",
+                MAX_GRID_COUNT
+            ));
+        }
+        if self.investment <= Decimal::ZERO || self.leverage < Decimal::ONE {
+            return Err("investment must be positive and leverage at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    // 等差网格的各档价格，按最小价格单位取整
+    fn prices(&self, filter: &SymbolFilter) -> Result<Vec<Decimal>, String> {
+        let step = (self.upper - self.lower) / Decimal::from(self.grid_count);
+        let prices: Vec<Decimal> = (0..=self.grid_count)
+            .map(|i| {
+                round_to_tick(
+                    self.lower + step * Decimal::from(i),
+                    filter.tick_size,
+                    false,
+                )
+            })
+            .collect();
+        if prices.windows(2).any(|w| w[1] <= w[0]) {
+            return Err("Grid spacing is smaller than the price tick".to_string());
+        }
+        Ok(prices)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum GridStatus {
+    Running,
+    Paused,
+    Stopped,
+}
+
+impl fmt::Display for GridStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl GridStatus {
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "Running" => Some(GridStatus::Running),
+            "Paused" => Some(GridStatus::Paused),
+            "Stopped" => Some(GridStatus::Stopped),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum LevelState {
+    Idle,     // 无持仓、无挂单
+    Entering, // 开仓单挂单中
+    Holding,  // 已开仓，平仓单待挂出（刚成交或暂停时撤单）
+    Exiting,  // 平仓单挂单中
+}
+
+// 单个网格：做多在下沿买入、上沿卖出，做空相反
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GridLevel {
+    pub entry: Decimal,
+    pub exit: Decimal,
+    pub state: LevelState,
+    pub order_id: Option<u64>,
+    pub entry_fill: Option<Decimal>, // 开仓成交价
+    pub held: Decimal,               // 该格持仓数量
+    pub round_trips: u32,
+    pub profit: Decimal, // 该格已实现利润（已扣手续费）
+}
+
+impl GridLevel {
+    fn new(entry: Decimal, exit: Decimal) -> Self {
+        Self {
+            entry,
+            exit,
+            state: LevelState::Idle,
+            order_id: None,
+            entry_fill: None,
+            held: Decimal::ZERO,
+            round_trips: 0,
+            profit: Decimal::ZERO,
+        }
+    }
+
+    fn order_price(&self) -> Decimal {
+        match self.state {
+            LevelState::Entering => self.entry,
+            _ => self.exit,
+        }
+    }
+}
+
+// 待挂出的限价单
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridOrder {
+    pub index: usize,
+    pub entry: bool,
+    pub price: Decimal,
+    pub quantity: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GridBot {
+    pub id: i64,
+    #[serde(skip)]
+    pub owner_id: String,
+    pub symbol: String,
+    pub direction: TradeDirection,
+    pub lower_price: Decimal,
+    pub upper_price: Decimal,
+    pub investment: Decimal,
+    pub leverage: Decimal,
+    pub quantity: Decimal, // 每格下单数量
+    pub fee_rate: Decimal,
+    pub status: GridStatus,
+    pub stop_reason: Option<String>,
+    pub levels: Vec<GridLevel>,
+    pub realized_profit: Decimal,
+    pub fees: Decimal,
+    pub round_trips: u32,
+    pub created_at: u32,
+}
+
+impl GridBot {
+    // 每格数量按全部开仓时名义价值等于 投入 × 杠杆 计算
+    pub fn new(
+        owner_id: String,
+        config: GridConfig,
+        filter: &SymbolFilter,
+        fee_rate: Decimal,
+    ) -> Result<Self, String> {
+        config.validate()?;
+        let prices = config.prices(filter)?;
+        let levels: Vec<GridLevel> = prices
+            .windows(2)
+            .map(|w| match config.direction {
+                TradeDirection::Long => GridLevel::new(w[0], w[1]),
+                TradeDirection::Short => GridLevel::new(w[1], w[0]),
+            })
+            .collect();
+        let total: Decimal = levels.iter().map(|l| l.entry).sum();
+        let quantity = round_to_step(
+            config.investment * config.leverage / total,
+            filter.step_size,
+        );
+        if quantity <= Decimal::ZERO || quantity < filter.min_qty {
+            return Err(format!(
+                "Quantity per grid {} below minimum {}",
+                quantity, filter.min_qty
+            ));
+        }
+        if quantity * config.lower < filter.min_notional {
+            return Err(format!(
+                "Order value per grid below minimum notional {}",
+                filter.min_notional
+            ));
+        }
+        Ok(Self {
+            id: 0,
+            owner_id,
+            symbol: config.symbol,
+            direction: config.direction,
+            lower_price: prices[0],
+            upper_price: prices[prices.len() - 1],
+            investment: config.investment,
+            leverage: config.leverage,
+            quantity,
+            fee_rate,
+            status: GridStatus::Running,
+            stop_reason: None,
+            levels,
+            realized_profit: Decimal::ZERO,
+            fees: Decimal::ZERO,
+            round_trips: 0,
+            created_at: unix_timestamp(),
+        })
+    }
+
+    pub fn from_model(model: &grids::Model) -> Result<Self, String> {
+        let direction = match model.direction.as_str() {
+            "Long" => TradeDirection::Long,
+            "Short" => TradeDirection::Short,
+            other => return Err(format!("Invalid grid direction {}", other)),
+        };
+        let status = GridStatus::parse(&model.status)
+            .ok_or(format!("Invalid grid status {}", model.status))?;
+        let levels = serde_json::from_str(&model.levels)
+            .map_err(|e| format!("Invalid grid levels: {}", e))?;
+        let decimal = |s: &str| parse_decimal(s).unwrap_or_default();
+        Ok(Self {
+            id: model.id,
+            owner_id: model.owner_id.clone(),
+            symbol: model.symbol.clone(),
+            direction,
+            lower_price: decimal(&model.lower_price),
+            upper_price: decimal(&model.upper_price),
+            investment: decimal(&model.investment),
+            leverage: decimal(&model.leverage),
+            quantity: decimal(&model.quantity),
+            fee_rate: decimal(&model.fee_rate),
+            status,
+            stop_reason: model.stop_reason.clone(),
+            levels,
+            realized_profit: decimal(&model.realized_profit),
+            fees: decimal(&model.fees),
+            round_trips: model.round_trips,
+            created_at: model.created_at,
+        })
+    }
+
+    // 下单方向与对冲模式的 positionSide
+    pub fn order_sides(&self, entry: bool) -> (&'static str, &'static str) {
+        match (&self.direction, entry) {
+            (TradeDirection::Long, true) => ("BUY", "LONG"),
+            (TradeDirection::Long, false) => ("SELL", "LONG"),
+            (TradeDirection::Short, true) => ("SELL", "SHORT"),
+            (TradeDirection::Short, false) => ("BUY", "SHORT"),
+        }
+    }
+
+    fn is_buy(&self, entry: bool) -> bool {
+        (self.direction == TradeDirection::Long) == entry
+    }
+
+    // 价格超出区间时网格停止
+    pub fn range_broken(&self, price: Decimal) -> bool {
+        price < self.lower_price || price > self.upper_price
+    }
+
+    // 需要挂出的订单：开仓单只挂在当前价格的挂单一侧，已持仓的网格挂平仓单
+    pub fn pending_orders(&self, price: Decimal) -> Vec<GridOrder> {
+        let entry_rests = |entry: Decimal| {
+            if self.is_buy(true) {
+                entry < price
+            } else {
+                entry > price
+            }
+        };
+        self.levels
+            .iter()
+            .enumerate()
+            .filter_map(|(index, level)| match level.state {
+                LevelState::Idle if entry_rests(level.entry) => Some(GridOrder {
+                    index,
+                    entry: true,
+                    price: level.entry,
+                    quantity: self.quantity,
+                }),
+                LevelState::Holding => Some(GridOrder {
+                    index,
+                    entry: false,
+                    price: level.exit,
+                    quantity: level.held,
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn resting_orders(&self) -> Vec<(usize, u64)> {
+        self.levels
+            .iter()
+            .enumerate()
+            .filter_map(|(index, level)| level.order_id.map(|id| (index, id)))
+            .collect()
+    }
+
+    // 只查询最可能成交的挂单：最高的买单和最低的卖单。成交总是由近及远，
+    // 一次成交后立即再查下一档即可跟上连续成交
+    pub fn orders_to_check(&self) -> Vec<(usize, u64)> {
+        let resting = self.resting_orders();
+        let is_buy = |index: usize| self.is_buy(self.levels[index].state == LevelState::Entering);
+        let price = |index: usize| self.levels[index].order_price();
+        let highest_buy = resting
+            .iter()
+            .filter(|(i, _)| is_buy(*i))
+            .max_by_key(|(i, _)| price(*i));
+        let lowest_sell = resting
+            .iter()
+            .filter(|(i, _)| !is_buy(*i))
+            .min_by_key(|(i, _)| price(*i));
+        highest_buy
+            .into_iter()
+            .chain(lowest_sell)
+            .copied()
+            .collect()
+    }
+
+    pub fn mark_placed(&mut self, index: usize, order_id: u64) {
+        let level = &mut self.levels[index];
+        level.state = match level.state {
+            LevelState::Idle => LevelState::Entering,
+            LevelState::Holding => LevelState::Exiting,
+            state => state,
+        };
+        level.order_id = Some(order_id);
+    }
+
+    // 挂单结束（成交或撤单）：按成交数量更新持仓，未成交部分退回挂单前的状态
+    pub fn settle(&mut self, index: usize, price: Decimal, executed: Decimal) {
+        let fee_rate = self.fee_rate;
+        let level = &mut self.levels[index];
+        level.order_id = None;
+        match level.state {
+            LevelState::Entering if executed > Decimal::ZERO => {
+                level.state = LevelState::Holding;
+                level.entry_fill = Some(price);
+                level.held = executed;
+                self.fees += price * executed * fee_rate;
+            }
+            LevelState::Entering => level.state = LevelState::Idle,
+            LevelState::Exiting => {
+                level.state = LevelState::Holding;
+                if executed > Decimal::ZERO {
+                    self.realize(index, price, executed);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // 停止时按市价平掉所有持仓
+    pub fn close_holdings(&mut self, price: Decimal) {
+        for index in 0..self.levels.len() {
+            let held = self.levels[index].held;
+            if held > Decimal::ZERO {
+                self.realize(index, price, held);
+            }
+        }
+    }
+
+    // 平仓成交：利润扣除开平仓两边手续费，持仓清空后该格回到空闲
+    fn realize(&mut self, index: usize, price: Decimal, quantity: Decimal) {
+        let level = &mut self.levels[index];
+        let entry = level.entry_fill.unwrap_or(level.entry);
+        let pnl = match self.direction {
+            TradeDirection::Long => (price - entry) * quantity,
+            TradeDirection::Short => (entry - price) * quantity,
+        };
+        let exit_fee = price * quantity * self.fee_rate;
+        let profit = pnl - (entry * quantity * self.fee_rate) - exit_fee;
+        level.profit += profit;
+        level.held -= quantity;
+        if level.held <= Decimal::ZERO {
+            level.held = Decimal::ZERO;
+            level.entry_fill = None;
+            level.state = LevelState::Idle;
+            level.round_trips += 1;
+            self.round_trips += 1;
+        }
+        self.realized_profit += profit;
+        self.fees += exit_fee;
+    }
+
+    pub fn holding(&self) -> Decimal {
+        self.levels.iter().map(|l| l.held).sum()
+    }
+
+    // 持仓的名义价值，按各格开仓成交价计算
+    pub fn notional(&self) -> Decimal {
+        self.levels
+            .iter()
+            .map(|l| l.held * l.entry_fill.unwrap_or(l.entry))
+            .sum()
+    }
+
+    pub fn unrealized_profit(&self, price: Decimal) -> Decimal {
+        self.levels
+            .iter()
+            .filter_map(|l| {
+                let entry = l.entry_fill?;
+                Some(match self.direction {
+                    TradeDirection::Long => (price - entry) * l.held,
+                    TradeDirection::Short => (entry - price) * l.held,
+                })
+            })
+            .sum()
+    }
+
+    pub fn stop(&mut self, reason: &str) {
+        self.status = GridStatus::Stopped;
+        if self.stop_reason.is_none() {
+            self.stop_reason = Some(reason.to_string());
+        }
+    }
+}
+
+// 运行中和暂停的网格，停止后由运行任务移除
+#[derive(Debug, Default)]
+pub struct Grids {
+    bots: Mutex<HashMap<i64, Arc<Mutex<GridBot>>>>,
+}
+
+impl Grids {
+    pub async fn insert(&self, bot: Arc<Mutex<GridBot>>) {
+        let id = bot.lock().await.id;
+        self.bots.lock().await.insert(id, bot);
+    }
+
+    pub async fn remove(&self, id: i64) {
+        self.bots.lock().await.remove(&id);
+    }
+
+    // 只返回属于该用户的网格
+    pub async fn get(&self, id: i64, owner_id: &str) -> Option<Arc<Mutex<GridBot>>> {
+        let bot = self.bots.lock().await.get(&id).cloned()?;
+        let owned = bot.lock().await.owner_id == owner_id;
+        owned.then_some(bot)
+    }
+
+    // 该用户的所有网格
+    pub async fn owned_by(&self, owner_id: &str) -> Vec<Arc<Mutex<GridBot>>> {
+        let bots: Vec<_> = self.bots.lock().await.values().cloned().collect();
+        let mut owned = Vec::new();
+        for bot in bots {
+            if bot.lock().await.owner_id == owner_id {
+                owned.push(bot);
+            }
+        }
+        owned
+    }
+
+    pub async fn has_symbol(&self, symbol: &str) -> bool {
+        let bots: Vec<_> = self.bots.lock().await.values().cloned().collect();
+        for bot in bots {
+            if bot.lock().await.symbol == symbol {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+pub async fn insert_grid(db: &DatabaseConnection, bot: &mut GridBot) -> Result<(), DbErr> {
+    let model = grids::ActiveModel {
+        owner_id: Set(bot.owner_id.clone()),
+        symbol: Set(bot.symbol.clone()),
+        direction: Set(bot.direction.to_string()),
+        lower_price: Set(bot.lower_price.to_string()),
+        upper_price: Set(bot.upper_price.to_string()),
+        grid_count: Set(bot.levels.len() as u32),
+        investment: Set(bot.investment.to_string()),
+        leverage: Set(bot.leverage.to_string()),
+        quantity: Set(bot.quantity.to_string()),
+        fee_rate: Set(bot.fee_rate.to_string()),
+        levels: Set(serde_json::to_string(&bot.levels).unwrap_or_default()),
+        status: Set(bot.status.to_string()),
+        created_at: Set(bot.created_at),
+        updated_at: Set(bot.created_at),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+    bot.id = model.id;
+    Ok(())
+}
+
+// 写回运行状态
+pub async fn save_grid(db: &DatabaseConnection, bot: &GridBot) -> Result<(), DbErr> {
+    grids::ActiveModel {
+        id: Set(bot.id),
+        levels: Set(serde_json::to_string(&bot.levels).unwrap_or_default()),
+        status: Set(bot.status.to_string()),
+        stop_reason: Set(bot.stop_reason.clone()),
+        realized_profit: Set(bot.realized_profit.to_string()),
+        fees: Set(bot.fees.to_string()),
+        round_trips: Set(bot.round_trips),
+        updated_at: Set(unix_timestamp()),
+        ..Default::default()
+    }
+    .update(db)
+    .await?;
+    Ok(())
+}
+
+// 启动时读取未停止的网格
+pub async fn find_active_grids(db: &DatabaseConnection) -> Result<Vec<grids::Model>, DbErr> {
+    grids::Entity::find()
+        .filter(grids::Column::Status.ne(GridStatus::Stopped.to_string()))
+        .all(db)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_long_grid_round_trip() {
+        let filter = SymbolFilter {
+            tick_size: dec!(0.01),
+            step_size: dec!(1),
+            ..Default::default()
+        };
+        let config = GridConfig {
+            symbol: "adausdt".to_string(),
+            direction: TradeDirection::Long,
+            lower: dec!(1),
+            upper: dec!(1.4),
+            grid_count: 4,
+            investment: dec!(100),
+            leverage: dec!(2),
+        };
+        let mut bot = GridBot::new("1".to_string(), config, &filter, dec!(0.001)).unwrap();
+        // 200 / (1 + 1.1 + 1.2 + 1.3) = 43.47 取整为 43
+        assert_eq!(bot.quantity, dec!(43));
+
+        // 价格 1.15：只在 1.0 和 1.1 挂买单
+        let orders = bot.pending_orders(dec!(1.15));
+        let prices: Vec<Decimal> = orders.iter().map(|o| o.price).collect();
+        assert_eq!(prices, vec![dec!(1), dec!(1.1)]);
+        bot.mark_placed(0, 1);
+        bot.mark_placed(1, 2);
+        assert_eq!(bot.orders_to_check(), vec![(1, 2)]);
+
+        // 1.1 买入成交后在 1.2 挂卖单
+        bot.settle(1, dec!(1.1), dec!(43));
+        // 敞口按开仓成交价计算
+        assert_eq!(bot.notional(), dec!(47.3));
+        assert_eq!(
+            bot.pending_orders(dec!(1.08)),
+            vec![GridOrder {
+                index: 1,
+                entry: false,
+                price: dec!(1.2),
+                quantity: dec!(43),
+            }]
+        );
+        bot.mark_placed(1, 3);
+        assert_eq!(bot.orders_to_check(), vec![(0, 1), (1, 3)]);
+
+        bot.settle(1, dec!(1.2), dec!(43));
+        // (1.2 - 1.1) × 43 - (1.1 + 1.2) × 43 × 0.001
+        assert_eq!(bot.realized_profit, dec!(4.2011));
+        assert_eq!(bot.levels[1].state, LevelState::Idle);
+        assert_eq!(bot.round_trips, 1);
+        assert_eq!(bot.holding(), Decimal::ZERO);
+
+        assert!(bot.range_broken(dec!(0.99)));
+        assert!(!bot.range_broken(dec!(1.4)));
+        assert_eq!(bot.order_sides(false), ("SELL", "LONG"));
+    }
+}
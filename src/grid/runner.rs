@@ -0,0 +1,273 @@
+use std::sync::Arc;
+
+use rust_decimal::Decimal;
+use sea_orm::DatabaseConnection;
+use tokio::{
+    sync::Mutex,
+    time::{sleep, Duration},
+};
+
+use super::{save_grid, GridBot, GridStatus, Grids};
+use crate::{
+    binance::{
+        account::get_order_api,
+        order::{cancel_order, create_order},
+    },
+    secret_key::SecretKey,
+    symbol::SymbolMap,
+    trade::price::PriceBook,
+    utils::parse_decimal,
+};
+
+// 挂单轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+// 连续下单失败次数上限，超过后停止网格
+const MAX_ORDER_FAILURES: u32 = 5;
+
+// 登记网格并启动运行任务
+pub async fn start_grid(
+    bot: GridBot,
+    grids: Arc<Grids>,
+    prices: Arc<SymbolMap<Mutex<PriceBook>>>,
+    key: SecretKey,
+    database: DatabaseConnection,
+) -> Arc<Mutex<GridBot>> {
+    let bot = Arc::new(Mutex::new(bot));
+    grids.insert(bot.clone()).await;
+    tokio::spawn(run_grid(bot.clone(), grids, prices, key, database));
+    bot
+}
+
+// 网格运行循环：按状态撤单或平仓，运行中轮询成交并补挂订单，价格突破区间时停止
+async fn run_grid(
+    bot: Arc<Mutex<GridBot>>,
+    grids: Arc<Grids>,
+    prices: Arc<SymbolMap<Mutex<PriceBook>>>,
+    key: SecretKey,
+    database: DatabaseConnection,
+) {
+    let (id, symbol) = {
+        let bot = bot.lock().await;
+        (bot.id, bot.symbol.clone())
+    };
+    let mut failures = 0;
+    loop {
+        let status = bot.lock().await.status;
+        match status {
+            GridStatus::Paused => {
+                if cancel_resting(&bot, &symbol, &key).await {
+                    save(&bot, &database).await;
+                }
+                sleep(POLL_INTERVAL).await;
+                continue;
+            }
+            GridStatus::Stopped => {
+                cancel_resting(&bot, &symbol, &key).await;
+                // 撤单结果未确认时重试，避免留下无人管理的挂单
+                if !bot.lock().await.resting_orders().is_empty() {
+                    sleep(POLL_INTERVAL).await;
+                    continue;
+                }
+                close_holdings(&bot, &symbol, &prices, &key).await;
+                save(&bot, &database).await;
+                break;
+            }
+            GridStatus::Running => {}
+        }
+
+        let Some(price) = mid_price(&prices, &symbol).await else {
+            sleep(POLL_INTERVAL).await;
+            continue;
+        };
+        {
+            let mut bot = bot.lock().await;
+            if bot.range_broken(price) {
+                println!("网格 {} 价格 {} 突破区间，停止。", id, price);
+                bot.stop(&format!("Price {} left the grid range", price));
+                continue;
+            }
+        }
+
+        let filled = poll_orders(&bot, &symbol, &key).await;
+        let placed = place_orders(&bot, &symbol, price, &key, &mut failures).await;
+        if failures >= MAX_ORDER_FAILURES {
+            bot.lock().await.stop("Repeated order failures");
+        }
+        if filled || placed {
+            save(&bot, &database).await;
+        }
+        // 有成交时立即检查下一档
+        if !filled {
+            sleep(POLL_INTERVAL).await;
+        }
+    }
+    grids.remove(id).await;
+}
+
+// 网格以买一卖一中间价判断区间和挂单方向
+pub async fn mid_price(prices: &SymbolMap<Mutex<PriceBook>>, symbol: &str) -> Option<Decimal> {
+    let book = prices.get(symbol)?;
+    let book = book.lock().await;
+    (book.ask > Decimal::ZERO && book.bid > Decimal::ZERO)
+        .then(|| (book.ask + book.bid) / Decimal::TWO)
+}
+
+// 查询最可能成交的挂单，返回是否有挂单结束
+async fn poll_orders(bot: &Mutex<GridBot>, symbol: &str, key: &SecretKey) -> bool {
+    let to_check = bot.lock().await.orders_to_check();
+    let mut changed = false;
+    for (index, order_id) in to_check {
+        let order = match get_order_api(symbol, order_id, &key.api_key, &key.api_secret).await {
+            Ok(order) => order,
+            Err(_) => continue,
+        };
+        // 外部撤单也按已成交数量结算，未成交的网格下一轮重新挂单
+        if matches!(
+            order.status.as_str(),
+            "FILLED" | "CANCELED" | "EXPIRED" | "REJECTED"
+        ) {
+            let mut bot = bot.lock().await;
+            let level = &bot.levels[index];
+            if level.order_id != Some(order_id) {
+                continue;
+            }
+            let price = parse_decimal(&order.avgPrice)
+                .filter(|p| *p > Decimal::ZERO)
+                .unwrap_or(level.order_price());
+            let executed = parse_decimal(&order.executedQty).unwrap_or_default();
+            bot.settle(index, price, executed);
+            changed = true;
+        }
+    }
+    changed
+}
+
+// 挂出缺少的限价单，返回是否有新挂单
+async fn place_orders(
+    bot: &Mutex<GridBot>,
+    symbol: &str,
+    price: Decimal,
+    key: &SecretKey,
+    failures: &mut u32,
+) -> bool {
+    let orders = {
+        let bot = bot.lock().await;
+        bot.pending_orders(price)
+            .into_iter()
+            .map(|order| (bot.order_sides(order.entry), order))
+            .collect::<Vec<_>>()
+    };
+    let mut placed = false;
+    for ((side, position_side), order) in orders {
+        let result = create_order(
+            symbol,
+            side,
+            position_side,
+            "LIMIT",
+            &order.quantity.to_string(),
+            Some(&order.price.to_string()),
+            None,
+            &key.api_key,
+            &key.api_secret,
+        )
+        .await;
+        match result {
+            Ok(response) => {
+                bot.lock().await.mark_placed(order.index, response.orderId);
+                *failures = 0;
+                placed = true;
+            }
+            Err(e) => {
+                eprintln!(
+                    "网格挂单失败，交易对 {}，价格 {}：{}",
+                    symbol, order.price, e
+                );
+                *failures += 1;
+                if *failures >= MAX_ORDER_FAILURES {
+                    break;
+                }
+            }
+        }
+    }
+    placed
+}
+
+// 撤销所有挂单并按最终成交数量结算，返回是否有挂单结束
+async fn cancel_resting(bot: &Mutex<GridBot>, symbol: &str, key: &SecretKey) -> bool {
+    let resting = bot.lock().await.resting_orders();
+    let mut changed = false;
+    for (index, order_id) in resting {
+        let _ = cancel_order(symbol, order_id, &key.api_key, &key.api_secret).await;
+        // 撤单后读取最终状态，撤单前可能已经成交
+        let Ok(order) = get_order_api(symbol, order_id, &key.api_key, &key.api_secret).await else {
+            continue;
+        };
+        if order.status == "NEW" || order.status == "PARTIALLY_FILLED" {
+            continue;
+        }
+        let mut bot = bot.lock().await;
+        let level = &bot.levels[index];
+        let price = parse_decimal(&order.avgPrice)
+            .filter(|p| *p > Decimal::ZERO)
+            .unwrap_or(level.order_price());
+        let executed = parse_decimal(&order.executedQty).unwrap_or_default();
+        bot.settle(index, price, executed);
+        changed = true;
+    }
+    changed
+}
+
+// 市价平掉网格持有的仓位（对冲模式下反向下单即为只减仓）
+async fn close_holdings(
+    bot: &Mutex<GridBot>,
+    symbol: &str,
+    prices: &SymbolMap<Mutex<PriceBook>>,
+    key: &SecretKey,
+) {
+    let (quantity, (side, position_side)) = {
+        let bot = bot.lock().await;
+        (bot.holding(), bot.order_sides(false))
+    };
+    if quantity <= Decimal::ZERO {
+        return;
+    }
+    let result = create_order(
+        symbol,
+        side,
+        position_side,
+        "MARKET",
+        &quantity.to_string(),
+        None,
+        None,
+        &key.api_key,
+        &key.api_secret,
+    )
+    .await;
+    let order = match result {
+        Ok(order) => order,
+        Err(e) => {
+            eprintln!("网格平仓失败，交易对 {}，数量 {}：{}", symbol, quantity, e);
+            bot.lock().await.stop_reason = Some(format!("Close failed: {}", e));
+            return;
+        }
+    };
+    // 成交均价查询失败时按中间价估算利润
+    let price = match get_order_api(symbol, order.orderId, &key.api_key, &key.api_secret).await {
+        Ok(order) => parse_decimal(&order.avgPrice).filter(|p| *p > Decimal::ZERO),
+        Err(_) => None,
+    };
+    let price = match price {
+        Some(price) => Some(price),
+        None => mid_price(prices, symbol).await,
+    };
+    if let Some(price) = price {
+        bot.lock().await.close_holdings(price);
+    }
+}
+
+async fn save(bot: &Mutex<GridBot>, database: &DatabaseConnection) {
+    let bot = bot.lock().await;
+    if let Err(e) = save_grid(database, &bot).await {
+        eprintln!("网格 {} 状态写入失败：{}", bot.id, e);
+    }
+}
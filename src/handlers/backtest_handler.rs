@@ -0,0 +1,134 @@
+use std::sync::Arc;
+
+use axum::{extract::Query, http::StatusCode, response::IntoResponse, Extension, Json};
+use sea_orm::DatabaseConnection;
+
+use crate::{
+    backtest::{
+        data::MarketTick,
+        job::{BacktestJobs, JobState},
+        run_backtest, BacktestConfig,
+    },
+    binance::{leverage::SymbolFilter, market::get_klines_between},
+    models::backtest_model::{
+        BacktestData, BacktestJobParams, BacktestJobResponse, BacktestRequest,
+    },
+    symbol::SymbolMap,
+    trade::{sizing::DEFAULT_TAKER_FEE, strategy::StopStrategyConfig, validate_adjustments},
+};
+
+use super::preset_handler::load_preset_adjustments;
+
+type HandlerError = (StatusCode, String);
+
+// 单次回测的最大行情数量
+const MAX_BACKTEST_TICKS: usize = 500_000;
+
+// 创建回测任务，在后台拉取数据并运行，通过 /backtest/job 查询结果
+pub async fn create_backtest(
+    Extension(user_id): Extension<String>,
+    Extension(database): Extension<DatabaseConnection>,
+    Extension(filters): Extension<Arc<SymbolMap<SymbolFilter>>>,
+    Extension(jobs): Extension<Arc<BacktestJobs>>,
+    Json(payload): Json<BacktestRequest>,
+) -> Result<impl IntoResponse, HandlerError> {
+    let adjustments = match (
+        &payload.stop_strategy,
+        payload.adjustments,
+        payload.adjustment_id,
+    ) {
+        (StopStrategyConfig::Ladder, Some(adjustments), _) => {
+            validate_adjustments(&adjustments).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+            adjustments
+        }
+        (StopStrategyConfig::Ladder, None, Some(id)) => {
+            load_preset_adjustments(&database, id, &user_id).await?
+        }
+        (StopStrategyConfig::Ladder, None, None) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Ladder requires adjustment_id or adjustments".to_string(),
+            ))
+        }
+        _ => Vec::new(),
+    };
+    // 未订阅的品种不做价格和数量取整
+    let filter = filters
+        .get(&payload.symbol)
+        .as_deref()
+        .cloned()
+        .unwrap_or_default();
+
+    let config = BacktestConfig {
+        symbol: payload.symbol,
+        direction: payload.direction,
+        leverage: payload.leverage,
+        margin: payload.margin,
+        stop_loss_percent: payload.stop_loss_percent,
+        stop_strategy: payload.stop_strategy,
+        adjustments,
+        take_profits: payload.take_profits,
+        price_source: payload.price_source,
+        fee_rate: payload.fee_rate.unwrap_or(DEFAULT_TAKER_FEE),
+        slippage: payload.slippage,
+        entry: payload.entry,
+        filter,
+    };
+    config
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    if let BacktestData::Ticks { ticks } = &payload.data {
+        if ticks.is_empty() || ticks.len() > MAX_BACKTEST_TICKS {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("ticks must contain 1 to {} items", MAX_BACKTEST_TICKS),
+            ));
+        }
+    }
+
+    let id = jobs.create(&user_id).await;
+    tokio::spawn(async move {
+        let state = match load_ticks(&config.symbol, payload.data).await {
+            Ok(ticks) => match run_backtest(&config, &ticks).await {
+                Ok(report) => JobState::Done { report },
+                Err(error) => JobState::Failed { error },
+            },
+            Err(error) => JobState::Failed { error },
+        };
+        jobs.finish(id, state).await;
+    });
+    Ok(Json(BacktestJobResponse { id }))
+}
+
+pub async fn get_backtest(
+    Extension(user_id): Extension<String>,
+    Extension(jobs): Extension<Arc<BacktestJobs>>,
+    Query(params): Query<BacktestJobParams>,
+) -> Result<impl IntoResponse, HandlerError> {
+    match jobs.get(params.id, &user_id).await {
+        Some(job) => Ok(Json(job)),
+        None => Err((StatusCode::NOT_FOUND, "Backtest job not found".to_string())),
+    }
+}
+
+async fn load_ticks(symbol: &str, data: BacktestData) -> Result<Vec<MarketTick>, String> {
+    match data {
+        BacktestData::Ticks { ticks } => Ok(ticks),
+        BacktestData::Klines {
+            interval,
+            start_time,
+            end_time,
+        } => {
+            let klines = get_klines_between(symbol, &interval, start_time, end_time)
+                .await
+                .map_err(|e| format!("Failed to load klines: {}", e))?;
+            if klines.is_empty() || klines.len() > MAX_BACKTEST_TICKS {
+                return Err(format!(
+                    "kline range must contain 1 to {} bars",
+                    MAX_BACKTEST_TICKS
+                ));
+            }
+            Ok(klines.iter().map(MarketTick::from).collect())
+        }
+    }
+}
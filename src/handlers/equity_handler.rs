@@ -0,0 +1,44 @@
+use axum::{extract::Query, http::StatusCode, response::IntoResponse, Extension, Json};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
+
+use crate::{
+    models::equity_model::EquityQueryParams,
+    orm::equity_snapshots,
+    trade::equity::{downsample, EquityPoint},
+};
+
+// 默认和最大返回点数
+const DEFAULT_MAX_POINTS: usize = 500;
+const MAX_POINTS_LIMIT: usize = 5000;
+
+// 当前用户的权益曲线，按时间升序
+pub async fn get_equity_curve(
+    Extension(user_id): Extension<String>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(params): Query<EquityQueryParams>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let mut query =
+        equity_snapshots::Entity::find().filter(equity_snapshots::Column::OwnerId.eq(user_id));
+    if let Some(start_time) = params.start_time {
+        query = query.filter(equity_snapshots::Column::CreatedAt.gte(start_time));
+    }
+    if let Some(end_time) = params.end_time {
+        query = query.filter(equity_snapshots::Column::CreatedAt.lte(end_time));
+    }
+    let models = query
+        .order_by_asc(equity_snapshots::Column::CreatedAt)
+        .all(&database)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Database error: {}", e),
+            )
+        })?;
+    let points = models.iter().map(EquityPoint::from_model).collect();
+    let max_points = params
+        .max_points
+        .unwrap_or(DEFAULT_MAX_POINTS)
+        .clamp(2, MAX_POINTS_LIMIT);
+    Ok(Json(downsample(points, max_points)))
+}
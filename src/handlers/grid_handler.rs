@@ -0,0 +1,253 @@
+use std::sync::Arc;
+
+use axum::{extract::Query, http::StatusCode, response::IntoResponse, Extension, Json};
+use rust_decimal::Decimal;
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder};
+use tokio::sync::Mutex;
+
+use crate::{
+    binance::{account::get_commission_rate, leverage::SymbolFilter},
+    grid::{
+        find_active_grids, insert_grid,
+        runner::{mid_price, start_grid},
+        save_grid, GridBot, GridConfig, GridStatus, Grids,
+    },
+    models::grid_model::{CreateGridRequest, GridDetail, GridIdRequest, GridQueryParams},
+    orm::grids,
+    secret_key::KeyManager,
+    symbol::SymbolMap,
+    trade::{
+        price::PriceBook,
+        risk::{check_exposure, user_exposure},
+        sizing::DEFAULT_TAKER_FEE,
+        Trade,
+    },
+};
+
+use super::{
+    get_api_key, load_api_key, risk_handler::check_account_risk, trade_hander::check_symbol_trading,
+};
+
+type HandlerError = (StatusCode, String);
+
+// 创建网格：当前价格必须在区间内，创建后立即开始挂单
+#[allow(clippy::too_many_arguments)]
+pub async fn create_grid(
+    Extension(user_id): Extension<String>,
+    Extension(api_keys): Extension<Arc<KeyManager>>,
+    Extension(trades): Extension<Arc<SymbolMap<Mutex<Vec<Trade>>>>>,
+    Extension(prices): Extension<Arc<SymbolMap<Mutex<PriceBook>>>>,
+    Extension(filters): Extension<Arc<SymbolMap<SymbolFilter>>>,
+    Extension(database): Extension<DatabaseConnection>,
+    Extension(grids): Extension<Arc<Grids>>,
+    Json(payload): Json<CreateGridRequest>,
+) -> Result<impl IntoResponse, HandlerError> {
+    let Some(filter) = filters.get(&payload.symbol) else {
+        return Err((StatusCode::BAD_REQUEST, "Symbol not found".to_string()));
+    };
+    check_symbol_trading(&filters, &payload.symbol)?;
+    let key = get_api_key(api_keys, &user_id).await?;
+    let risk_policy = check_account_risk(&database, &user_id, payload.leverage).await?;
+    let fee_rate = get_commission_rate(&payload.symbol, &key.api_key, &key.api_secret)
+        .await
+        .map(|rate| rate.taker_commission_rate)
+        .unwrap_or(DEFAULT_TAKER_FEE);
+
+    let config = GridConfig {
+        symbol: payload.symbol,
+        direction: payload.direction,
+        lower: payload.lower_price,
+        upper: payload.upper_price,
+        grid_count: payload.grid_count,
+        investment: payload.investment,
+        leverage: payload.leverage,
+    };
+    let mut bot = GridBot::new(user_id, config, &filter, fee_rate)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let price = mid_price(&prices, &bot.symbol).await.ok_or((
+        StatusCode::BAD_REQUEST,
+        "Market price not available".to_string(),
+    ))?;
+    if bot.range_broken(price) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Current price {} is outside the grid range", price),
+        ));
+    }
+    // 网格全部开仓时的名义价值计入敞口限制
+    if let Some(policy) = &risk_policy {
+        let exposure = user_exposure(&trades, &grids, &bot.owner_id, &bot.symbol).await;
+        check_exposure(policy, &exposure, bot.investment * bot.leverage)
+            .map_err(|e| (StatusCode::FORBIDDEN, e))?;
+    }
+
+    insert_grid(&database, &mut bot).await.map_err(db_error)?;
+    let detail = grid_detail(bot.clone(), Some(price));
+    start_grid(bot, grids, prices, key, database).await;
+    Ok(Json(detail))
+}
+
+pub async fn list_grids(
+    Extension(user_id): Extension<String>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(params): Query<GridQueryParams>,
+) -> Result<impl IntoResponse, HandlerError> {
+    let mut query = grids::Entity::find().filter(grids::Column::OwnerId.eq(user_id));
+    if let Some(symbol) = &params.symbol {
+        query = query.filter(grids::Column::Symbol.eq(symbol.as_str()));
+    }
+    if let Some(status) = &params.status {
+        query = query.filter(grids::Column::Status.eq(status.as_str()));
+    }
+    let models = query
+        .order_by_desc(grids::Column::Id)
+        .all(&database)
+        .await
+        .map_err(db_error)?;
+    let result: Vec<GridBot> = models
+        .iter()
+        .filter_map(|model| GridBot::from_model(model).ok())
+        .collect();
+    Ok(Json(result))
+}
+
+// 运行中的网格读取内存状态，已停止的读取数据库
+pub async fn get_grid(
+    Extension(user_id): Extension<String>,
+    Extension(prices): Extension<Arc<SymbolMap<Mutex<PriceBook>>>>,
+    Extension(database): Extension<DatabaseConnection>,
+    Extension(grids): Extension<Arc<Grids>>,
+    Query(params): Query<GridIdRequest>,
+) -> Result<impl IntoResponse, HandlerError> {
+    let bot = match grids.get(params.id, &user_id).await {
+        Some(bot) => bot.lock().await.clone(),
+        None => {
+            let model = grids::Entity::find_by_id(params.id)
+                .filter(grids::Column::OwnerId.eq(user_id.as_str()))
+                .one(&database)
+                .await
+                .map_err(db_error)?
+                .ok_or((StatusCode::BAD_REQUEST, "Grid not found".to_string()))?;
+            GridBot::from_model(&model).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+        }
+    };
+    let price = mid_price(&prices, &bot.symbol).await;
+    Ok(Json(grid_detail(bot, price)))
+}
+
+// 暂停：撤销挂单，保留持仓
+pub async fn pause_grid(
+    Extension(user_id): Extension<String>,
+    Extension(database): Extension<DatabaseConnection>,
+    Extension(grids): Extension<Arc<Grids>>,
+    Json(payload): Json<GridIdRequest>,
+) -> Result<impl IntoResponse, HandlerError> {
+    change_status(&grids, &database, payload.id, &user_id, |bot| {
+        if bot.status != GridStatus::Running {
+            return Err("Grid is not running".to_string());
+        }
+        bot.status = GridStatus::Paused;
+        Ok(())
+    })
+    .await
+}
+
+// 恢复：补挂持仓的平仓单和当前价格下方（做空为上方）的开仓单
+pub async fn resume_grid(
+    Extension(user_id): Extension<String>,
+    Extension(database): Extension<DatabaseConnection>,
+    Extension(grids): Extension<Arc<Grids>>,
+    Json(payload): Json<GridIdRequest>,
+) -> Result<impl IntoResponse, HandlerError> {
+    change_status(&grids, &database, payload.id, &user_id, |bot| {
+        if bot.status != GridStatus::Paused {
+            return Err("Grid is not paused".to_string());
+        }
+        bot.status = GridStatus::Running;
+        Ok(())
+    })
+    .await
+}
+
+// 停止：撤销挂单并市价平掉网格持仓
+pub async fn stop_grid(
+    Extension(user_id): Extension<String>,
+    Extension(database): Extension<DatabaseConnection>,
+    Extension(grids): Extension<Arc<Grids>>,
+    Json(payload): Json<GridIdRequest>,
+) -> Result<impl IntoResponse, HandlerError> {
+    change_status(&grids, &database, payload.id, &user_id, |bot| {
+        if bot.status == GridStatus::Stopped {
+            return Err("Grid is already stopped".to_string());
+        }
+        bot.stop("Manual");
+        Ok(())
+    })
+    .await
+}
+
+// 只修改状态，撤单和平仓由网格运行任务完成
+async fn change_status(
+    grids: &Grids,
+    database: &DatabaseConnection,
+    id: i64,
+    user_id: &str,
+    change: impl FnOnce(&mut GridBot) -> Result<(), String>,
+) -> Result<Json<GridBot>, HandlerError> {
+    let Some(bot) = grids.get(id, user_id).await else {
+        return Err((StatusCode::BAD_REQUEST, "Grid is not active".to_string()));
+    };
+    let mut bot = bot.lock().await;
+    change(&mut bot).map_err(|e| (StatusCode::CONFLICT, e))?;
+    save_grid(database, &bot).await.map_err(db_error)?;
+    Ok(Json(bot.clone()))
+}
+
+// 启动时恢复未停止的网格，继续轮询重启前的挂单
+pub async fn resume_grids(
+    grids: Arc<Grids>,
+    prices: Arc<SymbolMap<Mutex<PriceBook>>>,
+    api_keys: Arc<KeyManager>,
+    database: DatabaseConnection,
+) -> Result<(), DbErr> {
+    for model in find_active_grids(&database).await? {
+        let bot = match GridBot::from_model(&model) {
+            Ok(bot) => bot,
+            Err(e) => {
+                eprintln!("网格 {} 无法恢复：{}", model.id, e);
+                continue;
+            }
+        };
+        if !prices.contains_key(&bot.symbol) {
+            eprintln!("网格 {} 的交易对 {} 未订阅，跳过。", bot.id, bot.symbol);
+            continue;
+        }
+        let key = match load_api_key(&api_keys, &database, &bot.owner_id).await {
+            Ok(()) => api_keys.get_key(&bot.owner_id),
+            Err((_, e)) => {
+                eprintln!("网格 {} 无法加载 API Key：{}", bot.id, e);
+                continue;
+            }
+        };
+        if let Some(key) = key {
+            start_grid(bot, grids.clone(), prices.clone(), key, database.clone()).await;
+        }
+    }
+    Ok(())
+}
+
+fn grid_detail(grid: GridBot, price: Option<Decimal>) -> GridDetail {
+    GridDetail {
+        holding: grid.holding(),
+        unrealized_profit: price.map(|p| grid.unrealized_profit(p)).unwrap_or_default(),
+        price,
+        grid,
+    }
+}
+
+fn db_error(e: DbErr) -> HandlerError {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        format!("Database error: {}", e),
+    )
+}
@@ -1,12 +1,25 @@
 use std::sync::Arc;
 
 use reqwest::StatusCode;
+use sea_orm::{DatabaseConnection, EntityTrait};
 
-use crate::secret_key::{KeyManager, SecretKey};
+use crate::{
+    orm::users,
+    secret_key::{KeyManager, SecretKey},
+};
 
 pub mod auth_handler;
+pub mod backtest_handler;
+pub mod equity_handler;
+pub mod grid_handler;
+pub mod paper_handler;
+pub mod preset_handler;
 pub mod record_handler;
+pub mod risk_handler;
+pub mod symbol_handler;
 pub mod trade_hander;
+pub mod trigger_handler;
+pub mod webhook_handler;
 
 pub async fn get_api_key(
     api_keys: Arc<KeyManager>,
@@ -20,3 +33,37 @@ pub async fn get_api_key(
         )),
     }
 }
+
+// 不经登录的入口（webhook、触发器）使用前从用户表加载 API Key
+pub async fn load_api_key(
+    api_keys: &KeyManager,
+    database: &DatabaseConnection,
+    user_id: &str,
+) -> Result<(), (StatusCode, String)> {
+    if api_keys.get_key(user_id).is_some() {
+        return Ok(());
+    }
+    let user = users::Entity::find_by_id(parse_owner_id(user_id)?)
+        .one(database)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Database error: {}", e),
+            )
+        })?
+        .ok_or((StatusCode::UNAUTHORIZED, "User not found".to_string()))?;
+    api_keys.insert_key(SecretKey::new(
+        user_id.to_string(),
+        user.apikey,
+        user.secret,
+    ));
+    Ok(())
+}
+
+// 认证中间件写入的用户 ID 为数据库中的整数主键
+pub fn parse_owner_id(user_id: &str) -> Result<i64, (StatusCode, String)> {
+    user_id
+        .parse::<i64>()
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user id".to_string()))
+}
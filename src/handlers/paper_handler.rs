@@ -0,0 +1,182 @@
+use std::sync::Arc;
+
+use axum::{extract::Query, http::StatusCode, response::IntoResponse, Extension, Json};
+use rust_decimal::Decimal;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, IntoActiveModel,
+    QueryFilter, QueryOrder, Set,
+};
+use tokio::sync::Mutex;
+
+use crate::{
+    models::{
+        paper_model::{
+            PaperAccountResponse, PaperResetResponse, PaperTradeRecord, UpdatePaperAccountRequest,
+        },
+        trade_model::TradeQueryParams,
+    },
+    orm::{paper_accounts, paper_trades},
+    symbol::SymbolMap,
+    trade::{
+        paper::{find_paper_account, paper_balance, paper_used_margin, PaperAccount},
+        Trade,
+    },
+    utils::unix_timestamp,
+};
+
+use super::parse_owner_id;
+
+type HandlerError = (StatusCode, String);
+
+pub async fn get_paper_account(
+    Extension(user_id): Extension<String>,
+    Extension(trades): Extension<Arc<SymbolMap<Mutex<Vec<Trade>>>>>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<impl IntoResponse, HandlerError> {
+    let account = find_paper_account(&database, &user_id)
+        .await
+        .map_err(db_error)?;
+    Ok(Json(
+        account_response(&database, &trades, &user_id, account).await?,
+    ))
+}
+
+pub async fn update_paper_account(
+    Extension(user_id): Extension<String>,
+    Extension(trades): Extension<Arc<SymbolMap<Mutex<Vec<Trade>>>>>,
+    Extension(database): Extension<DatabaseConnection>,
+    Json(payload): Json<UpdatePaperAccountRequest>,
+) -> Result<impl IntoResponse, HandlerError> {
+    let owner_id = parse_owner_id(&user_id)?;
+    let mut account = find_paper_account(&database, &user_id)
+        .await
+        .map_err(db_error)?;
+    if let Some(enabled) = payload.enabled {
+        account.enabled = enabled;
+    }
+    if let Some(initial_balance) = payload.initial_balance {
+        account.initial_balance = initial_balance;
+    }
+    if let Some(fee_rate) = payload.fee_rate {
+        account.fee_rate = fee_rate;
+    }
+    account
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    save_account(&database, owner_id, &account).await?;
+    Ok(Json(
+        account_response(&database, &trades, &user_id, account).await?,
+    ))
+}
+
+pub async fn get_paper_history(
+    Extension(user_id): Extension<String>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(params): Query<TradeQueryParams>,
+) -> Result<impl IntoResponse, HandlerError> {
+    let mut query =
+        paper_trades::Entity::find().filter(paper_trades::Column::OwnerId.eq(user_id.as_str()));
+    if let Some(symbol) = &params.symbol {
+        query = query.filter(paper_trades::Column::Symbol.eq(symbol.as_str()));
+    }
+    if let Some(start_time) = params.start_time {
+        query = query.filter(paper_trades::Column::CreatedAt.gte(start_time));
+    }
+    if let Some(end_time) = params.end_time {
+        query = query.filter(paper_trades::Column::CreatedAt.lte(end_time));
+    }
+    let records = query
+        .order_by_desc(paper_trades::Column::CreatedAt)
+        .order_by_desc(paper_trades::Column::Id)
+        .all(&database)
+        .await
+        .map_err(db_error)?;
+    let result: Vec<PaperTradeRecord> = records
+        .into_iter()
+        .map(|record| PaperTradeRecord {
+            record,
+            paper: true,
+        })
+        .collect();
+    Ok(Json(result))
+}
+
+// 清空模拟平仓记录，虚拟余额回到初始值；有进行中的模拟交易时拒绝
+pub async fn reset_paper_account(
+    Extension(user_id): Extension<String>,
+    Extension(trades): Extension<Arc<SymbolMap<Mutex<Vec<Trade>>>>>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<impl IntoResponse, HandlerError> {
+    if paper_used_margin(&trades, &user_id).await > Decimal::ZERO {
+        return Err((
+            StatusCode::CONFLICT,
+            "Close open paper trades before reset".to_string(),
+        ));
+    }
+    let result = paper_trades::Entity::delete_many()
+        .filter(paper_trades::Column::OwnerId.eq(user_id.as_str()))
+        .exec(&database)
+        .await
+        .map_err(db_error)?;
+    Ok(Json(PaperResetResponse {
+        paper: true,
+        deleted: result.rows_affected,
+    }))
+}
+
+async fn account_response(
+    database: &DatabaseConnection,
+    trades: &SymbolMap<Mutex<Vec<Trade>>>,
+    user_id: &str,
+    account: PaperAccount,
+) -> Result<PaperAccountResponse, HandlerError> {
+    let balance = paper_balance(database, user_id, &account)
+        .await
+        .map_err(db_error)?;
+    let used_margin = paper_used_margin(trades, user_id).await;
+    Ok(PaperAccountResponse {
+        paper: true,
+        enabled: account.enabled,
+        initial_balance: account.initial_balance,
+        fee_rate: account.fee_rate,
+        balance,
+        used_margin,
+        available: balance - used_margin,
+    })
+}
+
+async fn save_account(
+    database: &DatabaseConnection,
+    owner_id: i64,
+    account: &PaperAccount,
+) -> Result<(), HandlerError> {
+    let existing = paper_accounts::Entity::find_by_id(owner_id)
+        .one(database)
+        .await
+        .map_err(db_error)?;
+    let is_new = existing.is_none();
+    let mut active = match existing {
+        Some(model) => model.into_active_model(),
+        None => paper_accounts::ActiveModel {
+            owner_id: Set(owner_id),
+            ..Default::default()
+        },
+    };
+    active.enabled = Set(account.enabled);
+    active.initial_balance = Set(account.initial_balance.to_string());
+    active.fee_rate = Set(account.fee_rate.to_string());
+    active.updated_at = Set(unix_timestamp());
+    let result = if is_new {
+        active.insert(database).await
+    } else {
+        active.update(database).await
+    };
+    result.map(|_| ()).map_err(db_error)
+}
+
+fn db_error(e: DbErr) -> HandlerError {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        format!("Database error: {}", e),
+    )
+}
@@ -0,0 +1,255 @@
+use axum::{
+    extract::Query,
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Extension, Json,
+};
+use sea_orm::{
+    ActiveModelTrait, DatabaseConnection, DbErr, EntityTrait, IntoActiveModel, QueryFilter,
+    QueryOrder, Set,
+};
+
+use crate::{
+    models::preset_model::{
+        ClonePresetRequest, CreatePresetRequest, ExportPresetParams, ImportPresetParams,
+        PresetDocument, PresetFormat, PresetIdParams, PresetResponse, UpdatePresetRequest,
+    },
+    orm::adjustment_presets,
+    trade::{
+        preset::{find_preset, parse_adjustments, visible_to},
+        validate_adjustments, Adjustment,
+    },
+    utils::unix_timestamp,
+};
+
+use super::parse_owner_id;
+
+type HandlerError = (StatusCode, String);
+
+pub async fn list_presets(
+    Extension(user_id): Extension<String>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<impl IntoResponse, HandlerError> {
+    let owner_id = parse_owner_id(&user_id)?;
+    let presets = adjustment_presets::Entity::find()
+        .filter(visible_to(owner_id))
+        .order_by_asc(adjustment_presets::Column::Id)
+        .all(&database)
+        .await
+        .map_err(db_error)?;
+
+    let mut result = Vec::with_capacity(presets.len());
+    for preset in presets {
+        let adjustments =
+            parse_adjustments(&preset).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+        result.push(PresetResponse::new(preset, adjustments));
+    }
+    Ok(Json(result))
+}
+
+pub async fn get_preset(
+    Extension(user_id): Extension<String>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(params): Query<PresetIdParams>,
+) -> Result<impl IntoResponse, HandlerError> {
+    let owner_id = parse_owner_id(&user_id)?;
+    let preset = load_visible(&database, params.id, owner_id).await?;
+    let adjustments =
+        parse_adjustments(&preset).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    Ok(Json(PresetResponse::new(preset, adjustments)))
+}
+
+pub async fn create_preset(
+    Extension(user_id): Extension<String>,
+    Extension(database): Extension<DatabaseConnection>,
+    Json(payload): Json<CreatePresetRequest>,
+) -> Result<impl IntoResponse, HandlerError> {
+    let owner_id = parse_owner_id(&user_id)?;
+    let preset = insert_preset(&database, owner_id, payload.name, payload.adjustments).await?;
+    Ok(Json(preset))
+}
+
+pub async fn update_preset(
+    Extension(user_id): Extension<String>,
+    Extension(database): Extension<DatabaseConnection>,
+    Json(payload): Json<UpdatePresetRequest>,
+) -> Result<impl IntoResponse, HandlerError> {
+    let owner_id = parse_owner_id(&user_id)?;
+    let preset = load_owned(&database, payload.id, owner_id).await?;
+
+    let name = match payload.name {
+        Some(name) => validate_name(name)?,
+        None => preset.name.clone(),
+    };
+    let adjustments = match payload.adjustments {
+        Some(adjustments) => {
+            validate_adjustments(&adjustments).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+            adjustments
+        }
+        None => parse_adjustments(&preset).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?,
+    };
+
+    let mut active = preset.into_active_model();
+    active.name = Set(name);
+    active.adjustments = Set(serialize_adjustments(&adjustments)?);
+    active.updated_at = Set(unix_timestamp());
+    let preset = active.update(&database).await.map_err(db_error)?;
+    Ok(Json(PresetResponse::new(preset, adjustments)))
+}
+
+pub async fn delete_preset(
+    Extension(user_id): Extension<String>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(params): Query<PresetIdParams>,
+) -> Result<impl IntoResponse, HandlerError> {
+    let owner_id = parse_owner_id(&user_id)?;
+    // 进行中的交易在创建时已复制阶梯，删除预设不影响它们
+    let preset = load_owned(&database, params.id, owner_id).await?;
+    adjustment_presets::Entity::delete_by_id(preset.id)
+        .exec(&database)
+        .await
+        .map_err(db_error)?;
+    Ok(Json(PresetIdParams { id: preset.id }))
+}
+
+pub async fn clone_preset(
+    Extension(user_id): Extension<String>,
+    Extension(database): Extension<DatabaseConnection>,
+    Json(payload): Json<ClonePresetRequest>,
+) -> Result<impl IntoResponse, HandlerError> {
+    let owner_id = parse_owner_id(&user_id)?;
+    let source = load_visible(&database, payload.id, owner_id).await?;
+    let adjustments =
+        parse_adjustments(&source).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let name = payload.name.unwrap_or(source.name);
+    let preset = insert_preset(&database, owner_id, name, adjustments).await?;
+    Ok(Json(preset))
+}
+
+pub async fn export_preset(
+    Extension(user_id): Extension<String>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(params): Query<ExportPresetParams>,
+) -> Result<impl IntoResponse, HandlerError> {
+    let owner_id = parse_owner_id(&user_id)?;
+    let preset = load_visible(&database, params.id, owner_id).await?;
+    let adjustments =
+        parse_adjustments(&preset).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let document = PresetDocument {
+        name: preset.name,
+        adjustments,
+    };
+
+    let (content_type, body) = match params.format {
+        PresetFormat::Json => (
+            "application/json",
+            serde_json::to_string_pretty(&document).map_err(internal_error)?,
+        ),
+        PresetFormat::Toml => (
+            "application/toml",
+            toml::to_string_pretty(&document).map_err(internal_error)?,
+        ),
+    };
+    Ok(([(header::CONTENT_TYPE, content_type)], body))
+}
+
+pub async fn import_preset(
+    Extension(user_id): Extension<String>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(params): Query<ImportPresetParams>,
+    body: String,
+) -> Result<impl IntoResponse, HandlerError> {
+    let owner_id = parse_owner_id(&user_id)?;
+    let document: PresetDocument = match params.format {
+        PresetFormat::Json => serde_json::from_str(&body).map_err(bad_request)?,
+        PresetFormat::Toml => toml::from_str(&body).map_err(bad_request)?,
+    };
+    let preset = insert_preset(&database, owner_id, document.name, document.adjustments).await?;
+    Ok(Json(preset))
+}
+
+// 读取对当前用户可见的预设，并解析其阶梯配置
+pub async fn load_preset_adjustments(
+    database: &DatabaseConnection,
+    id: i64,
+    user_id: &str,
+) -> Result<Vec<Adjustment>, HandlerError> {
+    let owner_id = parse_owner_id(user_id)?;
+    let preset = load_visible(database, id, owner_id).await?;
+    parse_adjustments(&preset).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+async fn insert_preset(
+    database: &DatabaseConnection,
+    owner_id: i64,
+    name: String,
+    adjustments: Vec<Adjustment>,
+) -> Result<PresetResponse, HandlerError> {
+    let name = validate_name(name)?;
+    validate_adjustments(&adjustments).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let preset = adjustment_presets::ActiveModel {
+        owner_id: Set(Some(owner_id)),
+        name: Set(name),
+        adjustments: Set(serialize_adjustments(&adjustments)?),
+        ..Default::default()
+    }
+    .insert(database)
+    .await
+    .map_err(db_error)?;
+    Ok(PresetResponse::new(preset, adjustments))
+}
+
+async fn load_visible(
+    database: &DatabaseConnection,
+    id: i64,
+    owner_id: i64,
+) -> Result<adjustment_presets::Model, HandlerError> {
+    find_preset(database, id, owner_id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, format!("Preset {} not found", id)))
+}
+
+// 只能修改自己的预设，系统预设只读
+async fn load_owned(
+    database: &DatabaseConnection,
+    id: i64,
+    owner_id: i64,
+) -> Result<adjustment_presets::Model, HandlerError> {
+    let preset = load_visible(database, id, owner_id).await?;
+    if preset.owner_id != Some(owner_id) {
+        return Err((StatusCode::FORBIDDEN, format!("Preset {} is read-only", id)));
+    }
+    Ok(preset)
+}
+
+fn validate_name(name: String) -> Result<String, HandlerError> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Preset name must not be empty".to_string(),
+        ));
+    }
+    Ok(name)
+}
+
+fn serialize_adjustments(adjustments: &[Adjustment]) -> Result<String, HandlerError> {
+    serde_json::to_string(adjustments).map_err(internal_error)
+}
+
+fn db_error(e: DbErr) -> HandlerError {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        format!("Database error: {}", e),
+    )
+}
+
+fn internal_error(e: impl std::fmt::Display) -> HandlerError {
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+fn bad_request(e: impl std::fmt::Display) -> HandlerError {
+    (StatusCode::BAD_REQUEST, format!("Invalid preset: {}", e))
+}
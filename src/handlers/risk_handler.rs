@@ -0,0 +1,340 @@
+use std::sync::Arc;
+
+use axum::{http::StatusCode, response::IntoResponse, Extension, Json};
+use rust_decimal::Decimal;
+use sea_orm::{ActiveModelTrait, DatabaseConnection, DbErr, IntoActiveModel, Set};
+use tokio::sync::Mutex;
+
+use crate::{
+    binance::{account::get_order_api, order::cancel_order},
+    grid::{save_grid, GridStatus, Grids},
+    models::risk_model::{KillSwitchResponse, RiskPolicyResponse},
+    orm::risk_policies,
+    secret_key::{KeyManager, SecretKey},
+    symbol::SymbolMap,
+    trade::{
+        close_with_retry,
+        event::{EventBus, TradeEventKind},
+        price::PriceBook,
+        risk::{
+            check_entry, closed_records_since, consecutive_losses, find_policy, parse_policy,
+            recent_closed_records, record_net_pnl, RiskPolicy,
+        },
+        CloseReason, Trade, TradeStatus,
+    },
+    utils::{parse_decimal, unix_timestamp},
+};
+
+use super::{get_api_key, parse_owner_id};
+
+type HandlerError = (StatusCode, String);
+
+const SECONDS_PER_DAY: u32 = 86_400;
+
+pub async fn get_risk_policy(
+    Extension(user_id): Extension<String>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<impl IntoResponse, HandlerError> {
+    let owner_id = parse_owner_id(&user_id)?;
+    let result = match find_policy(&database, owner_id).await.map_err(db_error)? {
+        Some(model) => RiskPolicyResponse {
+            policy: parse_policy(&model).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?,
+            kill_switch: model.kill_switch,
+            updated_at: model.updated_at,
+        },
+        None => RiskPolicyResponse {
+            policy: RiskPolicy::default(),
+            kill_switch: false,
+            updated_at: 0,
+        },
+    };
+    Ok(Json(result))
+}
+
+// 整体替换风控策略，急停状态保持不变
+pub async fn update_risk_policy(
+    Extension(user_id): Extension<String>,
+    Extension(database): Extension<DatabaseConnection>,
+    Json(policy): Json<RiskPolicy>,
+) -> Result<impl IntoResponse, HandlerError> {
+    let owner_id = parse_owner_id(&user_id)?;
+    policy
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let document = serde_json::to_string(&policy)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let model = save_policy(&database, owner_id, Some(document), None).await?;
+    Ok(Json(RiskPolicyResponse {
+        policy,
+        kill_switch: model.kill_switch,
+        updated_at: model.updated_at,
+    }))
+}
+
+// 急停：禁止新开仓，撤销未成交的开仓单并市价平掉该用户所有托管交易，同时停止该用户的网格
+#[allow(clippy::too_many_arguments)]
+pub async fn activate_kill_switch(
+    Extension(user_id): Extension<String>,
+    Extension(api_keys): Extension<Arc<KeyManager>>,
+    Extension(trades): Extension<Arc<SymbolMap<Mutex<Vec<Trade>>>>>,
+    Extension(prices): Extension<Arc<SymbolMap<Mutex<PriceBook>>>>,
+    Extension(database): Extension<DatabaseConnection>,
+    Extension(events): Extension<EventBus>,
+    Extension(grids): Extension<Arc<Grids>>,
+) -> Result<impl IntoResponse, HandlerError> {
+    let owner_id = parse_owner_id(&user_id)?;
+    let key = get_api_key(api_keys, &user_id).await?;
+    // 先落库，平仓过程中的开仓请求也会被拒绝
+    save_policy(&database, owner_id, None, Some(true)).await?;
+
+    let mut result = KillSwitchResponse {
+        cancelled: 0,
+        closing: 0,
+        grids_stopped: 0,
+    };
+    // 网格只修改状态，撤单和平仓由网格运行任务完成
+    for bot in grids.owned_by(&user_id).await {
+        let mut bot = bot.lock().await;
+        if bot.status == GridStatus::Stopped {
+            continue;
+        }
+        bot.stop("Kill switch");
+        if let Err(e) = save_grid(&database, &bot).await {
+            eprintln!("网格 {} 状态写入失败：{}", bot.id, e);
+        }
+        result.grids_stopped += 1;
+    }
+
+    // 持锁时只收集待撤单和待平仓的交易，撤单和平仓都在释放锁后的后台任务中进行
+    let mut to_cancel = Vec::new();
+    let mut to_close = Vec::new();
+    for (symbol, mutex_vec) in trades.entries() {
+        // 成交均价缺失时用盘口价记录
+        let book = match prices.get(&symbol) {
+            Some(mutex) => Some(mutex.lock().await.clone()),
+            None => None,
+        };
+        let mut trade_list = mutex_vec.lock().await;
+        for trade in trade_list
+            .iter_mut()
+            .filter(|t| t.owner_id == user_id && !t.is_closed())
+        {
+            let price = book
+                .as_ref()
+                .and_then(|b| b.exit_price(&trade.direction))
+                .unwrap_or_default();
+            // 尚未成交的开仓单撤单即结束，已部分成交的继续平掉成交部分
+            if trade.status == TradeStatus::Pending && trade.remaining_quantity <= Decimal::ZERO {
+                to_cancel.push((symbol.clone(), trade.id, trade.order_id, price));
+                result.cancelled += 1;
+                continue;
+            }
+            if trade.status == TradeStatus::Closing {
+                continue;
+            }
+            // 平仓前需要先撤销的订单：部分成交的开仓单和交易所托管的止损单
+            let mut orders = Vec::new();
+            if trade.status == TradeStatus::Pending {
+                orders.push(trade.order_id);
+            }
+            if trade.stop_strategy.is_exchange_managed() {
+                orders.push(trade.stop_order);
+            }
+
+            trade.status = TradeStatus::Closing;
+            events.publish(
+                trade,
+                TradeEventKind::CloseRequested {
+                    price,
+                    reason: CloseReason::KillSwitch,
+                },
+            );
+            to_close.push((symbol.clone(), trade.id, price, orders));
+            result.closing += 1;
+        }
+    }
+
+    for (symbol, trade_id, order_id, price) in to_cancel {
+        tokio::spawn(cancel_pending_entry(
+            trades.clone(),
+            events.clone(),
+            key.clone(),
+            symbol,
+            trade_id,
+            order_id,
+            price,
+        ));
+    }
+    for (symbol, trade_id, price, orders) in to_close {
+        let trades = trades.clone();
+        let events = events.clone();
+        let key = key.clone();
+        tokio::spawn(async move {
+            for order_id in orders {
+                let _ = cancel_order(&symbol, order_id, &key.api_key, &key.api_secret).await;
+            }
+            close_with_retry(
+                trades,
+                events,
+                symbol,
+                trade_id,
+                price,
+                CloseReason::KillSwitch,
+            )
+            .await;
+        });
+    }
+    Ok(Json(result))
+}
+
+// 急停撤销未成交的开仓单；撤单后读取最终成交量，撤单前已成交的部分继续平仓
+async fn cancel_pending_entry(
+    trades: Arc<SymbolMap<Mutex<Vec<Trade>>>>,
+    events: EventBus,
+    key: SecretKey,
+    symbol: String,
+    trade_id: usize,
+    order_id: u64,
+    price: Decimal,
+) {
+    if let Err(e) = cancel_order(&symbol, order_id, &key.api_key, &key.api_secret).await {
+        eprintln!(
+            "急停撤销开仓单失败，交易对 {}，交易 ID {}：{}",
+            symbol, trade_id, e
+        );
+        return;
+    }
+    let order = get_order_api(&symbol, order_id, &key.api_key, &key.api_secret)
+        .await
+        .ok();
+
+    let Some(mutex_vec) = trades.get(&symbol) else {
+        return;
+    };
+    {
+        let mut vec = mutex_vec.lock().await;
+        let Some(t) = vec.iter_mut().find(|t| {
+            t.id == trade_id && matches!(t.status, TradeStatus::Pending | TradeStatus::Open)
+        }) else {
+            return;
+        };
+        if let Some(order) = order {
+            t.apply_entry_fill(
+                parse_decimal(&order.avgPrice).unwrap_or_default(),
+                parse_decimal(&order.executedQty).unwrap_or_default(),
+            );
+        }
+        if t.status == TradeStatus::Pending {
+            t.status = TradeStatus::Closed;
+            return;
+        }
+        t.status = TradeStatus::Closing;
+        events.publish(
+            t,
+            TradeEventKind::CloseRequested {
+                price,
+                reason: CloseReason::KillSwitch,
+            },
+        );
+    }
+    close_with_retry(
+        trades,
+        events,
+        symbol,
+        trade_id,
+        price,
+        CloseReason::KillSwitch,
+    )
+    .await;
+}
+
+// 解除急停，不影响已平仓的交易
+pub async fn reset_kill_switch(
+    Extension(user_id): Extension<String>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<impl IntoResponse, HandlerError> {
+    let owner_id = parse_owner_id(&user_id)?;
+    save_policy(&database, owner_id, None, Some(false)).await?;
+    Ok(StatusCode::OK)
+}
+
+// 开仓前的账户级风控：急停、杠杆、当日亏损和连续亏损冷却；返回用户策略供后续检查持仓上限
+pub async fn check_account_risk(
+    database: &DatabaseConnection,
+    user_id: &str,
+    leverage: Decimal,
+) -> Result<Option<RiskPolicy>, HandlerError> {
+    let owner_id = parse_owner_id(user_id)?;
+    let Some(model) = find_policy(database, owner_id).await.map_err(db_error)? else {
+        return Ok(None);
+    };
+    if model.kill_switch {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Kill switch is active, new entries are blocked".to_string(),
+        ));
+    }
+    let policy = parse_policy(&model).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    let now = unix_timestamp();
+    let daily_pnl = match policy.daily_loss_limit {
+        Some(_) => closed_records_since(database, user_id, now - now % SECONDS_PER_DAY)
+            .await
+            .map_err(db_error)?
+            .iter()
+            .map(record_net_pnl)
+            .sum(),
+        None => Decimal::ZERO,
+    };
+    let losses = match policy.max_consecutive_losses {
+        Some(_) => consecutive_losses(
+            &recent_closed_records(database, user_id)
+                .await
+                .map_err(db_error)?,
+        ),
+        None => (0, None),
+    };
+    check_entry(&policy, leverage, daily_pnl, losses, now)
+        .map_err(|e| (StatusCode::FORBIDDEN, e))?;
+    Ok(Some(policy))
+}
+
+// 写入策略或急停状态，未提供的部分保持不变；首次写入时策略为空
+async fn save_policy(
+    database: &DatabaseConnection,
+    owner_id: i64,
+    policy: Option<String>,
+    kill_switch: Option<bool>,
+) -> Result<risk_policies::Model, HandlerError> {
+    let existing = find_policy(database, owner_id).await.map_err(db_error)?;
+    let is_new = existing.is_none();
+    let mut active = match existing {
+        Some(model) => model.into_active_model(),
+        None => risk_policies::ActiveModel {
+            owner_id: Set(owner_id),
+            policy: Set("{}".to_string()),
+            kill_switch: Set(false),
+            ..Default::default()
+        },
+    };
+    if let Some(policy) = policy {
+        active.policy = Set(policy);
+    }
+    if let Some(kill_switch) = kill_switch {
+        active.kill_switch = Set(kill_switch);
+    }
+    active.updated_at = Set(unix_timestamp());
+    let result = if is_new {
+        active.insert(database).await
+    } else {
+        active.update(database).await
+    };
+    result.map_err(db_error)
+}
+
+fn db_error(e: DbErr) -> HandlerError {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        format!("Database error: {}", e),
+    )
+}
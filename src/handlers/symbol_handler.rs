@@ -0,0 +1,126 @@
+use std::{env, sync::Arc};
+
+use axum::{http::StatusCode, response::IntoResponse, Extension, Json};
+use sea_orm::{DatabaseConnection, DbErr, EntityTrait, QueryOrder};
+use tokio::sync::Mutex;
+
+use crate::{
+    binance::leverage::{get_symbol_filters, SymbolFilter},
+    grid::Grids,
+    models::symbol_model::SymbolRequest,
+    orm::symbols,
+    symbol::{normalize_symbol, save_symbol, SymbolMap},
+    trade::Trade,
+    websocket_lib::feed::MarketFeed,
+};
+
+type HandlerError = (StatusCode, String);
+
+// 停用过程中写入交易规则的状态
+const DISABLED_STATUS: &str = "DISABLED";
+
+pub async fn list_symbols(
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<impl IntoResponse, HandlerError> {
+    let result = symbols::Entity::find()
+        .order_by_asc(symbols::Column::Symbol)
+        .all(&database)
+        .await
+        .map_err(db_error)?;
+    Ok(Json(result))
+}
+
+// 启用交易对：读取交易规则后订阅行情
+pub async fn enable_symbol(
+    Extension(user_id): Extension<String>,
+    Extension(database): Extension<DatabaseConnection>,
+    Extension(filters): Extension<Arc<SymbolMap<SymbolFilter>>>,
+    Extension(feed): Extension<Arc<MarketFeed>>,
+    Json(payload): Json<SymbolRequest>,
+) -> Result<impl IntoResponse, HandlerError> {
+    require_admin(&user_id)?;
+    let symbol = normalize_symbol(&payload.symbol).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let filter = get_symbol_filters(std::slice::from_ref(&symbol))
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?
+        .remove(&symbol)
+        .ok_or((StatusCode::BAD_REQUEST, "Symbol not found".to_string()))?;
+    let model = save_symbol(&database, &symbol, true)
+        .await
+        .map_err(db_error)?;
+    filters.replace(&symbol, filter);
+    feed.start(&symbol);
+    Ok(Json(model))
+}
+
+// 停用交易对：有进行中的交易或网格时拒绝
+pub async fn disable_symbol(
+    Extension(user_id): Extension<String>,
+    Extension(database): Extension<DatabaseConnection>,
+    Extension(trades): Extension<Arc<SymbolMap<Mutex<Vec<Trade>>>>>,
+    Extension(filters): Extension<Arc<SymbolMap<SymbolFilter>>>,
+    Extension(grids): Extension<Arc<Grids>>,
+    Extension(feed): Extension<Arc<MarketFeed>>,
+    Json(payload): Json<SymbolRequest>,
+) -> Result<impl IntoResponse, HandlerError> {
+    require_admin(&user_id)?;
+    let symbol = normalize_symbol(&payload.symbol).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    if symbols::Entity::find_by_id(symbol.as_str())
+        .one(&database)
+        .await
+        .map_err(db_error)?
+        .is_none()
+    {
+        return Err((StatusCode::BAD_REQUEST, "Symbol not found".to_string()));
+    }
+    // 先标记为停用，检查期间新的开仓请求会被 check_symbol_trading 拒绝；检查不通过时恢复
+    let previous = filters.get(&symbol);
+    if let Some(filter) = &previous {
+        filters.replace(
+            &symbol,
+            SymbolFilter {
+                status: DISABLED_STATUS.to_string(),
+                ..(**filter).clone()
+            },
+        );
+    }
+    let restore = || {
+        if let Some(filter) = &previous {
+            filters.replace(&symbol, (**filter).clone());
+        }
+    };
+    if grids.has_symbol(&symbol).await {
+        restore();
+        return Err((StatusCode::CONFLICT, "Symbol has active grids".to_string()));
+    }
+    // 持有交易列表的锁直到移除，避免检查后又有新开仓
+    if let Some(mutex_vec) = trades.get(&symbol) {
+        let vec = mutex_vec.lock().await;
+        if vec.iter().any(|t| !t.is_closed()) {
+            restore();
+            return Err((StatusCode::CONFLICT, "Symbol has open trades".to_string()));
+        }
+        feed.stop(&symbol);
+    }
+    filters.remove(&symbol);
+    let model = save_symbol(&database, &symbol, false)
+        .await
+        .map_err(db_error)?;
+    Ok(Json(model))
+}
+
+// 管理员为 ADMIN_USER_IDS 中列出的用户，逗号分隔
+fn require_admin(user_id: &str) -> Result<(), HandlerError> {
+    let admins = env::var("ADMIN_USER_IDS").unwrap_or_default();
+    if admins.split(',').any(|id| id.trim() == user_id) {
+        return Ok(());
+    }
+    Err((StatusCode::FORBIDDEN, "Admin only".to_string()))
+}
+
+fn db_error(e: DbErr) -> HandlerError {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        format!("Database error: {}", e),
+    )
+}
@@ -1,57 +1,144 @@
-use std::{collections::HashMap, f32::consts::E, sync::Arc};
+use std::{collections::HashMap, convert::Infallible, f32::consts::E, sync::Arc};
 
-use axum::{extract::Query, http::StatusCode, response::IntoResponse, Extension, Json};
+use axum::{
+    extract::Query,
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
+    Extension, Json,
+};
+use futures_util::{stream, Stream};
 use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
 
+use rust_decimal::{prelude::ToPrimitive, Decimal};
 use serde::{Deserialize, Serialize};
-use tokio::sync::Mutex;
+use tokio::{
+    sync::{broadcast::error::RecvError, Mutex},
+    time::{Duration, Instant},
+};
 
 // use validator::Validate;
 
 use crate::{
     binance::{
-        account::{get_order_api, get_risk, Position},
-        leverage::change_leverage,
-        order::create_order,
+        account::{get_account_balance, get_commission_rate, get_order_api, get_risk, Position},
+        leverage::{
+            change_leverage, get_leverage_brackets, get_symbol_config, max_notional_for_leverage,
+            LeverageBracket, MarginType, SymbolFilter,
+        },
+        market::{get_klines, Kline},
+        order::{
+            cancel_order, create_order, create_order_with_options, OrderOptions, OrderResponse,
+        },
     },
+    grid::Grids,
+    models::preset_model::UpdatePresetRequest,
     models::trade_model::{
-        CloseTradeRequest, CloseTradeResponse, CreateTradeRequest, CreateTradeResponse,
+        AddToTradeRequest, AddToTradeResponse, AnalyticsQueryParams, CloseTradeRequest,
+        CloseTradeResponse, CreateTradeRequest, CreateTradeResponse, EntryOrder, LiquidationGuard,
+        ModifyTradeRequest, PartialCloseRequest, PartialCloseResponse, SimulateStopRequest,
+        SimulateStopResponse, SimulatedExit, SizingMode, TakeProfitRequest, TradeLegQueryParams,
         TradeQueryParams,
     },
-    orm::trades,
+    orm::{trade_legs, trades},
     secret_key::{KeyManager, SecretKey},
-    trade::{create_trade_record, Adjustment, AdjustmentConfig, Trade, TradeDirection},
-    utils::TradeIdGenerator,
+    symbol::SymbolMap,
+    trade::{
+        analytics::{analyze, group_closed_trades},
+        build_take_profits, calculate_stop_price, create_leg_record,
+        event::{EventBus, TradeEventKind},
+        exchange::{Exchange, PaperExchange, TradeExchange},
+        liquidation::{
+            liquidation_distance, max_stop_loss_percent, stop_before_liquidation,
+            LiquidationContext,
+        },
+        paper::{find_paper_account, paper_balance, paper_used_margin, PaperAccount},
+        price::{PriceBook, PriceSource},
+        risk::{check_exposure, user_exposure, Exposure},
+        round_stop_price,
+        sizing::{check_risk_limits, risk_quantity, RiskContext, DEFAULT_TAKER_FEE},
+        strategy::{build_stop_strategy, StopStrategyConfig},
+        validate_adjustments, watch_exchange_stop, watch_pending_entry, Adjustment, CloseReason,
+        Trade, TradeDirection, TradeParams, TradeStatus,
+    },
+    utils::{parse_decimal, round_to_step, round_to_tick, TradeIdGenerator},
 };
 
 use crate::routes::error::AppError;
 
-use super::get_api_key;
+use super::{
+    get_api_key,
+    preset_handler::{load_preset_adjustments, update_preset},
+    risk_handler::check_account_risk,
+};
 
 // 导入我们创建的 TradeIdGenerator
 
+#[allow(clippy::too_many_arguments)]
 pub async fn create_trade(
     Extension(user_id): Extension<String>,
     Extension(api_keys): Extension<Arc<KeyManager>>,
-    Extension(trades): Extension<Arc<HashMap<String, Mutex<Vec<Trade>>>>>,
-    Extension(prices): Extension<Arc<HashMap<String, Mutex<(String, String)>>>>,
-    Extension(precisions): Extension<Arc<HashMap<String, u8>>>,
+    Extension(trades): Extension<Arc<SymbolMap<Mutex<Vec<Trade>>>>>,
+    Extension(prices): Extension<Arc<SymbolMap<Mutex<PriceBook>>>>,
+    Extension(filters): Extension<Arc<SymbolMap<SymbolFilter>>>,
     Extension(id_generator): Extension<Arc<TradeIdGenerator>>,
-    Extension(adjustments): Extension<Arc<HashMap<u8, Mutex<AdjustmentConfig>>>>,
-    Json(payload): Json<CreateTradeRequest>,
+    Extension(database): Extension<DatabaseConnection>,
+    Extension(events): Extension<EventBus>,
+    Extension(grids): Extension<Arc<Grids>>,
+    Json(mut payload): Json<CreateTradeRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
     let key = get_api_key(api_keys, &user_id).await?;
+    validate_trade_request(&payload)?;
+    check_symbol_trading(&filters, &payload.symbol)?;
+    // 未指定 paper 时按模拟盘账户设置
+    let paper_account = find_paper_account(&database, &user_id).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Database error: {}", e),
+        )
+    })?;
+    if payload.paper.unwrap_or(paper_account.enabled) {
+        return create_paper_trade(
+            user_id,
+            &key,
+            trades,
+            &prices,
+            &filters,
+            &id_generator,
+            &database,
+            events,
+            payload,
+            paper_account,
+        )
+        .await;
+    }
+    // 按风险计算仓位时先读取账户权益、费率和杠杆分层
+    let brackets = load_symbol_brackets(&payload.symbol, &key).await;
+    let risk_context = load_risk_context(&payload, brackets.as_deref(), &key).await?;
+    // 预估强平价所需的保证金模式和账户权益，查询失败时不做强平校验
+    let liquidation_context = match brackets {
+        Some(brackets) => load_liquidation_context(&payload.symbol, brackets, &key).await,
+        None => None,
+    };
+    // 账户级风控：急停、杠杆、当日亏损和冷却期
+    let risk_policy = check_account_risk(&database, &user_id, payload.leverage).await?;
+    // 阶梯预设只在阶梯策略下使用，需在锁定盘口前读取
+    let adjustment = match payload.stop_strategy {
+        StopStrategyConfig::Ladder => {
+            load_preset_adjustments(&database, payload.adjustment_id, &user_id).await?
+        }
+        _ => Vec::new(),
+    };
     if let Some(mutex) = prices.get(&payload.symbol) {
-        let book = mutex.lock().await;
-
-        if let Some(mutex_config) = adjustments.get(&payload.adjustment_id) {
-            let config = mutex_config.lock().await;
-            let adjustment = config.adjustments.clone();
-            let _ = change_leverage(&payload.symbol, payload.leverage as u32);
+        // 复制盘口后立即释放锁，后续的 K 线、下单和持仓查询都是网络请求
+        let book = mutex.lock().await.clone();
 
-            // 获取精度，如果不存在则返回错误
-            let precision = match precisions.get(&payload.symbol) {
-                Some(&p) => p, // 解引用获取精度值
+        {
+            // 获取交易规则，如果不存在则返回错误
+            let filter = match filters.get(&payload.symbol) {
+                Some(f) => f,
                 None => {
                     return Err((
                         StatusCode::BAD_REQUEST,
@@ -62,23 +149,132 @@ pub async fn create_trade(
                 }
             };
 
-            let (side, position_side, price) = match payload.direction {
-                TradeDirection::Long => ("BUY", "LONG", &book.0),
-                TradeDirection::Short => ("SELL", "SHORT", &book.1),
+            let (side, position_side) = match payload.direction {
+                TradeDirection::Long => ("BUY", "LONG"),
+                TradeDirection::Short => ("SELL", "SHORT"),
             };
-            let price_f64: f64 = price.parse().unwrap();
-            let quantity = calculate_quantity(&payload, price_f64, precision);
+            let market_price = match book.entry_price(&payload.direction) {
+                Some(p) => p,
+                None => {
+                    return Err((
+                        StatusCode::BAD_REQUEST,
+                        "Market price not available".to_string(),
+                    ))
+                }
+            };
+            round_entry_prices(&mut payload, &filter);
+            validate_entry(&payload, &book)?;
+            // 非市价开仓按预期开仓价计算数量和止盈
+            let reference_price = payload.entry.reference_price().unwrap_or(market_price);
+            validate_take_profits(&payload.take_profits, &payload.direction, reference_price)?;
+            let quantity = size_trade(
+                &mut payload,
+                reference_price,
+                &filter,
+                risk_context.as_ref(),
+            )?;
+            if quantity <= Decimal::ZERO || quantity < filter.min_qty {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    format!("Quantity {} below minimum {}", quantity, filter.min_qty),
+                ));
+            }
+            if let Some(policy) = &risk_policy {
+                let exposure = user_exposure(&trades, &grids, &user_id, &payload.symbol).await;
+                check_exposure(policy, &exposure, quantity * reference_price)
+                    .map_err(|e| (StatusCode::FORBIDDEN, e))?;
+            }
+            let liquidation = guard_liquidation(
+                &mut payload,
+                reference_price,
+                quantity,
+                &filter,
+                liquidation_context.as_ref(),
+            )?;
             // 确定方向
 
+            let klines = load_strategy_klines(&payload.stop_strategy, &payload.symbol).await?;
+            let stop_strategy = build_stop_strategy(&payload.stop_strategy, adjustment, &klines);
+
+            // 下单前用用户的 API Key 设置交易对杠杆，失败时不开仓，避免按账户原有杠杆成交
+            let leverage = payload
+                .leverage
+                .to_u32()
+                .ok_or((StatusCode::BAD_REQUEST, "Invalid leverage".to_string()))?;
+            change_leverage(&payload.symbol, leverage, &key.api_key, &key.api_secret)
+                .await
+                .map_err(|e| {
+                    (
+                        StatusCode::BAD_REQUEST,
+                        format!("Change leverage failed: {}", e),
+                    )
+                })?;
+
+            // 限价、只做 Maker 和条件开仓：挂单后创建 Pending 交易，由 watch_pending_entry 激活
+            if !matches!(payload.entry, EntryOrder::Market) {
+                let order = place_entry_order(&payload, quantity, &key)
+                    .await
+                    .map_err(|e| (StatusCode::BAD_REQUEST, format!("Order failed: {}", e)))?;
+                let take_profits = build_take_profits(
+                    &payload.take_profits,
+                    &payload.direction,
+                    payload.leverage,
+                    reference_price,
+                    &filter,
+                );
+                let id = id_generator.next_id();
+                let mut t = Trade::new(
+                    TradeParams {
+                        id,
+                        owner_id: user_id.clone(),
+                        order_id: order.orderId,
+                        symbol: payload.symbol.clone(),
+                        entry_price: reference_price,
+                        direction: payload.direction.clone(),
+                        quantity,
+                        leverage: payload.leverage,
+                        stop_loss_percent: payload.stop_loss_percent,
+                        stop_strategy,
+                        take_profits,
+                        price_source: payload.price_source,
+                        api_key: key.api_key.clone(),
+                        api_secret: key.api_secret.clone(),
+                    },
+                    &filter,
+                )
+                .await;
+                t.mark_pending();
+                t.adjustment_id = ladder_preset(&payload.stop_strategy, payload.adjustment_id);
+
+                let Some(mutex_vec) = trades.get(&payload.symbol) else {
+                    return Err((StatusCode::BAD_REQUEST, "Failed to save trade".to_string()));
+                };
+                mutex_vec.lock().await.push(t.clone());
+
+                let expires_at = payload
+                    .entry_expiry_secs
+                    .map(|secs| Instant::now() + Duration::from_secs(secs));
+                tokio::spawn(watch_pending_entry(
+                    trades.clone(),
+                    events,
+                    payload.symbol.clone(),
+                    id,
+                    expires_at,
+                ));
+
+                let result = create_trade_response(payload, &t, quantity, liquidation);
+                return Ok((StatusCode::OK, Json(result)).into_response());
+            }
+
             // 调用 create_order 函数
             let order_response = create_order(
                 &payload.symbol,
                 side,
                 position_side,
-                "MARKET",  // 假设使用市价单
-                &quantity, // 将数量格式化为字符串
-                None,      // 市价单无需价格
-                None,      // 此示例未设置止损价格
+                "MARKET",              // 假设使用市价单
+                &quantity.to_string(), // 将数量格式化为字符串
+                None,                  // 市价单无需价格
+                None,                  // 此示例未设置止损价格
                 &key.api_key,
                 &key.api_secret,
             )
@@ -95,40 +291,84 @@ pub async fn create_trade(
                     .await
                     {
                         Ok(b_order) => {
-                            let price_f64: f64 = b_order.avgPrice.parse().unwrap();
+                            // 成交均价异常时退回到下单前的盘口价
+                            let entry_price = parse_decimal(&b_order.avgPrice)
+                                .filter(|p| *p > Decimal::ZERO)
+                                .unwrap_or(market_price);
+                            let take_profits = build_take_profits(
+                                &payload.take_profits,
+                                &payload.direction,
+                                payload.leverage,
+                                entry_price,
+                                &filter,
+                            );
                             // 获取订单 ID
                             let id = id_generator.next_id(); // 使用 id_generator 获取自增的 id
-                            let t = Trade::new(
-                                id,
-                                user_id.clone(),
-                                order.orderId,
-                                payload.symbol.clone(),
-                                price_f64,
-                                payload.direction.clone(),
-                                quantity.clone(),
-                                payload.leverage,
-                                payload.stop_loss_percent,
-                                adjustment,
-                                key.api_key.clone(),
-                                key.api_secret.clone(),
+                            let mut t = Trade::new(
+                                TradeParams {
+                                    id,
+                                    owner_id: user_id.clone(),
+                                    order_id: order.orderId,
+                                    symbol: payload.symbol.clone(),
+                                    entry_price,
+                                    direction: payload.direction.clone(),
+                                    quantity,
+                                    leverage: payload.leverage,
+                                    stop_loss_percent: payload.stop_loss_percent,
+                                    stop_strategy,
+                                    take_profits,
+                                    price_source: payload.price_source,
+                                    api_key: key.api_key.clone(),
+                                    api_secret: key.api_secret.clone(),
+                                },
+                                &filter,
+                            )
+                            .await;
+                            t.adjustment_id =
+                                ladder_preset(&payload.stop_strategy, payload.adjustment_id);
+
+                            // 交易所托管的跟踪止损：下 TRAILING_STOP_MARKET 单并轮询成交
+                            let exchange_stop = place_exchange_trailing_stop(
+                                &payload.symbol,
+                                &payload.direction,
+                                &payload.stop_strategy,
+                                payload.price_source,
+                                quantity,
+                                &key,
                             )
                             .await;
+                            if let Some(stop_order_id) = exchange_stop {
+                                t.stop_order = stop_order_id;
+                            }
+
+                            // 成交后以交易所持仓的强平价为准
+                            let liquidation = position_liquidation_price(&t, position_side, &key)
+                                .await
+                                .or(liquidation);
 
                             // 保存交易
                             if let Some(mutex_vec) = trades.get(&payload.symbol) {
                                 let mut vec = mutex_vec.lock().await;
                                 vec.push(t.clone());
+                                events.publish(
+                                    &t,
+                                    TradeEventKind::TradeOpened {
+                                        quantity,
+                                        stop_loss: t.stop_loss,
+                                    },
+                                );
 
-                                let result = CreateTradeResponse {
-                                    id,
-                                    symbol: payload.symbol,
-                                    direction: payload.direction,
-                                    leverage: payload.leverage,
-                                    margin: payload.margin,
-                                    quantity,
-                                    entry_price: b_order.avgPrice.clone(),
-                                    stop_price: format!("{:.4}", t.stop_loss),
-                                };
+                                if exchange_stop.is_some() {
+                                    tokio::spawn(watch_exchange_stop(
+                                        trades.clone(),
+                                        events,
+                                        payload.symbol.clone(),
+                                        id,
+                                    ));
+                                }
+
+                                let result =
+                                    create_trade_response(payload, &t, quantity, liquidation);
                                 Ok((StatusCode::OK, Json(result)).into_response())
                             } else {
                                 Err((StatusCode::BAD_REQUEST, "Failed to save trade".to_string()))
@@ -142,37 +382,680 @@ pub async fn create_trade(
                     Err((StatusCode::BAD_REQUEST, format!("Order failed: {}", e)))
                 }
             }
-        } else {
-            Err((StatusCode::BAD_REQUEST, "failed, symbol".to_string()))
         }
     } else {
         Err((StatusCode::BAD_REQUEST, "failed, symbol".to_string()))
     }
 }
 
+// 模拟盘开仓：使用实时盘口和完整的交易引擎，按盘口价成交，不向交易所下单。
+// 保证金从虚拟余额中占用，按逐仓预估强平价；账户风控只作用于实盘
+#[allow(clippy::too_many_arguments)]
+async fn create_paper_trade(
+    user_id: String,
+    key: &SecretKey,
+    trades: Arc<SymbolMap<Mutex<Vec<Trade>>>>,
+    prices: &SymbolMap<Mutex<PriceBook>>,
+    filters: &SymbolMap<SymbolFilter>,
+    id_generator: &TradeIdGenerator,
+    database: &DatabaseConnection,
+    events: EventBus,
+    mut payload: CreateTradeRequest,
+    account: PaperAccount,
+) -> Result<axum::response::Response, (StatusCode, String)> {
+    if !matches!(payload.entry, EntryOrder::Market) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Paper trades only support market entry".to_string(),
+        ));
+    }
+    if matches!(
+        payload.stop_strategy,
+        StopStrategyConfig::ExchangeTrailing { .. }
+    ) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Paper trades cannot use an exchange-managed stop".to_string(),
+        ));
+    }
+    let balance = paper_balance(database, &user_id, &account)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Database error: {}", e),
+            )
+        })?;
+    let available = balance - paper_used_margin(&trades, &user_id).await;
+    // 杠杆分层为公开规则，仍从交易所读取
+    let brackets = load_symbol_brackets(&payload.symbol, key).await;
+    let risk_context = match payload.sizing {
+        SizingMode::Margin => None,
+        _ => Some(RiskContext {
+            equity: balance,
+            available_balance: available,
+            fee_rate: account.fee_rate,
+            max_notional: brackets
+                .as_deref()
+                .and_then(|b| max_notional_for_leverage(b, payload.leverage.to_u32().unwrap_or(1))),
+        }),
+    };
+    let liquidation_context = brackets.map(|brackets| LiquidationContext {
+        brackets,
+        margin_type: MarginType::Isolated,
+        equity: Decimal::ZERO,
+    });
+    let adjustment = match payload.stop_strategy {
+        StopStrategyConfig::Ladder => {
+            load_preset_adjustments(database, payload.adjustment_id, &user_id).await?
+        }
+        _ => Vec::new(),
+    };
+
+    let (Some(mutex_book), Some(filter), Some(mutex_vec)) = (
+        prices.get(&payload.symbol),
+        filters.get(&payload.symbol),
+        trades.get(&payload.symbol),
+    ) else {
+        return Err((StatusCode::BAD_REQUEST, "failed, symbol".to_string()));
+    };
+    let book = mutex_book.lock().await.clone();
+    let market_price = book.entry_price(&payload.direction).ok_or((
+        StatusCode::BAD_REQUEST,
+        "Market price not available".to_string(),
+    ))?;
+    validate_take_profits(&payload.take_profits, &payload.direction, market_price)?;
+    let quantity = size_trade(&mut payload, market_price, &filter, risk_context.as_ref())?;
+    if quantity <= Decimal::ZERO || quantity < filter.min_qty {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Quantity {} below minimum {}", quantity, filter.min_qty),
+        ));
+    }
+    let margin = quantity * market_price / payload.leverage;
+    if margin > available {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Margin {} exceeds available paper balance {}",
+                margin.round_dp(2),
+                available.round_dp(2)
+            ),
+        ));
+    }
+    let liquidation = guard_liquidation(
+        &mut payload,
+        market_price,
+        quantity,
+        &filter,
+        liquidation_context.as_ref(),
+    )?;
+
+    let klines = load_strategy_klines(&payload.stop_strategy, &payload.symbol).await?;
+    let stop_strategy = build_stop_strategy(&payload.stop_strategy, adjustment, &klines);
+    let take_profits = build_take_profits(
+        &payload.take_profits,
+        &payload.direction,
+        payload.leverage,
+        market_price,
+        &filter,
+    );
+    let mut t = Trade::new(
+        TradeParams {
+            id: id_generator.next_id(),
+            owner_id: user_id,
+            order_id: PaperExchange::next_order_id(),
+            symbol: payload.symbol.clone(),
+            entry_price: market_price,
+            direction: payload.direction.clone(),
+            quantity,
+            leverage: payload.leverage,
+            stop_loss_percent: payload.stop_loss_percent,
+            stop_strategy,
+            take_profits,
+            price_source: payload.price_source,
+            api_key: key.api_key.clone(),
+            api_secret: key.api_secret.clone(),
+        },
+        &filter,
+    )
+    .await;
+    t.paper = true;
+    t.adjustment_id = ladder_preset(&payload.stop_strategy, payload.adjustment_id);
+
+    mutex_vec.lock().await.push(t.clone());
+    events.publish(
+        &t,
+        TradeEventKind::TradeOpened {
+            quantity,
+            stop_loss: t.stop_loss,
+        },
+    );
+    let result = create_trade_response(payload, &t, quantity, liquidation);
+    Ok((StatusCode::OK, Json(result)).into_response())
+}
+
+fn create_trade_response(
+    trade_request: CreateTradeRequest,
+    trade: &Trade,
+    quantity: Decimal,
+    liquidation: Option<Decimal>,
+) -> CreateTradeResponse {
+    CreateTradeResponse {
+        id: trade.id,
+        symbol: trade_request.symbol,
+        direction: trade_request.direction,
+        leverage: trade_request.leverage,
+        margin: trade_request.margin,
+        quantity,
+        entry_price: trade.entry_price,
+        stop_price: trade.stop_loss,
+        stop_strategy: trade.stop_strategy.config(),
+        take_profits: trade.take_profits.clone(),
+        price_source: trade.price_source,
+        status: trade.status,
+        liquidation_price: liquidation,
+        liquidation_distance: liquidation.map(|l| liquidation_distance(trade.entry_price, l)),
+        paper: trade.paper,
+    }
+}
+
+// 开仓价格按最小价格单位取整，取整方向偏保守：做多向下、做空向上（条件单相反）
+fn round_entry_prices(trade_request: &mut CreateTradeRequest, filter: &SymbolFilter) {
+    let is_long = trade_request.direction == TradeDirection::Long;
+    match &mut trade_request.entry {
+        EntryOrder::Market => {}
+        EntryOrder::Limit { price, .. } => {
+            *price = round_to_tick(*price, filter.tick_size, !is_long);
+        }
+        EntryOrder::Stop { stop_price, price } => {
+            *stop_price = round_to_tick(*stop_price, filter.tick_size, is_long);
+            if let Some(price) = price {
+                *price = round_to_tick(*price, filter.tick_size, !is_long);
+            }
+        }
+    }
+}
+
+// 校验非市价开仓参数
+fn validate_entry(
+    trade_request: &CreateTradeRequest,
+    book: &PriceBook,
+) -> Result<(), (StatusCode, String)> {
+    let reject = |message: &str| Err((StatusCode::BAD_REQUEST, message.to_string()));
+    if trade_request.entry_expiry_secs == Some(0) {
+        return reject("entry_expiry_secs must be positive");
+    }
+
+    let is_long = trade_request.direction == TradeDirection::Long;
+    match &trade_request.entry {
+        EntryOrder::Market => Ok(()),
+        // 托管跟踪止损需要在开仓时按成交数量下单
+        _ if matches!(
+            trade_request.stop_strategy,
+            StopStrategyConfig::ExchangeTrailing { .. }
+        ) =>
+        {
+            reject("ExchangeTrailing requires a market entry")
+        }
+        EntryOrder::Limit { price, post_only } => {
+            if *price <= Decimal::ZERO {
+                return reject("Limit price must be positive");
+            }
+            // 只做 Maker 的挂单不能与盘口成交
+            let crosses = match book.entry_price(&trade_request.direction) {
+                Some(market) if is_long => *price >= market,
+                Some(market) => *price <= market,
+                None => false,
+            };
+            if *post_only && crosses {
+                return reject("Post-only limit price would cross the book");
+            }
+            Ok(())
+        }
+        EntryOrder::Stop { stop_price, price } => {
+            if *stop_price <= Decimal::ZERO || price.is_some_and(|p| p <= Decimal::ZERO) {
+                return reject("Stop entry prices must be positive");
+            }
+            // 条件开仓价必须位于突破方向，否则会立即触发
+            let triggered = match book.entry_price(&trade_request.direction) {
+                Some(market) if is_long => *stop_price <= market,
+                Some(market) => *stop_price >= market,
+                None => false,
+            };
+            if triggered {
+                return reject("Stop entry price would trigger immediately");
+            }
+            Ok(())
+        }
+    }
+}
+
+// 下非市价开仓单
+async fn place_entry_order(
+    trade_request: &CreateTradeRequest,
+    quantity: Decimal,
+    key: &SecretKey,
+) -> crate::error::Result<OrderResponse> {
+    let (side, position_side) = match trade_request.direction {
+        TradeDirection::Long => ("BUY", "LONG"),
+        TradeDirection::Short => ("SELL", "SHORT"),
+    };
+    let quantity = quantity.to_string();
+    let (order_type, price, stop_price, options) = match &trade_request.entry {
+        EntryOrder::Market => ("MARKET", None, None, OrderOptions::default()),
+        EntryOrder::Limit { price, post_only } => (
+            "LIMIT",
+            Some(price.to_string()),
+            None,
+            OrderOptions {
+                time_in_force: Some(if *post_only { "GTX" } else { "GTC" }),
+                ..Default::default()
+            },
+        ),
+        EntryOrder::Stop { stop_price, price } => (
+            if price.is_some() {
+                "STOP"
+            } else {
+                "STOP_MARKET"
+            },
+            price.map(|p| p.to_string()),
+            Some(stop_price.to_string()),
+            OrderOptions {
+                working_type: Some(trade_request.price_source.working_type()),
+                ..Default::default()
+            },
+        ),
+    };
+
+    create_order_with_options(
+        &trade_request.symbol,
+        side,
+        position_side,
+        order_type,
+        &quantity,
+        price.as_deref(),
+        stop_price.as_deref(),
+        options,
+        &key.api_key,
+        &key.api_secret,
+    )
+    .await
+}
+
+// 下交易所原生跟踪止损单，返回订单 ID；失败时仅保留本地止损
+async fn place_exchange_trailing_stop(
+    symbol: &str,
+    direction: &TradeDirection,
+    config: &StopStrategyConfig,
+    price_source: PriceSource,
+    quantity: Decimal,
+    key: &SecretKey,
+) -> Option<u64> {
+    let StopStrategyConfig::ExchangeTrailing {
+        callback_rate,
+        activation_price,
+    } = config
+    else {
+        return None;
+    };
+    let (side, position_side) = match direction {
+        TradeDirection::Long => ("SELL", "LONG"),
+        TradeDirection::Short => ("BUY", "SHORT"),
+    };
+    let callback_rate = callback_rate.to_string();
+    let activation_price = activation_price.map(|p| p.to_string());
+    let options = OrderOptions {
+        callback_rate: Some(&callback_rate),
+        activation_price: activation_price.as_deref(),
+        working_type: Some(price_source.working_type()),
+        ..Default::default()
+    };
+
+    match create_order_with_options(
+        symbol,
+        side,
+        position_side,
+        "TRAILING_STOP_MARKET",
+        &quantity.to_string(),
+        None,
+        None,
+        options,
+        &key.api_key,
+        &key.api_secret,
+    )
+    .await
+    {
+        Ok(order) => Some(order.orderId),
+        Err(e) => {
+            eprintln!("跟踪止损下单失败，交易对 {}：{}，仅保留本地止损", symbol, e);
+            None
+        }
+    }
+}
+
 pub fn calculate_quantity(
     trade_request: &CreateTradeRequest,
-    market_price: f64,
-    precision: u8,
-) -> String {
+    market_price: Decimal,
+    filter: &SymbolFilter,
+) -> Decimal {
     // 确保市场价格有效，避免除以 0
-    if market_price <= 0.0 {
-        return "0.0".to_string();
+    if market_price <= Decimal::ZERO {
+        return Decimal::ZERO;
     }
 
     // 计算可买数量
     let quantity = trade_request.margin * trade_request.leverage / market_price;
 
-    // 动态格式化数量，保留指定的精度
-    format!("{:.precision$}", quantity, precision = precision as usize)
+    // 按数量步长向下取整
+    round_to_step(quantity, filter.step_size)
+}
+
+// 按请求的仓位计算方式得到数量；按风险计算时校验限额并回填所需保证金
+fn size_trade(
+    trade_request: &mut CreateTradeRequest,
+    price: Decimal,
+    filter: &SymbolFilter,
+    risk_context: Option<&RiskContext>,
+) -> Result<Decimal, (StatusCode, String)> {
+    let (risk, context) = match (&trade_request.sizing, risk_context) {
+        (SizingMode::RiskAmount { amount }, Some(context)) => (*amount, context),
+        (SizingMode::RiskPercent { percent }, Some(context)) => (context.equity * percent, context),
+        _ => return Ok(calculate_quantity(trade_request, price, filter)),
+    };
+
+    let quantity = risk_quantity(
+        &trade_request.direction,
+        price,
+        trade_request.leverage,
+        trade_request.stop_loss_percent,
+        risk,
+        context.fee_rate,
+        filter,
+    );
+    check_risk_limits(quantity, price, trade_request.leverage, context, filter)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    trade_request.margin = quantity * price / trade_request.leverage;
+    Ok(quantity)
+}
+
+// 读取按风险计算仓位所需的账户数据；费率和杠杆分层查询失败时使用默认费率、不限制名义价值
+async fn load_risk_context(
+    trade_request: &CreateTradeRequest,
+    brackets: Option<&[LeverageBracket]>,
+    key: &SecretKey,
+) -> Result<Option<RiskContext>, (StatusCode, String)> {
+    if matches!(trade_request.sizing, SizingMode::Margin) {
+        return Ok(None);
+    }
+    let balance = get_account_balance(&key.api_key, &key.api_secret)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("Account balance failed: {}", e),
+            )
+        })?;
+    let fee_rate = get_commission_rate(&trade_request.symbol, &key.api_key, &key.api_secret)
+        .await
+        .map(|rate| rate.taker_commission_rate)
+        .unwrap_or(DEFAULT_TAKER_FEE);
+
+    let leverage = trade_request.leverage.to_u32().unwrap_or(1);
+    let max_notional = match brackets {
+        Some(brackets) => Some(max_notional_for_leverage(brackets, leverage).ok_or((
+            StatusCode::BAD_REQUEST,
+            format!("Leverage {} exceeds the symbol maximum", leverage),
+        ))?),
+        None => None,
+    };
+
+    Ok(Some(RiskContext {
+        equity: balance.total_margin_balance,
+        available_balance: balance.available_balance,
+        fee_rate,
+        max_notional,
+    }))
+}
+
+async fn load_symbol_brackets(symbol: &str, key: &SecretKey) -> Option<Vec<LeverageBracket>> {
+    get_leverage_brackets(symbol, &key.api_key, &key.api_secret)
+        .await
+        .ok()?
+        .into_iter()
+        .find(|b| b.symbol.eq_ignore_ascii_case(symbol))
+        .map(|b| b.brackets)
+}
+
+// 全仓时还需要账户权益；任一查询失败时返回 None
+async fn load_liquidation_context(
+    symbol: &str,
+    brackets: Vec<LeverageBracket>,
+    key: &SecretKey,
+) -> Option<LiquidationContext> {
+    let margin_type = get_symbol_config(symbol, &key.api_key, &key.api_secret)
+        .await
+        .ok()?
+        .into_iter()
+        .find(|c| c.symbol.eq_ignore_ascii_case(symbol))?
+        .margin_type;
+    let equity = match margin_type {
+        MarginType::Isolated => Decimal::ZERO,
+        MarginType::Cross => {
+            get_account_balance(&key.api_key, &key.api_secret)
+                .await
+                .ok()?
+                .total_margin_balance
+        }
+    };
+    Some(LiquidationContext {
+        brackets,
+        margin_type,
+        equity,
+    })
+}
+
+// 止损价越过预估强平价时按请求拒绝或收紧止损比例，返回预估强平价
+fn guard_liquidation(
+    trade_request: &mut CreateTradeRequest,
+    price: Decimal,
+    quantity: Decimal,
+    filter: &SymbolFilter,
+    context: Option<&LiquidationContext>,
+) -> Result<Option<Decimal>, (StatusCode, String)> {
+    let direction = &trade_request.direction;
+    let Some(liquidation) =
+        context.and_then(|c| c.estimate(direction, price, quantity, trade_request.leverage))
+    else {
+        return Ok(None);
+    };
+    let stop = round_stop_price(
+        direction,
+        calculate_stop_price(
+            direction,
+            price,
+            trade_request.leverage,
+            trade_request.stop_loss_percent,
+        ),
+        filter.tick_size,
+    );
+    if stop_before_liquidation(direction, stop, liquidation) {
+        return Ok(Some(liquidation));
+    }
+    match trade_request.liquidation_guard {
+        LiquidationGuard::Reject => Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Stop {} is beyond estimated liquidation price {}",
+                stop,
+                liquidation.round_dp(filter.tick_size.scale())
+            ),
+        )),
+        LiquidationGuard::Clamp => {
+            // 止损价取整可能放宽一个价格单位，预先扣除
+            let tick_percent = filter.tick_size * trade_request.leverage / price;
+            let percent =
+                max_stop_loss_percent(direction, price, trade_request.leverage, liquidation)
+                    - tick_percent;
+            if percent <= Decimal::ZERO {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    "No room for a stop before liquidation".to_string(),
+                ));
+            }
+            trade_request.stop_loss_percent = percent;
+            Ok(Some(liquidation))
+        }
+    }
+}
+
+// 交易所持仓的强平价，查询失败或为 0 时返回 None
+async fn position_liquidation_price(
+    trade: &Trade,
+    position_side: &str,
+    key: &SecretKey,
+) -> Option<Decimal> {
+    let positions = get_risk(&key.api_key, &key.api_secret).await.ok()?;
+    let liquidation = positions
+        .iter()
+        .find(|p| p.symbol.eq_ignore_ascii_case(&trade.symbol) && p.position_side == position_side)
+        .map(|p| p.liquidation_price)
+        .filter(|p| *p > Decimal::ZERO)?;
+    if !stop_before_liquidation(&trade.direction, trade.stop_loss, liquidation) {
+        eprintln!(
+            "止损价 {} 越过交易所强平价 {}，交易对 {}，交易 ID {}",
+            trade.stop_loss, liquidation, trade.symbol, trade.id
+        );
+    }
+    Some(liquidation)
+}
+
+// 阶梯策略记录使用的预设，写入平仓记录供统计筛选
+fn ladder_preset(config: &StopStrategyConfig, adjustment_id: i64) -> Option<i64> {
+    matches!(config, StopStrategyConfig::Ladder).then_some(adjustment_id)
+}
+
+// 交易所暂停交易或即将下架的交易对不再开仓和加仓，平仓不受影响
+pub fn check_symbol_trading(
+    filters: &SymbolMap<SymbolFilter>,
+    symbol: &str,
+) -> Result<(), (StatusCode, String)> {
+    match filters.get(symbol).and_then(|f| f.halt_reason()) {
+        Some(reason) => Err((
+            StatusCode::CONFLICT,
+            format!("Symbol {} is not trading: {}", symbol, reason),
+        )),
+        None => Ok(()),
+    }
+}
+
+// 校验请求中的数值参数，避免后续计算除以 0
+pub fn validate_trade_request(
+    trade_request: &CreateTradeRequest,
+) -> Result<(), (StatusCode, String)> {
+    if trade_request.leverage < Decimal::ONE || trade_request.stop_loss_percent <= Decimal::ZERO {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "leverage must be >= 1, stop_loss_percent must be positive".to_string(),
+        ));
+    }
+    let sizing_valid = match &trade_request.sizing {
+        SizingMode::Margin => trade_request.margin > Decimal::ZERO,
+        SizingMode::RiskAmount { amount } => *amount > Decimal::ZERO,
+        SizingMode::RiskPercent { percent } => *percent > Decimal::ZERO && *percent <= Decimal::ONE,
+    };
+    if !sizing_valid {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "margin or risk amount must be positive, risk percent within (0, 1]".to_string(),
+        ));
+    }
+    trade_request
+        .stop_strategy
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))
+}
+
+// 吊灯止损需要先获取 K 线计算开仓时的 ATR，之后由收盘 K 线更新
+async fn load_strategy_klines(
+    config: &StopStrategyConfig,
+    symbol: &str,
+) -> Result<Vec<Kline>, (StatusCode, String)> {
+    match config {
+        StopStrategyConfig::Chandelier {
+            interval, period, ..
+        } => {
+            let mut klines = get_klines(symbol, interval, (*period as u32 * 3 + 2).min(1500))
+                .await
+                .map_err(|e| (StatusCode::BAD_REQUEST, format!("Klines failed: {}", e)))?;
+            // 最后一根尚未收盘，收盘后由 K 线推送任务计入
+            klines.pop();
+            Ok(klines)
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+// 校验止盈目标：price 与 roi 二选一，比例合计不超过 1，价格必须位于盈利方向
+fn validate_take_profits(
+    take_profits: &[TakeProfitRequest],
+    direction: &TradeDirection,
+    market_price: Decimal,
+) -> Result<(), (StatusCode, String)> {
+    let mut total_fraction = Decimal::ZERO;
+    for tp in take_profits {
+        if tp.fraction <= Decimal::ZERO || tp.fraction > Decimal::ONE {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Take profit fraction must be in (0, 1]".to_string(),
+            ));
+        }
+        total_fraction += tp.fraction;
+
+        match (tp.price, tp.roi) {
+            (Some(price), None) => {
+                let profitable = match direction {
+                    TradeDirection::Long => price > market_price,
+                    TradeDirection::Short => price < market_price,
+                };
+                if !profitable {
+                    return Err((
+                        StatusCode::BAD_REQUEST,
+                        format!("Take profit price {} is on the wrong side of market", price),
+                    ));
+                }
+            }
+            (None, Some(roi)) if roi > Decimal::ZERO => {}
+            (None, Some(_)) => {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    "Take profit roi must be positive".to_string(),
+                ));
+            }
+            _ => {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    "Take profit requires exactly one of price or roi".to_string(),
+                ));
+            }
+        }
+    }
+
+    if total_fraction > Decimal::ONE {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Take profit fractions exceed 100%".to_string(),
+        ));
+    }
+    Ok(())
 }
 
 pub async fn get_trade(
-    Extension(trades): Extension<Arc<HashMap<String, Mutex<Vec<Trade>>>>>,
+    Extension(trades): Extension<Arc<SymbolMap<Mutex<Vec<Trade>>>>>,
 ) -> impl IntoResponse {
     let mut all_trades = Vec::new();
 
-    for (_, mutex_vec) in trades.iter() {
+    for (_, mutex_vec) in trades.entries() {
         let trades = mutex_vec.lock().await.clone();
         all_trades.extend(trades);
     }
@@ -181,13 +1064,13 @@ pub async fn get_trade(
 }
 
 pub async fn get_price(
-    Extension(prices): Extension<Arc<HashMap<String, Mutex<(String, String)>>>>,
+    Extension(prices): Extension<Arc<SymbolMap<Mutex<PriceBook>>>>,
 ) -> impl IntoResponse {
     // 创建一个新的 HashMap 来存储结果
     let mut all_prices = HashMap::new();
 
     // 遍历 `prices` 并解锁每个价格，将它们插入到 `all_prices` 中
-    for (key, mutex_f64) in prices.iter() {
+    for (key, mutex_f64) in prices.entries() {
         let price = mutex_f64.lock().await;
         all_prices.insert(key.clone(), price.clone());
     }
@@ -196,79 +1079,786 @@ pub async fn get_price(
     Json(all_prices)
 }
 
+// 手动平仓：持锁时只认领交易并标记为 Closing，撤单和平仓请求在释放锁后发出，
+// 完成后重新加锁写回结果，请求期间不阻塞该交易对的行情任务
 pub async fn close_trade(
     Extension(id): Extension<String>,
     Extension(api_keys): Extension<Arc<KeyManager>>,
-    Extension(trades): Extension<Arc<HashMap<String, Mutex<Vec<Trade>>>>>,
-    Extension(prices): Extension<Arc<HashMap<String, Mutex<(String, String)>>>>,
-    Extension(database): Extension<DatabaseConnection>,
+    Extension(trades): Extension<Arc<SymbolMap<Mutex<Vec<Trade>>>>>,
+    Extension(prices): Extension<Arc<SymbolMap<Mutex<PriceBook>>>>,
+    Extension(events): Extension<EventBus>,
     Json(payload): Json<CloseTradeRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
     let key = get_api_key(api_keys, &id).await?;
-    // 检查是否存在该 symbol 的交易记录
-    if let Some(mutex) = prices.get(&payload.symbol) {
-        let book = mutex.lock().await;
-        if let Some(mutex_vec) = trades.get(&payload.symbol) {
+    let (Some(mutex_book), Some(mutex_vec)) =
+        (prices.get(&payload.symbol), trades.get(&payload.symbol))
+    else {
+        return Err((StatusCode::BAD_REQUEST, "Symbol not found".to_string()));
+    };
+    let book = mutex_book.lock().await.clone();
+
+    let mut trade = {
+        let trade_list = mutex_vec.lock().await;
+        let Some(trade) = trade_list
+            .iter()
+            .find(|t| t.id == payload.id && !t.is_closed())
+        else {
+            return Err((StatusCode::BAD_REQUEST, "Trade not found".to_string()));
+        };
+        trade.clone()
+    };
+
+    // 开仓单尚未成交时只需撤单；撤单前已有成交的部分继续平仓
+    if trade.status == TradeStatus::Pending {
+        cancel_order(
+            &payload.symbol,
+            trade.order_id,
+            &key.api_key,
+            &key.api_secret,
+        )
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Cancel failed: {}", e)))?;
+        let order = get_order_api(
+            &payload.symbol,
+            trade.order_id,
+            &key.api_key,
+            &key.api_secret,
+        )
+        .await
+        .ok();
+
+        let mut trade_list = mutex_vec.lock().await;
+        let Some(t) = trade_list.iter_mut().find(|t| t.id == payload.id) else {
+            return Err((StatusCode::BAD_REQUEST, "Trade not found".to_string()));
+        };
+        if let Some(order) = order {
+            t.apply_entry_fill(
+                parse_decimal(&order.avgPrice).unwrap_or_default(),
+                parse_decimal(&order.executedQty).unwrap_or_default(),
+            );
+        }
+        if t.status == TradeStatus::Pending {
+            t.status = TradeStatus::Closed;
+            let result = CloseTradeResponse {
+                id: t.id,
+                symbol: payload.symbol,
+                direction: t.direction.clone(),
+                entry_price: t.entry_price,
+                close_price: Decimal::ZERO,
+                quantity: Decimal::ZERO,
+                paper: t.paper,
+            };
+            return Ok((StatusCode::OK, Json(result)).into_response());
+        }
+        trade = t.clone();
+    }
+
+    // 认领交易：正在平仓的交易由平仓任务处理。成交均价缺失时用盘口价记录
+    let fallback_price = book.exit_price(&trade.direction).unwrap_or_default();
+    let previous_status = {
+        let mut trade_list = mutex_vec.lock().await;
+        let Some(t) = trade_list
+            .iter_mut()
+            .find(|t| t.id == payload.id && !t.is_closed())
+        else {
+            return Err((StatusCode::BAD_REQUEST, "Trade not found".to_string()));
+        };
+        if t.status == TradeStatus::Closing {
+            return Err((StatusCode::CONFLICT, "Trade is already closing".to_string()));
+        }
+        let previous_status = t.status;
+        t.status = TradeStatus::Closing;
+        events.publish(
+            t,
+            TradeEventKind::CloseRequested {
+                price: fallback_price,
+                reason: CloseReason::Manual,
+            },
+        );
+        trade = t.clone();
+        previous_status
+    };
+
+    if trade.stop_strategy.is_exchange_managed() {
+        let _ = cancel_order(
+            &payload.symbol,
+            trade.stop_order,
+            &key.api_key,
+            &key.api_secret,
+        )
+        .await;
+    }
+    let quantity = trade.remaining_quantity;
+    let fill = TradeExchange
+        .close_position(&trade, quantity, fallback_price)
+        .await;
+
+    let mut trade_list = mutex_vec.lock().await;
+    let t = trade_list.iter_mut().find(|t| t.id == payload.id);
+    match (fill, t) {
+        (Some(fill), t) => {
+            if let Some(t) = t {
+                t.reduce_position(quantity);
+                events.publish(t, t.close_event(&fill, quantity, CloseReason::Manual));
+            }
+            // 返回平仓结果
+            let result = CloseTradeResponse {
+                id: trade.id,
+                symbol: payload.symbol,
+                direction: trade.direction,
+                entry_price: trade.entry_price,
+                close_price: fill.price,
+                quantity,
+                paper: trade.paper,
+            };
+            Ok((StatusCode::OK, Json(result)).into_response())
+        }
+        (None, t) => {
+            // 平仓失败时保留交易：Open 的继续由本地止损保护，CloseFailed 的等待再次手动平仓
+            if let Some(t) = t.filter(|t| t.status == TradeStatus::Closing) {
+                t.status = previous_status;
+            }
+            Err((
+                StatusCode::BAD_REQUEST,
+                "Close order failed, trade kept".to_string(),
+            ))
+        }
+    }
+}
+
+// 部分平仓：按比例或数量市价减仓，托管止损单按剩余数量重下
+pub async fn partial_close_trade(
+    Extension(user_id): Extension<String>,
+    Extension(api_keys): Extension<Arc<KeyManager>>,
+    Extension(trades): Extension<Arc<SymbolMap<Mutex<Vec<Trade>>>>>,
+    Extension(prices): Extension<Arc<SymbolMap<Mutex<PriceBook>>>>,
+    Extension(filters): Extension<Arc<SymbolMap<SymbolFilter>>>,
+    Extension(events): Extension<EventBus>,
+    Json(payload): Json<PartialCloseRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let key = get_api_key(api_keys, &user_id).await?;
+    let (Some(mutex_book), Some(mutex_vec), Some(filter)) = (
+        prices.get(&payload.symbol),
+        trades.get(&payload.symbol),
+        filters.get(&payload.symbol),
+    ) else {
+        return Err((StatusCode::BAD_REQUEST, "Symbol not found".to_string()));
+    };
+    let book = mutex_book.lock().await.clone();
+
+    // 持锁时计算数量并将交易标记为 Closing，减仓期间行情任务不再处理该交易
+    let (trade, quantity) = {
+        let mut trade_list = mutex_vec.lock().await;
+        let Some(trade) = trade_list
+            .iter_mut()
+            .find(|t| t.id == payload.id && t.owner_id == user_id)
+        else {
+            return Err((StatusCode::BAD_REQUEST, "Trade not found".to_string()));
+        };
+        if trade.status != TradeStatus::Open {
+            return Err((StatusCode::BAD_REQUEST, "Trade is not open".to_string()));
+        }
+
+        let requested = match (payload.percent, payload.quantity) {
+            (Some(percent), None) if percent > Decimal::ZERO && percent <= Decimal::ONE => {
+                trade.remaining_quantity * percent
+            }
+            (None, Some(quantity)) if quantity > Decimal::ZERO => quantity,
+            _ => {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    "Requires exactly one of percent in (0, 1] or positive quantity".to_string(),
+                ))
+            }
+        };
+        let mut quantity = round_to_step(requested, filter.step_size).min(trade.remaining_quantity);
+        if quantity <= Decimal::ZERO || quantity < filter.min_qty {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Quantity {} below minimum {}", quantity, filter.min_qty),
+            ));
+        }
+        // 剩余数量不足最小下单量时无法再单独平仓，一并平掉
+        if trade.remaining_quantity - quantity < filter.min_qty {
+            quantity = trade.remaining_quantity;
+        }
+        trade.status = TradeStatus::Closing;
+        (trade.clone(), quantity)
+    };
+
+    // 成交均价缺失时用盘口价记录
+    let fallback_price = book.exit_price(&trade.direction).unwrap_or_default();
+    let fill = TradeExchange
+        .close_position(&trade, quantity, fallback_price)
+        .await;
+
+    let (trade, fill) = {
+        let mut trade_list = mutex_vec.lock().await;
+        let Some(t) = trade_list.iter_mut().find(|t| t.id == payload.id) else {
+            return Err((StatusCode::BAD_REQUEST, "Trade not found".to_string()));
+        };
+        if t.status == TradeStatus::Closing {
+            t.status = TradeStatus::Open;
+        }
+        let Some(fill) = fill else {
+            return Err((StatusCode::BAD_REQUEST, "Close order failed".to_string()));
+        };
+        t.reduce_position(quantity);
+        events.publish(t, t.close_event(&fill, quantity, CloseReason::Manual));
+        (t.clone(), fill)
+    };
+
+    // 托管止损单按剩余数量重下，同样在锁外向交易所下单
+    if trade.stop_strategy.is_exchange_managed() {
+        if trade.is_closed() {
+            let _ = cancel_order(
+                &payload.symbol,
+                trade.stop_order,
+                &key.api_key,
+                &key.api_secret,
+            )
+            .await;
+        } else if let Some(order_id) = resize_exchange_stop(&trade, &key).await {
             let mut trade_list = mutex_vec.lock().await;
+            if let Some(t) = trade_list
+                .iter_mut()
+                .find(|t| t.id == payload.id && t.stop_order == trade.stop_order)
+            {
+                t.stop_order = order_id;
+            }
+        }
+    }
 
-            // 查找匹配的交易
-            if let Some(index) = trade_list.iter().position(|trade| trade.id == payload.id) {
-                let trade = trade_list.remove(index);
-                let (side, position_side, price) = match trade.direction {
-                    TradeDirection::Long => ("SELL", "LONG", book.1.clone()),
-                    TradeDirection::Short => ("BUY", "SHORT", book.0.clone()),
+    let result = PartialCloseResponse {
+        id: trade.id,
+        symbol: payload.symbol,
+        close_price: fill.price,
+        closed_quantity: quantity,
+        remaining_quantity: trade.remaining_quantity,
+        status: trade.status,
+        paper: trade.paper,
+    };
+    Ok((StatusCode::OK, Json(result)).into_response())
+}
+
+// 按剩余数量重下托管跟踪止损单，新单下单成功后才撤销旧单，返回新单 ID
+async fn resize_exchange_stop(trade: &Trade, key: &SecretKey) -> Option<u64> {
+    let order_id = place_exchange_trailing_stop(
+        &trade.symbol,
+        &trade.direction,
+        &trade.stop_strategy.config(),
+        trade.price_source,
+        trade.remaining_quantity,
+        key,
+    )
+    .await?;
+    let _ = cancel_order(
+        &trade.symbol,
+        trade.stop_order,
+        &key.api_key,
+        &key.api_secret,
+    )
+    .await;
+    Some(order_id)
+}
+
+// 修改进行中的交易：止损价、止损策略（含阶梯预设）和止盈目标。
+// 持锁完成校验后释放锁向交易所改单，再重新加锁确认交易未变化并一次性写回，行情任务不会看到中间状态
+#[allow(clippy::too_many_arguments)]
+pub async fn modify_trade(
+    Extension(user_id): Extension<String>,
+    Extension(api_keys): Extension<Arc<KeyManager>>,
+    Extension(trades): Extension<Arc<SymbolMap<Mutex<Vec<Trade>>>>>,
+    Extension(prices): Extension<Arc<SymbolMap<Mutex<PriceBook>>>>,
+    Extension(filters): Extension<Arc<SymbolMap<SymbolFilter>>>,
+    Extension(database): Extension<DatabaseConnection>,
+    Extension(events): Extension<EventBus>,
+    Json(payload): Json<ModifyTradeRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let key = get_api_key(api_keys, &user_id).await?;
+    if payload.stop_loss.is_none()
+        && payload.stop_strategy.is_none()
+        && payload.adjustment_id.is_none()
+        && payload.take_profits.is_none()
+    {
+        return Err((StatusCode::BAD_REQUEST, "Nothing to modify".to_string()));
+    }
+    if let Some(config) = &payload.stop_strategy {
+        config
+            .validate()
+            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+        if matches!(config, StopStrategyConfig::Ladder) && payload.adjustment_id.is_none() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "adjustment_id is required for Ladder".to_string(),
+            ));
+        }
+    }
+    let (Some(mutex_book), Some(mutex_vec), Some(filter)) = (
+        prices.get(&payload.symbol),
+        trades.get(&payload.symbol),
+        filters.get(&payload.symbol),
+    ) else {
+        return Err((StatusCode::BAD_REQUEST, "Symbol not found".to_string()));
+    };
+
+    // 预设和 K 线需在锁定交易列表前读取
+    let adjustments = match payload.adjustment_id {
+        Some(id) => Some(load_preset_adjustments(&database, id, &user_id).await?),
+        None => None,
+    };
+    let klines = match &payload.stop_strategy {
+        Some(config) => load_strategy_klines(config, &payload.symbol).await?,
+        None => Vec::new(),
+    };
+    let book = mutex_book.lock().await.clone();
+
+    // 持锁时完成校验并记下交易快照，交易所改单在释放锁后进行
+    let (snapshot, stop_loss, strategy, take_profits) = {
+        let trade_list = mutex_vec.lock().await;
+        let Some(trade) = trade_list
+            .iter()
+            .find(|t| t.id == payload.id && t.owner_id == user_id)
+        else {
+            return Err((StatusCode::BAD_REQUEST, "Trade not found".to_string()));
+        };
+        if !matches!(trade.status, TradeStatus::Open | TradeStatus::Pending) {
+            return Err((StatusCode::BAD_REQUEST, "Trade is not open".to_string()));
+        }
+        let is_open = trade.status == TradeStatus::Open;
+        let trigger_price = book.trigger_price(trade.price_source, &trade.direction);
+
+        // 手动止损必须位于亏损方向，否则会立即触发
+        let stop_loss = match payload.stop_loss {
+            Some(_) if !is_open => {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    "Stop loss is re-anchored when the entry fills".to_string(),
+                ))
+            }
+            Some(stop) => {
+                let stop = round_stop_price(&trade.direction, stop, filter.tick_size);
+                let price = trigger_price.ok_or((
+                    StatusCode::BAD_REQUEST,
+                    "Market price not available".to_string(),
+                ))?;
+                let valid = match trade.direction {
+                    TradeDirection::Long => stop > Decimal::ZERO && stop < price,
+                    TradeDirection::Short => stop > price,
                 };
+                if !valid {
+                    return Err((
+                        StatusCode::BAD_REQUEST,
+                        format!("Stop loss {} would trigger at price {}", stop, price),
+                    ));
+                }
+                Some(stop)
+            }
+            None => None,
+        };
 
-                let order_response = create_order(
-                    &payload.symbol,
-                    side,
-                    position_side,
-                    "MARKET",        // 假设使用市价单
-                    &trade.quantity, // 将数量格式化为字符串
-                    None,            // 市价单无需价格
-                    None,            // 此示例未设置止损价格
+        // 新策略从当前止损开始继续跟踪
+        let strategy = match (payload.stop_strategy, adjustments) {
+            (Some(config), adjustments) => Some(build_stop_strategy(
+                &config,
+                adjustments.unwrap_or_default(),
+                &klines,
+            )),
+            (None, Some(adjustments)) => {
+                let config = trade.stop_strategy.config();
+                if !matches!(config, StopStrategyConfig::Ladder) {
+                    return Err((
+                        StatusCode::BAD_REQUEST,
+                        "adjustment_id only applies to the Ladder strategy".to_string(),
+                    ));
+                }
+                Some(build_stop_strategy(&config, adjustments, &klines))
+            }
+            (None, None) => None,
+        };
+        if strategy
+            .as_ref()
+            .is_some_and(|s| s.is_exchange_managed() && !is_open)
+        {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "ExchangeTrailing requires a filled entry".to_string(),
+            ));
+        }
+        if trade.paper && strategy.as_ref().is_some_and(|s| s.is_exchange_managed()) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Paper trades cannot use an exchange-managed stop".to_string(),
+            ));
+        }
+
+        // 止盈按当前价格校验，按开仓均价换算 ROI 目标
+        let take_profits = match &payload.take_profits {
+            Some(requests) => {
+                let market_price = trigger_price.unwrap_or(trade.entry_price);
+                validate_take_profits(requests, &trade.direction, market_price)?;
+                Some(build_take_profits(
+                    requests,
+                    &trade.direction,
+                    trade.leverage,
+                    trade.entry_price,
+                    &filter,
+                ))
+            }
+            None => None,
+        };
+
+        (trade.clone(), stop_loss, strategy, take_profits)
+    };
+
+    // 交易所改单：先下新的托管止损单，成功后再撤销旧单
+    let was_managed = snapshot.stop_strategy.is_exchange_managed();
+    let mut new_stop_order = None;
+    if let Some(strategy) = &strategy {
+        if strategy.is_exchange_managed() {
+            let order_id = place_exchange_trailing_stop(
+                &snapshot.symbol,
+                &snapshot.direction,
+                &strategy.config(),
+                snapshot.price_source,
+                snapshot.remaining_quantity,
+                &key,
+            )
+            .await
+            .ok_or((
+                StatusCode::BAD_REQUEST,
+                "Trailing stop order failed".to_string(),
+            ))?;
+            if was_managed {
+                let _ = cancel_order(
+                    &snapshot.symbol,
+                    snapshot.stop_order,
                     &key.api_key,
                     &key.api_secret,
                 )
                 .await;
+            }
+            new_stop_order = Some(order_id);
+        } else if was_managed {
+            cancel_order(
+                &snapshot.symbol,
+                snapshot.stop_order,
+                &key.api_key,
+                &key.api_secret,
+            )
+            .await
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("Cancel failed: {}", e)))?;
+        }
+    }
 
-                match order_response {
-                    Ok(order) => match get_order_api(
-                        &payload.symbol,
-                        order.orderId,
-                        &key.api_key,
-                        &key.api_secret,
-                    )
-                    .await
-                    {
-                        Ok(b_order) => {
-                            create_trade_record(&database, &trade, &b_order.avgPrice).await;
-
-                            // 返回平仓结果
-                            let result = CloseTradeResponse {
-                                id: trade.id,
-                                symbol: payload.symbol,
-                                direction: trade.direction,
-                                entry_price: trade.entry_price,
-                                close_price: b_order.avgPrice,
-                                quantity: trade.quantity,
-                            };
-
-                            return Ok((StatusCode::OK, Json(result)).into_response());
-                        }
-                        Err(_) => Err((StatusCode::BAD_REQUEST, "Trade not found".to_string())),
-                    },
-                    Err(_) => Err((StatusCode::BAD_REQUEST, "Trade not found".to_string())),
-                }
-            } else {
-                return Err((StatusCode::BAD_REQUEST, "Trade not found".to_string()));
+    // 重新加锁写回；改单期间交易已平仓或数量变化时撤销新下的托管止损单
+    let mut trade_list = mutex_vec.lock().await;
+    let trade = trade_list.iter_mut().find(|t| {
+        t.id == payload.id
+            && matches!(t.status, TradeStatus::Open | TradeStatus::Pending)
+            && (new_stop_order.is_none() || t.remaining_quantity == snapshot.remaining_quantity)
+    });
+    let Some(trade) = trade else {
+        drop(trade_list);
+        if let Some(order_id) = new_stop_order {
+            let _ = cancel_order(&snapshot.symbol, order_id, &key.api_key, &key.api_secret).await;
+        }
+        return Err((
+            StatusCode::CONFLICT,
+            "Trade changed while modifying".to_string(),
+        ));
+    };
+    if let Some(strategy) = strategy {
+        if let Some(order_id) = new_stop_order {
+            trade.stop_order = order_id;
+            if !was_managed {
+                tokio::spawn(watch_exchange_stop(
+                    trades.clone(),
+                    events.clone(),
+                    payload.symbol.clone(),
+                    trade.id,
+                ));
             }
-        } else {
-            Err((StatusCode::BAD_REQUEST, "Symbol not found".to_string()))
         }
+        trade.stop_strategy = strategy;
+        trade.adjustment_id = ladder_preset(
+            &trade.stop_strategy.config(),
+            payload
+                .adjustment_id
+                .or(trade.adjustment_id)
+                .unwrap_or_default(),
+        );
+    }
+    if let Some(stop) = stop_loss.filter(|stop| *stop != trade.stop_loss) {
+        events.publish(
+            trade,
+            TradeEventKind::StopMoved {
+                from: trade.stop_loss,
+                to: stop,
+            },
+        );
+        trade.stop_loss = stop;
+    }
+    if let Some(take_profits) = take_profits {
+        trade.take_profits = take_profits;
+    }
+
+    Ok((StatusCode::OK, Json(trade.clone())).into_response())
+}
+
+// 加仓：市价追加同方向持仓，按加权平均更新开仓价并重新锚定止损
+#[allow(clippy::too_many_arguments)]
+pub async fn add_to_trade(
+    Extension(user_id): Extension<String>,
+    Extension(api_keys): Extension<Arc<KeyManager>>,
+    Extension(trades): Extension<Arc<SymbolMap<Mutex<Vec<Trade>>>>>,
+    Extension(prices): Extension<Arc<SymbolMap<Mutex<PriceBook>>>>,
+    Extension(filters): Extension<Arc<SymbolMap<SymbolFilter>>>,
+    Extension(database): Extension<DatabaseConnection>,
+    Extension(events): Extension<EventBus>,
+    Extension(grids): Extension<Arc<Grids>>,
+    Json(payload): Json<AddToTradeRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let key = get_api_key(api_keys, &user_id).await?;
+    if payload.margin <= Decimal::ZERO {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "margin must be positive".to_string(),
+        ));
+    }
+    let (Some(mutex_book), Some(mutex_vec), Some(filter)) = (
+        prices.get(&payload.symbol),
+        trades.get(&payload.symbol),
+        filters.get(&payload.symbol),
+    ) else {
+        return Err((StatusCode::BAD_REQUEST, "Symbol not found".to_string()));
+    };
+    check_symbol_trading(&filters, &payload.symbol)?;
+    let book = mutex_book.lock().await.clone();
+
+    // 复制交易后释放锁，风控统计和下单都在锁外完成
+    let Some(snapshot) = mutex_vec
+        .lock()
+        .await
+        .iter()
+        .find(|t| t.id == payload.id && t.owner_id == user_id)
+        .cloned()
+    else {
+        return Err((StatusCode::BAD_REQUEST, "Trade not found".to_string()));
+    };
+    if snapshot.status != TradeStatus::Open {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Trade entry is not filled yet".to_string(),
+        ));
+    }
+    // 托管止损单的数量在下单时已固定
+    if snapshot.stop_strategy.is_exchange_managed() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Cannot add to a trade with an exchange-managed stop".to_string(),
+        ));
+    }
+
+    let market_price = book.entry_price(&snapshot.direction).ok_or((
+        StatusCode::BAD_REQUEST,
+        "Market price not available".to_string(),
+    ))?;
+    let quantity = round_to_step(
+        payload.margin * snapshot.leverage / market_price,
+        filter.step_size,
+    );
+    if quantity <= Decimal::ZERO || quantity < filter.min_qty {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Quantity {} below minimum {}", quantity, filter.min_qty),
+        ));
+    }
+
+    let (order_id, fill_price, fill_quantity) = if snapshot.paper {
+        let account = find_paper_account(&database, &user_id).await.map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Database error: {}", e),
+            )
+        })?;
+        let balance = paper_balance(&database, &user_id, &account)
+            .await
+            .map_err(|e| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Database error: {}", e),
+                )
+            })?;
+        let available = balance - paper_used_margin(&trades, &user_id).await;
+        // 模拟盘按盘口价全部成交
+        let margin = quantity * market_price / snapshot.leverage;
+        if margin > available {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "Margin {} exceeds available paper balance {}",
+                    margin.round_dp(2),
+                    available.round_dp(2)
+                ),
+            ));
+        }
+        (PaperExchange::next_order_id(), market_price, quantity)
     } else {
-        Err((StatusCode::BAD_REQUEST, "failed, symbol".to_string()))
+        // 加仓与开仓一样受杠杆上限和敞口限制，该交易已计入持仓笔数
+        if let Some(policy) = check_account_risk(&database, &user_id, snapshot.leverage).await? {
+            let exposure = user_exposure(&trades, &grids, &user_id, &payload.symbol).await;
+            let exposure = Exposure {
+                open_trades: exposure.open_trades.saturating_sub(1),
+                ..exposure
+            };
+            check_exposure(&policy, &exposure, quantity * market_price)
+                .map_err(|e| (StatusCode::FORBIDDEN, e))?;
+        }
+        let (side, position_side) = match snapshot.direction {
+            TradeDirection::Long => ("BUY", "LONG"),
+            TradeDirection::Short => ("SELL", "SHORT"),
+        };
+        let order = create_order(
+            &payload.symbol,
+            side,
+            position_side,
+            "MARKET",
+            &quantity.to_string(),
+            None,
+            None,
+            &key.api_key,
+            &key.api_secret,
+        )
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Order failed: {}", e)))?;
+
+        // 成交信息缺失时按下单数量和盘口价估算
+        match get_order_api(
+            &payload.symbol,
+            order.orderId,
+            &key.api_key,
+            &key.api_secret,
+        )
+        .await
+        {
+            Ok(b_order) => (
+                order.orderId,
+                parse_decimal(&b_order.avgPrice)
+                    .filter(|p| *p > Decimal::ZERO)
+                    .unwrap_or(market_price),
+                parse_decimal(&b_order.executedQty)
+                    .filter(|q| *q > Decimal::ZERO)
+                    .unwrap_or(quantity),
+            ),
+            Err(_) => (order.orderId, market_price, quantity),
+        }
+    };
+
+    let mut trade_list = mutex_vec.lock().await;
+    let Some(trade) = trade_list
+        .iter_mut()
+        .find(|t| t.id == payload.id && t.owner_id == user_id && t.status == TradeStatus::Open)
+    else {
+        drop(trade_list);
+        // 下单期间交易已被平仓，立即平掉加仓部分，避免留下无人管理的持仓
+        if !snapshot.paper {
+            TradeExchange
+                .close_position(&snapshot, fill_quantity, fill_price)
+                .await;
+        }
+        return Err((
+            StatusCode::CONFLICT,
+            "Trade closed while adding".to_string(),
+        ));
+    };
+
+    let previous_stop = trade.stop_loss;
+    trade.add_fill(fill_price, fill_quantity, payload.reanchor);
+    if trade.stop_loss != previous_stop {
+        events.publish(
+            trade,
+            TradeEventKind::StopMoved {
+                from: previous_stop,
+                to: trade.stop_loss,
+            },
+        );
+    }
+    let trade = trade.clone();
+    drop(trade_list);
+    // 加仓记录用于查询实盘手续费，模拟盘不需要
+    if !trade.paper {
+        create_leg_record(
+            &database,
+            &trade,
+            order_id,
+            fill_price,
+            fill_quantity,
+            payload.reanchor,
+        )
+        .await;
     }
+
+    let result = AddToTradeResponse {
+        id: trade.id,
+        symbol: payload.symbol,
+        fill_price,
+        added_quantity: fill_quantity,
+        entry_price: trade.entry_price,
+        quantity: trade.quantity,
+        stop_price: trade.stop_loss,
+        paper: trade.paper,
+    };
+    Ok((StatusCode::OK, Json(result)).into_response())
+}
+
+// 以 SSE 推送当前用户的交易事件
+pub async fn trade_events(
+    Extension(user_id): Extension<String>,
+    Extension(events): Extension<EventBus>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let stream = stream::unfold(events.subscribe(), move |mut receiver| {
+        let user_id = user_id.clone();
+        async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) if event.owner_id == user_id => {
+                        let data = Event::default()
+                            .event("trade")
+                            .json_data(&event)
+                            .unwrap_or_default();
+                        return Some((Ok(data), receiver));
+                    }
+                    // 客户端处理过慢时跳过丢失的事件
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        }
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+pub async fn get_trade_legs(
+    Extension(user_id): Extension<String>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(params): Query<TradeLegQueryParams>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    use sea_orm::QueryOrder;
+
+    let legs = trade_legs::Entity::find()
+        .filter(trade_legs::Column::TradeId.eq(params.trade_id))
+        .filter(trade_legs::Column::OwnerId.eq(user_id))
+        .order_by_asc(trade_legs::Column::Id)
+        .all(&database)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to fetch legs: {}", e),
+            )
+        })?;
+    Ok(Json(legs))
 }
 
 pub async fn get_all_history_trades(
@@ -306,6 +1896,46 @@ pub async fn get_all_history_trades(
     }
 }
 
+// 已平仓交易的统计：胜率、盈亏比、回撤、持仓时长以及按品种和星期的分组
+pub async fn get_trade_analytics(
+    Extension(user_id): Extension<String>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(params): Query<AnalyticsQueryParams>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    use sea_orm::QueryOrder;
+
+    // 只统计当前用户的交易
+    let mut query = trades::Entity::find().filter(trades::Column::OwnerId.eq(user_id));
+    if let Some(symbol) = &params.symbol {
+        query = query.filter(trades::Column::Symbol.eq(symbol.as_str()));
+    }
+    if let Some(direction) = &params.direction {
+        query = query.filter(trades::Column::Direction.eq(direction.to_string()));
+    }
+    if let Some(adjustment_id) = params.adjustment_id {
+        query = query.filter(trades::Column::AdjustmentId.eq(adjustment_id));
+    }
+    if let Some(start_time) = params.start_time {
+        query = query.filter(trades::Column::CreatedAt.gte(start_time));
+    }
+    if let Some(end_time) = params.end_time {
+        query = query.filter(trades::Column::CreatedAt.lte(end_time));
+    }
+
+    let records = query
+        .order_by_asc(trades::Column::CreatedAt)
+        .order_by_asc(trades::Column::Id)
+        .all(&database)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to fetch trades: {}", e),
+            )
+        })?;
+    Ok(Json(analyze(&group_closed_trades(&records))))
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct DeleteResponse {
     pub id: u32,
@@ -339,48 +1969,166 @@ pub async fn delete_trade_by_id(
 
 #[derive(Deserialize, Serialize)]
 pub struct AdjustmentRequest {
-    pub id: u8,
+    pub id: i64,
 }
 
+// 兼容旧接口：读取预设的阶梯配置
 pub async fn get_adjustments(
-    Extension(adjustments): Extension<Arc<HashMap<u8, Mutex<AdjustmentConfig>>>>,
+    Extension(user_id): Extension<String>,
+    Extension(database): Extension<DatabaseConnection>,
     Query(params): Query<AdjustmentRequest>,
-) -> impl IntoResponse {
-    let id = params.id;
-    if let Some(mutex_config) = adjustments.get(&id) {
-        // 尝试解锁 Mutex
-        let config = mutex_config.lock().await;
-        let adjustment = config.adjustments.clone();
-        (StatusCode::OK, Json(adjustment)).into_response()
-    } else {
-        // 如果键不存在，返回 404
-        let error_message = format!("Failed to get adjustment id: {}", id);
-        AppError::new(StatusCode::INTERNAL_SERVER_ERROR, error_message).into_response()
-    }
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let adjustment = load_preset_adjustments(&database, params.id, &user_id).await?;
+    Ok(Json(adjustment))
 }
 
 #[derive(Deserialize)]
 pub struct UpdateAdjustmentRequest {
-    pub id: u8,
+    pub id: i64,
     pub adjustment: Vec<Adjustment>,
 }
 
+// 兼容旧接口：校验后更新自己的预设
 pub async fn update_adjustments(
-    Extension(adjustments): Extension<Arc<HashMap<u8, Mutex<AdjustmentConfig>>>>,
+    user_id: Extension<String>,
+    database: Extension<DatabaseConnection>,
     Json(payload): Json<UpdateAdjustmentRequest>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, (StatusCode, String)> {
     let id = payload.id;
-    if let Some(mutex_config) = adjustments.get(&id) {
-        // 尝试解锁 Mutex
-        let mut config = mutex_config.lock().await;
-        config.adjustments = payload.adjustment;
-        let response = AdjustmentRequest { id };
-        (StatusCode::OK, Json(response)).into_response()
-    } else {
-        // 如果键不存在，返回 404
-        let error_message = format!("Failed to get adjustment id: {}", id);
-        AppError::new(StatusCode::INTERNAL_SERVER_ERROR, error_message).into_response()
+    let request = UpdatePresetRequest {
+        id,
+        name: None,
+        adjustments: Some(payload.adjustment),
+    };
+    update_preset(user_id, database, Json(request)).await?;
+    Ok(Json(AdjustmentRequest { id }))
+}
+
+// 止损轨迹模拟上限，避免超长路径占用请求
+const MAX_SIMULATION_PRICES: usize = 10_000;
+
+// 使用实盘相同的 Trade::track_price 逐个价格推演止损
+pub async fn simulate_stop(
+    Extension(user_id): Extension<String>,
+    Extension(filters): Extension<Arc<SymbolMap<SymbolFilter>>>,
+    Extension(database): Extension<DatabaseConnection>,
+    Json(payload): Json<SimulateStopRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    if payload.entry_price <= Decimal::ZERO
+        || payload.leverage < Decimal::ONE
+        || payload.stop_loss_percent <= Decimal::ZERO
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "entry_price must be positive, leverage >= 1, stop_loss_percent positive".to_string(),
+        ));
+    }
+    if payload.prices.is_empty() || payload.prices.len() > MAX_SIMULATION_PRICES {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("prices must contain 1 to {} items", MAX_SIMULATION_PRICES),
+        ));
+    }
+    if payload.prices.iter().any(|p| *p <= Decimal::ZERO) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "prices must be positive".to_string(),
+        ));
+    }
+    payload
+        .stop_strategy
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    // 未指定品种时不做价格取整
+    let filter = match &payload.symbol {
+        Some(symbol) => filters
+            .get(symbol)
+            .as_deref()
+            .cloned()
+            .ok_or((StatusCode::BAD_REQUEST, "Symbol not found".to_string()))?,
+        None => SymbolFilter::default(),
+    };
+
+    let adjustment = match (
+        &payload.stop_strategy,
+        payload.adjustments,
+        payload.adjustment_id,
+    ) {
+        (StopStrategyConfig::Ladder, Some(adjustments), _) => {
+            validate_adjustments(&adjustments).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+            adjustments
+        }
+        (StopStrategyConfig::Ladder, None, Some(id)) => {
+            load_preset_adjustments(&database, id, &user_id).await?
+        }
+        (StopStrategyConfig::Ladder, None, None) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Ladder requires adjustment_id or adjustments".to_string(),
+            ))
+        }
+        _ => Vec::new(),
+    };
+    let klines = match (&payload.stop_strategy, &payload.symbol) {
+        (StopStrategyConfig::Chandelier { .. }, None) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Chandelier requires symbol".to_string(),
+            ))
+        }
+        (config, Some(symbol)) => load_strategy_klines(config, symbol).await?,
+        _ => Vec::new(),
+    };
+    let stop_strategy = build_stop_strategy(&payload.stop_strategy, adjustment, &klines);
+
+    let mut trade = Trade::new(
+        TradeParams {
+            id: 0,
+            owner_id: user_id,
+            order_id: 0,
+            symbol: payload.symbol.unwrap_or_default(),
+            entry_price: payload.entry_price,
+            direction: payload.direction.clone(),
+            quantity: Decimal::ZERO,
+            leverage: payload.leverage,
+            stop_loss_percent: payload.stop_loss_percent,
+            stop_strategy,
+            take_profits: Vec::new(),
+            price_source: PriceSource::Book,
+            api_key: String::new(),
+            api_secret: String::new(),
+        },
+        &filter,
+    )
+    .await;
+    let initial_stop = trade.stop_loss;
+
+    let mut steps = Vec::with_capacity(payload.prices.len());
+    let mut exit = None;
+    for (index, price) in payload.prices.into_iter().enumerate() {
+        let step = trade.track_price(price);
+        let stop_hit = step.stop_hit;
+        steps.push(step);
+        if stop_hit {
+            let change = match payload.direction {
+                TradeDirection::Long => (price - payload.entry_price) / payload.entry_price,
+                TradeDirection::Short => (payload.entry_price - price) / payload.entry_price,
+            };
+            exit = Some(SimulatedExit {
+                index,
+                price,
+                roi: change * payload.leverage,
+            });
+            break;
+        }
     }
+
+    Ok(Json(SimulateStopResponse {
+        initial_stop,
+        steps,
+        exit,
+    }))
 }
 
 pub async fn get_user_hold(
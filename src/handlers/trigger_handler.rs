@@ -0,0 +1,331 @@
+use std::{convert::Infallible, sync::Arc};
+
+use axum::{
+    body::to_bytes,
+    extract::Query,
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
+    Extension, Json,
+};
+use futures_util::{stream, Stream};
+use rust_decimal::Decimal;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    Set,
+};
+use tokio::sync::{broadcast::error::RecvError, mpsc, Mutex};
+
+use crate::{
+    binance::leverage::SymbolFilter,
+    grid::Grids,
+    models::{
+        trade_model::CreateTradeRequest,
+        trigger_model::{CancelTriggerRequest, CreateTriggerRequest, TriggerQueryParams},
+    },
+    orm::triggers,
+    secret_key::KeyManager,
+    symbol::SymbolMap,
+    trade::{
+        event::EventBus,
+        price::PriceBook,
+        trigger::{Trigger, TriggerAction, TriggerFire, TriggerNotice, Triggers},
+        Trade,
+    },
+    utils::{unix_timestamp, TradeIdGenerator},
+};
+
+use super::{
+    load_api_key,
+    trade_hander::{create_trade, validate_trade_request},
+};
+
+type HandlerError = (StatusCode, String);
+
+// 每个用户同时布防的触发器上限
+const MAX_ARMED_TRIGGERS: usize = 50;
+// 开仓结果写入数据库的最大长度
+const MAX_RESULT_BYTES: usize = 64 * 1024;
+
+pub async fn create_trigger(
+    Extension(user_id): Extension<String>,
+    Extension(prices): Extension<Arc<SymbolMap<Mutex<PriceBook>>>>,
+    Extension(database): Extension<DatabaseConnection>,
+    Extension(triggers): Extension<Arc<Triggers>>,
+    Json(payload): Json<CreateTriggerRequest>,
+) -> Result<impl IntoResponse, HandlerError> {
+    payload
+        .condition
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    // 开仓模板在创建时校验，触发时只剩行情相关的错误
+    if let TriggerAction::OpenTrade { trade } = &payload.action {
+        let request: CreateTradeRequest = serde_json::from_value(trade.clone())
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid trade: {}", e)))?;
+        if request.symbol != payload.symbol {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Trade symbol must match the trigger symbol".to_string(),
+            ));
+        }
+        validate_trade_request(&request)?;
+    }
+    let (Some(mutex_book), true) = (
+        prices.get(&payload.symbol),
+        triggers.has_symbol(&payload.symbol),
+    ) else {
+        return Err((StatusCode::BAD_REQUEST, "Symbol not found".to_string()));
+    };
+    if triggers.armed_count(&user_id).await >= MAX_ARMED_TRIGGERS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("At most {} armed triggers", MAX_ARMED_TRIGGERS),
+        ));
+    }
+    let reference_price = payload.source.price(&*mutex_book.lock().await).ok_or((
+        StatusCode::BAD_REQUEST,
+        "Market price not available".to_string(),
+    ))?;
+    let expires_at = payload
+        .expires_in_secs
+        .map(|secs| unix_timestamp().saturating_add(secs));
+
+    let model = triggers::ActiveModel {
+        owner_id: Set(user_id.clone()),
+        symbol: Set(payload.symbol.clone()),
+        source: Set(payload.source.to_string()),
+        condition: Set(to_json(&payload.condition)?),
+        action: Set(to_json(&payload.action)?),
+        reference_price: Set(reference_price.to_string()),
+        status: Set("Armed".to_string()),
+        expires_at: Set(expires_at),
+        created_at: Set(unix_timestamp()),
+        ..Default::default()
+    }
+    .insert(&database)
+    .await
+    .map_err(db_error)?;
+
+    triggers
+        .arm(Trigger::new(
+            model.id,
+            user_id,
+            payload.symbol,
+            payload.source,
+            payload.condition,
+            payload.action,
+            reference_price,
+            expires_at,
+        ))
+        .await;
+    Ok(Json(model))
+}
+
+pub async fn list_triggers(
+    Extension(user_id): Extension<String>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(params): Query<TriggerQueryParams>,
+) -> Result<impl IntoResponse, HandlerError> {
+    let mut query = triggers::Entity::find().filter(triggers::Column::OwnerId.eq(user_id));
+    if let Some(symbol) = &params.symbol {
+        query = query.filter(triggers::Column::Symbol.eq(symbol.as_str()));
+    }
+    if let Some(status) = &params.status {
+        query = query.filter(triggers::Column::Status.eq(status.as_str()));
+    }
+    let result = query
+        .order_by_desc(triggers::Column::Id)
+        .all(&database)
+        .await
+        .map_err(db_error)?;
+    Ok(Json(result))
+}
+
+pub async fn cancel_trigger(
+    Extension(user_id): Extension<String>,
+    Extension(database): Extension<DatabaseConnection>,
+    Extension(triggers): Extension<Arc<Triggers>>,
+    Json(payload): Json<CancelTriggerRequest>,
+) -> Result<impl IntoResponse, HandlerError> {
+    let model = triggers::Entity::find_by_id(payload.id)
+        .filter(triggers::Column::OwnerId.eq(user_id.as_str()))
+        .one(&database)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::BAD_REQUEST, "Trigger not found".to_string()))?;
+    // 已被价格循环取出的触发器无法撤销
+    if !triggers.disarm(&model.symbol, model.id, &user_id).await {
+        return Err((StatusCode::CONFLICT, "Trigger is not armed".to_string()));
+    }
+    let model = finish_trigger(&database, model.id, "Cancelled", None, None)
+        .await
+        .map_err(db_error)?;
+    Ok(Json(model))
+}
+
+// 以 SSE 推送当前用户的触发器通知
+pub async fn trigger_events(
+    Extension(user_id): Extension<String>,
+    Extension(triggers): Extension<Arc<Triggers>>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let stream = stream::unfold(triggers.subscribe(), move |mut receiver| {
+        let user_id = user_id.clone();
+        async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(notice) if notice.owner_id == user_id => {
+                        let data = Event::default()
+                            .event("trigger")
+                            .json_data(&notice)
+                            .unwrap_or_default();
+                        return Some((Ok(data), receiver));
+                    }
+                    // 客户端处理过慢时跳过丢失的通知
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        }
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+// 执行价格循环交出的触发器：开仓或通知，并写回状态。每个触发器独立执行，互不阻塞
+#[allow(clippy::too_many_arguments)]
+pub async fn execute_triggers(
+    mut receiver: mpsc::UnboundedReceiver<TriggerFire>,
+    triggers: Arc<Triggers>,
+    api_keys: Arc<KeyManager>,
+    trades: Arc<SymbolMap<Mutex<Vec<Trade>>>>,
+    prices: Arc<SymbolMap<Mutex<PriceBook>>>,
+    filters: Arc<SymbolMap<SymbolFilter>>,
+    id_generator: Arc<TradeIdGenerator>,
+    database: DatabaseConnection,
+    events: EventBus,
+    grids: Arc<Grids>,
+) {
+    while let Some(fire) = receiver.recv().await {
+        let (trigger, price) = match fire {
+            TriggerFire::Fired { trigger, price } => (trigger, Some(price)),
+            TriggerFire::Expired { trigger } => (trigger, None),
+        };
+        let triggers = triggers.clone();
+        let api_keys = api_keys.clone();
+        let trades = trades.clone();
+        let prices = prices.clone();
+        let filters = filters.clone();
+        let id_generator = id_generator.clone();
+        let database = database.clone();
+        let events = events.clone();
+        let grids = grids.clone();
+        tokio::spawn(async move {
+            let (status, message) = match (price, &trigger.action) {
+                (None, _) => ("Expired", None),
+                (Some(_), TriggerAction::Notify { message }) => ("Fired", message.clone()),
+                (Some(_), TriggerAction::OpenTrade { trade }) => {
+                    let result = open_trade(
+                        &trigger.owner_id,
+                        trade.clone(),
+                        api_keys,
+                        trades,
+                        prices,
+                        filters,
+                        id_generator,
+                        &database,
+                        events,
+                        grids,
+                    )
+                    .await;
+                    match result {
+                        Ok(body) => ("Fired", Some(body)),
+                        Err((_, message)) => ("Failed", Some(message)),
+                    }
+                }
+            };
+            if let Err(e) =
+                finish_trigger(&database, trigger.id, status, price, message.clone()).await
+            {
+                eprintln!("触发器 {} 状态写入失败：{}", trigger.id, e);
+            }
+            triggers.notify(TriggerNotice {
+                trigger_id: trigger.id,
+                owner_id: trigger.owner_id,
+                symbol: trigger.symbol,
+                status: status.to_string(),
+                price,
+                message,
+                timestamp: unix_timestamp(),
+            });
+        });
+    }
+}
+
+// 按开仓模板调用开仓逻辑，成功时返回开仓结果 JSON
+#[allow(clippy::too_many_arguments)]
+async fn open_trade(
+    user_id: &str,
+    trade: serde_json::Value,
+    api_keys: Arc<KeyManager>,
+    trades: Arc<SymbolMap<Mutex<Vec<Trade>>>>,
+    prices: Arc<SymbolMap<Mutex<PriceBook>>>,
+    filters: Arc<SymbolMap<SymbolFilter>>,
+    id_generator: Arc<TradeIdGenerator>,
+    database: &DatabaseConnection,
+    events: EventBus,
+    grids: Arc<Grids>,
+) -> Result<String, HandlerError> {
+    let request: CreateTradeRequest = serde_json::from_value(trade)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid trade: {}", e)))?;
+    load_api_key(&api_keys, database, user_id).await?;
+    let response = create_trade(
+        Extension(user_id.to_string()),
+        Extension(api_keys),
+        Extension(trades),
+        Extension(prices),
+        Extension(filters),
+        Extension(id_generator),
+        Extension(database.clone()),
+        Extension(events),
+        Extension(grids),
+        Json(request),
+    )
+    .await?
+    .into_response();
+    let body = to_bytes(response.into_body(), MAX_RESULT_BYTES)
+        .await
+        .unwrap_or_default();
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+async fn finish_trigger(
+    database: &DatabaseConnection,
+    id: i64,
+    status: &str,
+    price: Option<Decimal>,
+    result: Option<String>,
+) -> Result<triggers::Model, DbErr> {
+    let fired_at = price.map(|_| unix_timestamp());
+    triggers::ActiveModel {
+        id: Set(id),
+        status: Set(status.to_string()),
+        fired_price: Set(price.map(|p| p.to_string())),
+        fired_at: Set(fired_at),
+        result: Set(result),
+        ..Default::default()
+    }
+    .update(database)
+    .await
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<String, HandlerError> {
+    serde_json::to_string(value).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+fn db_error(e: DbErr) -> HandlerError {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        format!("Database error: {}", e),
+    )
+}
@@ -0,0 +1,280 @@
+use std::sync::Arc;
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use rand::RngCore;
+use sea_orm::{
+    sea_query::OnConflict, ActiveModelTrait, DatabaseConnection, DbErr, EntityTrait, Set, SqlErr,
+};
+use sha2::{Digest, Sha256};
+use tokio::sync::Mutex;
+
+use crate::{
+    binance::leverage::SymbolFilter,
+    grid::Grids,
+    models::{
+        trade_model::CloseTradeRequest,
+        webhook_model::{FlattenResult, SignalAction, WebhookSecretResponse, WebhookSignal},
+    },
+    orm::{webhook_secrets, webhook_signals},
+    secret_key::KeyManager,
+    symbol::SymbolMap,
+    trade::{event::EventBus, price::PriceBook, Trade, TradeStatus},
+    utils::{unix_timestamp, TradeIdGenerator},
+};
+
+use super::{
+    load_api_key, parse_owner_id,
+    trade_hander::{close_trade, create_trade},
+};
+
+type HandlerError = (StatusCode, String);
+
+// 信号时间与服务器时间的最大偏差（秒），超出视为重放
+const WEBHOOK_MAX_SKEW_SECS: u32 = 300;
+
+// 生成新的 webhook 密钥并替换旧密钥
+pub async fn rotate_webhook_secret(
+    Extension(user_id): Extension<String>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<impl IntoResponse, HandlerError> {
+    let owner_id = parse_owner_id(&user_id)?;
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let secret = hex::encode(bytes);
+
+    let model = webhook_secrets::ActiveModel {
+        owner_id: Set(owner_id),
+        secret_hash: Set(sha256_hex(secret.as_bytes())),
+        created_at: Set(unix_timestamp()),
+    };
+    webhook_secrets::Entity::insert(model)
+        .on_conflict(
+            OnConflict::column(webhook_secrets::Column::OwnerId)
+                .update_columns([
+                    webhook_secrets::Column::SecretHash,
+                    webhook_secrets::Column::CreatedAt,
+                ])
+                .to_owned(),
+        )
+        .exec(&database)
+        .await
+        .map_err(db_error)?;
+    Ok(Json(WebhookSecretResponse { secret }))
+}
+
+// 删除密钥，之后的信号全部拒绝
+pub async fn delete_webhook_secret(
+    Extension(user_id): Extension<String>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<impl IntoResponse, HandlerError> {
+    let owner_id = parse_owner_id(&user_id)?;
+    webhook_secrets::Entity::delete_by_id(owner_id)
+        .exec(&database)
+        .await
+        .map_err(db_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// 接收告警信号：校验密钥和时间窗口，按 signal_id 去重后交给开仓/平仓逻辑
+#[allow(clippy::too_many_arguments)]
+pub async fn receive_signal(
+    Extension(api_keys): Extension<Arc<KeyManager>>,
+    Extension(trades): Extension<Arc<SymbolMap<Mutex<Vec<Trade>>>>>,
+    Extension(prices): Extension<Arc<SymbolMap<Mutex<PriceBook>>>>,
+    Extension(filters): Extension<Arc<SymbolMap<SymbolFilter>>>,
+    Extension(id_generator): Extension<Arc<TradeIdGenerator>>,
+    Extension(database): Extension<DatabaseConnection>,
+    Extension(events): Extension<EventBus>,
+    Extension(grids): Extension<Arc<Grids>>,
+    body: String,
+) -> Result<Response, HandlerError> {
+    let signal: WebhookSignal = serde_json::from_str(&body)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid signal: {}", e)))?;
+    verify_signal(&database, &signal).await?;
+    let signal_id = signal
+        .signal_id
+        .clone()
+        .unwrap_or_else(|| sha256_hex(body.as_bytes()));
+    let record_id =
+        claim_signal(&database, signal.user_id, &signal_id, signal.action.name()).await?;
+    // 用户重启后未登录时 API Key 尚未加载
+    load_api_key(&api_keys, &database, &signal.user_id.to_string()).await?;
+
+    let user_id = signal.user_id.to_string();
+    let response = match signal.action {
+        SignalAction::Open(payload) => create_trade(
+            Extension(user_id),
+            Extension(api_keys),
+            Extension(trades),
+            Extension(prices),
+            Extension(filters),
+            Extension(id_generator),
+            Extension(database.clone()),
+            Extension(events),
+            Extension(grids),
+            Json(payload),
+        )
+        .await
+        .into_response(),
+        SignalAction::Close { symbol, id } => {
+            if !owns_trade(&trades, &user_id, &symbol, id).await {
+                Err::<(), _>((StatusCode::BAD_REQUEST, "Trade not found".to_string()))
+                    .into_response()
+            } else {
+                close_trade(
+                    Extension(user_id),
+                    Extension(api_keys),
+                    Extension(trades),
+                    Extension(prices),
+                    Extension(events),
+                    Json(CloseTradeRequest { id, symbol }),
+                )
+                .await
+                .into_response()
+            }
+        }
+        SignalAction::Flatten { symbol } => {
+            flatten(&user_id, symbol, api_keys, trades, prices, events)
+                .await
+                .into_response()
+        }
+    };
+
+    let status = webhook_signals::ActiveModel {
+        id: Set(record_id),
+        status: Set(Some(response.status().as_u16())),
+        ..Default::default()
+    };
+    if let Err(e) = status.update(&database).await {
+        eprintln!("Webhook 信号状态写入失败 {}：{}", signal_id, e);
+    }
+    Ok(response)
+}
+
+async fn verify_signal(
+    database: &DatabaseConnection,
+    signal: &WebhookSignal,
+) -> Result<(), HandlerError> {
+    let stored = webhook_secrets::Entity::find_by_id(signal.user_id)
+        .one(database)
+        .await
+        .map_err(db_error)?;
+    // 用户不存在和密钥错误返回相同的错误
+    let valid = stored.is_some_and(|s| s.secret_hash == sha256_hex(signal.secret.as_bytes()));
+    if !valid {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            "Invalid webhook secret".to_string(),
+        ));
+    }
+    if unix_timestamp().abs_diff(signal.timestamp) > WEBHOOK_MAX_SKEW_SECS {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            "Signal timestamp outside allowed window".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// 写入信号记录，唯一约束冲突说明该信号已处理过
+async fn claim_signal(
+    database: &DatabaseConnection,
+    owner_id: i64,
+    signal_id: &str,
+    action: &str,
+) -> Result<i64, HandlerError> {
+    let record = webhook_signals::ActiveModel {
+        owner_id: Set(owner_id),
+        signal_id: Set(signal_id.to_string()),
+        action: Set(action.to_string()),
+        ..Default::default()
+    };
+    match record.insert(database).await {
+        Ok(model) => Ok(model.id),
+        Err(e) if matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => Err((
+            StatusCode::CONFLICT,
+            format!("Duplicate signal {}", signal_id),
+        )),
+        Err(e) => Err(db_error(e)),
+    }
+}
+
+async fn owns_trade(
+    trades: &SymbolMap<Mutex<Vec<Trade>>>,
+    user_id: &str,
+    symbol: &str,
+    id: usize,
+) -> bool {
+    match trades.get(symbol) {
+        Some(mutex_vec) => mutex_vec
+            .lock()
+            .await
+            .iter()
+            .any(|t| t.id == id && t.owner_id == user_id),
+        None => false,
+    }
+}
+
+// 逐笔平掉用户的交易；正在平仓或已结束的交易跳过
+async fn flatten(
+    user_id: &str,
+    symbol: Option<String>,
+    api_keys: Arc<KeyManager>,
+    trades: Arc<SymbolMap<Mutex<Vec<Trade>>>>,
+    prices: Arc<SymbolMap<Mutex<PriceBook>>>,
+    events: EventBus,
+) -> Json<Vec<FlattenResult>> {
+    let mut targets = Vec::new();
+    for (trade_symbol, mutex_vec) in trades.entries() {
+        if symbol.as_ref().is_some_and(|s| *s != trade_symbol) {
+            continue;
+        }
+        let vec = mutex_vec.lock().await;
+        targets.extend(
+            vec.iter()
+                .filter(|t| {
+                    t.owner_id == user_id
+                        && !matches!(t.status, TradeStatus::Closing | TradeStatus::Closed)
+                })
+                .map(|t| (trade_symbol.clone(), t.id)),
+        );
+    }
+
+    let mut results = Vec::new();
+    for (symbol, id) in targets {
+        let result = close_trade(
+            Extension(user_id.to_string()),
+            Extension(api_keys.clone()),
+            Extension(trades.clone()),
+            Extension(prices.clone()),
+            Extension(events.clone()),
+            Json(CloseTradeRequest {
+                id,
+                symbol: symbol.clone(),
+            }),
+        )
+        .await;
+        results.push(FlattenResult {
+            id,
+            symbol,
+            closed: result.is_ok(),
+            error: result.err().map(|(_, message)| message),
+        });
+    }
+    Json(results)
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+fn db_error(e: DbErr) -> HandlerError {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        format!("Database error: {}", e),
+    )
+}
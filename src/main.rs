@@ -1,28 +1,46 @@
+mod backtest;
 mod binance;
 mod db;
 mod error;
+mod grid;
 mod handlers;
 mod models;
 mod mw;
 mod orm;
 mod routes;
 mod secret_key;
+mod symbol;
 mod trade;
 mod utils;
 mod websocket_lib;
 
-use binance::leverage::get_quantity_precision;
-use db::connect_db;
+use binance::leverage::get_symbol_filters;
+use db::{connect_db, migration::run_migrations};
 use dotenvy::dotenv;
-use futures_util::future::join_all;
-use sea_orm::DatabaseConnection;
-use std::{collections::HashMap, env, sync::Arc};
-use trade::{Adjustment, AdjustmentConfig, Trade};
-use utils::{create_adjustment_config_raw, TradeIdGenerator};
+use grid::Grids;
+use handlers::grid_handler::resume_grids;
+use handlers::trigger_handler::execute_triggers;
+use std::{env, sync::Arc, time::Duration};
+use symbol::{
+    find_enabled_symbols,
+    refresh::{refresh_symbols, DiscoveryRules, DEFAULT_REFRESH_SECS},
+    seed_default_symbols, SymbolMap,
+};
+use trade::{
+    alert::alert_failures,
+    candle::{feed_closed_klines, CANDLE_POLL_SECS},
+    equity::{record_equity, DEFAULT_SNAPSHOT_SECS},
+    event::EventBus,
+    preset::seed_system_presets,
+    price::PriceBook,
+    record::record_closes,
+    trigger::{find_armed_triggers, Trigger, Triggers},
+};
+use utils::TradeIdGenerator;
 
 use service_utils_rs::{services::jwt::Jwt, settings::Settings};
 use tokio::{self, sync::Mutex};
-use websocket_lib::connection::connect_to_websocket;
+use websocket_lib::feed::MarketFeed;
 
 #[tokio::main]
 async fn main() {
@@ -31,158 +49,134 @@ async fn main() {
     let jwt = Jwt::new(settings.jwt);
     let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
     let database = connect_db(&database_url).await.unwrap();
+    run_migrations(&database).await.unwrap();
+    seed_system_presets(&database).await.unwrap();
     let port = env::var("PORT").expect("PORT must be set");
-    let base_symbols = vec![
-        "ada", "crv", "doge", "dot", "hbar", "om", "xlm", "xrp", "sui", "wif", "render", "neiro",
-        "pnut", "act", "ltc", "trx", "bnb", "wld", "fil",
-    ];
 
-    // let base_symbols = vec![
-    //     "ADA", "CRV", "DOGE", "DOT", "HBAR", "OM", "SHIB", "XLM", "XRP", "SOL", "SUI", "WIF",
-    //     "RENDER", "NEIRO", "PNUT", "ACT", "USUAL", "LTC", "TRX", "BNB", "WLD", "FIL",
-    // ];
+    // 交易对以数据库为准，运行时通过 /symbol 接口启用或停用
+    seed_default_symbols(&database).await.unwrap();
+    let symbols = find_enabled_symbols(&database).await.unwrap();
 
-    let symbols: Vec<String> = base_symbols.iter().map(|s| format!("{}usdt", s)).collect();
-
-    let precisions = get_quantity_precision(&symbols).await.unwrap();
-    println!("{:?}", &precisions);
+    let filters = SymbolMap::from_map(get_symbol_filters(&symbols).await.unwrap());
 
     // 初始化共享状态
-    let trades = init_trade(&symbols);
-    let prices = init_price(&symbols);
+    let trades = SymbolMap::new(&symbols, || Mutex::new(Vec::new()));
+    let prices = SymbolMap::new(&symbols, || Mutex::new(PriceBook::default()));
     let id_generator = Arc::new(TradeIdGenerator::new());
-    let adjustment = init_adjustment();
     let api_keys = secret_key::KeyManager::new();
+    let events = EventBus::new();
+
+    // 平仓记录由事件订阅者写入
+    tokio::spawn(record_closes(
+        events.subscribe(),
+        database.clone(),
+        api_keys.clone(),
+    ));
+
+    // 平仓或止盈多次失败时告警，ALERT_WEBHOOK_URL 可配置推送地址
+    tokio::spawn(alert_failures(
+        events.subscribe(),
+        env::var("ALERT_WEBHOOK_URL")
+            .ok()
+            .filter(|url| !url.is_empty()),
+    ));
+
+    // 定时写入账户权益快照，EQUITY_SNAPSHOT_SECS 可调整间隔
+    let snapshot_secs = env::var("EQUITY_SNAPSHOT_SECS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(DEFAULT_SNAPSHOT_SECS);
+    tokio::spawn(record_equity(
+        database.clone(),
+        Duration::from_secs(snapshot_secs),
+    ));
+
+    // 吊灯止损在 K 线收盘时更新 ATR
+    tokio::spawn(feed_closed_klines(
+        trades.clone(),
+        events.clone(),
+        Duration::from_secs(CANDLE_POLL_SECS),
+    ));
+
+    // 网格在触发器之前创建，触发开仓时统计网格持仓
+    let grids = Arc::new(Grids::default());
+
+    // 恢复仍在布防的触发器，触发后由执行任务开仓或通知
+    let (triggers, fired) = Triggers::new(&symbols);
+    for model in find_armed_triggers(&database).await.unwrap() {
+        match Trigger::from_model(&model) {
+            Ok(trigger) => triggers.arm(trigger).await,
+            Err(e) => eprintln!("触发器 {} 无法恢复：{}", model.id, e),
+        }
+    }
+    tokio::spawn(execute_triggers(
+        fired,
+        triggers.clone(),
+        api_keys.clone(),
+        trades.clone(),
+        prices.clone(),
+        filters.clone(),
+        id_generator.clone(),
+        database.clone(),
+        events.clone(),
+        grids.clone(),
+    ));
+
+    // 恢复未停止的网格
+    resume_grids(
+        grids.clone(),
+        prices.clone(),
+        api_keys.clone(),
+        database.clone(),
+    )
+    .await
+    .unwrap();
+
+    let feed = MarketFeed::from_env(
+        trades.clone(),
+        prices.clone(),
+        events.clone(),
+        triggers.clone(),
+    );
+    // 交易所找不到的交易对暂不订阅，刷新任务发现其出现后再启动
+    for symbol in symbols.iter().filter(|s| filters.contains_key(s)) {
+        feed.start(symbol);
+    }
 
-    let ws_task = start_websocket(&symbols, trades.clone(), prices.clone(), database.clone());
+    // 定时刷新交易规则，EXCHANGE_INFO_REFRESH_SECS 可调整间隔
+    let refresh_secs = env::var("EXCHANGE_INFO_REFRESH_SECS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(DEFAULT_REFRESH_SECS);
+    tokio::spawn(refresh_symbols(
+        database.clone(),
+        filters.clone(),
+        trades.clone(),
+        feed.clone(),
+        events.clone(),
+        DiscoveryRules::from_env(),
+        Duration::from_secs(refresh_secs),
+    ));
 
     let routes = routes::create_routes(
         trades.clone(),
         prices.clone(),
         id_generator.clone(),
         database,
-        Arc::new(precisions),
-        adjustment,
+        events,
+        filters,
         jwt,
         api_keys,
+        triggers,
+        grids,
+        feed,
     );
 
     let addr = format!("0.0.0.0:{}", port);
 
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
 
-    let http_task = axum::serve(listener, routes);
-    let _ = tokio::join!(ws_task, http_task);
-}
-
-// WebSocket 启动函数
-async fn start_websocket(
-    symbles: &Vec<String>,
-    trades: Arc<HashMap<String, Mutex<Vec<Trade>>>>,
-    prices: Arc<HashMap<String, Mutex<(String, String)>>>,
-    database: DatabaseConnection,
-) {
-    let mut tasks: Vec<tokio::task::JoinHandle<()>> = Vec::new();
-
-    for symbol in symbles {
-        let symbol_clone = symbol.clone();
-        let trades_clone = trades.clone();
-        let prices_clone = prices.clone();
-        let database_clone = database.clone();
-        let task = tokio::spawn(async move {
-            connect_to_websocket(symbol_clone, trades_clone, prices_clone, database_clone).await;
-        });
-        tasks.push(task);
-    }
-
-    // 使用 join_all 处理所有 WebSocket 任务
-    join_all(tasks).await;
-}
-
-fn init_trade(symbols: &Vec<String>) -> Arc<HashMap<String, Mutex<Vec<Trade>>>> {
-    let map = symbols
-        .iter()
-        .map(|symbol| (symbol.clone(), Mutex::new(Vec::new())))
-        .collect::<HashMap<_, _>>();
-
-    Arc::new(map)
-}
-
-fn init_price(symbols: &Vec<String>) -> Arc<HashMap<String, Mutex<(String, String)>>> {
-    // let r = get_quantity_precision(symbols).await.unwrap();
-    let map = symbols
-        .iter()
-        .map(|symbol| {
-            (
-                symbol.clone(),
-                Mutex::new(("0".to_string(), "0".to_string())),
-            )
-        })
-        .collect::<HashMap<_, _>>();
-
-    Arc::new(map)
-}
-
-fn init_adjustment() -> Arc<HashMap<u8, Mutex<AdjustmentConfig>>> {
-    let mut map = HashMap::new();
-    let adjustment = vec![
-        Adjustment {
-            min: 0.10,
-            max: Some(0.19),
-            adjustment: 0.02,
-        },
-        Adjustment {
-            min: 0.20,
-            max: Some(0.29),
-            adjustment: 0.04,
-        },
-        Adjustment {
-            min: 0.30,
-            max: Some(0.39),
-            adjustment: 0.09,
-        },
-        Adjustment {
-            min: 0.40,
-            max: Some(0.49),
-            adjustment: 0.16,
-        },
-        Adjustment {
-            min: 0.50,
-            max: Some(0.59),
-            adjustment: 0.25,
-        },
-        Adjustment {
-            min: 0.60,
-            max: Some(0.69),
-            adjustment: 0.36,
-        },
-        Adjustment {
-            min: 0.70,
-            max: Some(0.79),
-            adjustment: 0.49,
-        },
-        Adjustment {
-            min: 0.8,
-            max: Some(0.89),
-            adjustment: 0.64,
-        },
-        Adjustment {
-            min: 0.9,
-            max: Some(0.99),
-            adjustment: 0.81,
-        },
-        Adjustment {
-            min: 1.0,
-            max: Some(1.1),
-            adjustment: 0.90,
-        },
-    ];
-
-    let a = create_adjustment_config_raw(adjustment.clone());
-    let b = create_adjustment_config_raw(adjustment.clone());
-
-    map.insert(1, Mutex::new(a));
-    map.insert(2, Mutex::new(b));
-
-    Arc::new(map)
+    axum::serve(listener, routes).await.unwrap();
 }
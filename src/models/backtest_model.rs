@@ -0,0 +1,58 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    backtest::{data::MarketTick, EntrySchedule},
+    models::trade_model::TakeProfitRequest,
+    trade::{price::PriceSource, strategy::StopStrategyConfig, Adjustment, TradeDirection},
+};
+
+// 回测请求，参数与开仓请求一致
+#[derive(Deserialize)]
+pub struct BacktestRequest {
+    pub symbol: String,
+    pub direction: TradeDirection,
+    pub leverage: Decimal,
+    pub margin: Decimal,
+    pub stop_loss_percent: Decimal,
+    #[serde(default)]
+    pub stop_strategy: StopStrategyConfig,
+    pub adjustment_id: Option<i64>,           // 阶梯预设 ID
+    pub adjustments: Option<Vec<Adjustment>>, // 直接提供的阶梯配置，优先于 adjustment_id
+    #[serde(default)]
+    pub take_profits: Vec<TakeProfitRequest>,
+    #[serde(default)]
+    pub price_source: PriceSource,
+    pub fee_rate: Option<Decimal>, // 为空时使用默认 taker 费率
+    #[serde(default)]
+    pub slippage: Decimal, // 每笔市价单的滑点比例
+    #[serde(default)]
+    pub entry: EntrySchedule,
+    pub data: BacktestData,
+}
+
+// 回测数据来源
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+pub enum BacktestData {
+    // 直接提供的行情，按时间顺序
+    Ticks {
+        ticks: Vec<MarketTick>,
+    },
+    // 从交易所拉取的历史 K 线，时间为毫秒
+    Klines {
+        interval: String,
+        start_time: u64,
+        end_time: u64,
+    },
+}
+
+#[derive(Serialize)]
+pub struct BacktestJobResponse {
+    pub id: u64,
+}
+
+#[derive(Deserialize)]
+pub struct BacktestJobParams {
+    pub id: u64,
+}
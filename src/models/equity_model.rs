@@ -0,0 +1,8 @@
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct EquityQueryParams {
+    pub start_time: Option<u32>,
+    pub end_time: Option<u32>,
+    pub max_points: Option<usize>, // 返回点数上限，超过时降采样
+}
@@ -0,0 +1,36 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::{grid::GridBot, trade::TradeDirection};
+
+#[derive(Deserialize)]
+pub struct CreateGridRequest {
+    pub symbol: String,
+    pub direction: TradeDirection, // 做多网格低买高卖，做空网格高卖低买
+    pub lower_price: Decimal,
+    pub upper_price: Decimal,
+    pub grid_count: u32,
+    pub investment: Decimal, // 投入保证金
+    pub leverage: Decimal,
+}
+
+#[derive(Deserialize)]
+pub struct GridIdRequest {
+    pub id: i64,
+}
+
+#[derive(Deserialize)]
+pub struct GridQueryParams {
+    pub symbol: Option<String>,
+    pub status: Option<String>, // 'Running', 'Paused', 'Stopped'
+}
+
+// 网格详情，运行中的网格附带当前价格和浮动盈亏
+#[derive(Serialize)]
+pub struct GridDetail {
+    #[serde(flatten)]
+    pub grid: GridBot,
+    pub price: Option<Decimal>,
+    pub holding: Decimal,
+    pub unrealized_profit: Decimal,
+}
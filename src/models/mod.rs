@@ -1,6 +1,15 @@
 pub mod auth_model;
+pub mod backtest_model;
+pub mod equity_model;
+pub mod grid_model;
+pub mod paper_model;
+pub mod preset_model;
 pub mod record_model;
+pub mod risk_model;
+pub mod symbol_model;
 pub mod trade_model;
+pub mod trigger_model;
+pub mod webhook_model;
 
 use sea_orm::prelude::DateTimeWithTimeZone;
 use serde::{Deserialize, Serialize};
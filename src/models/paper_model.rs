@@ -0,0 +1,39 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::orm::paper_trades;
+
+// 修改模拟盘账户，未提供的字段保持不变
+#[derive(Deserialize)]
+pub struct UpdatePaperAccountRequest {
+    pub enabled: Option<bool>, // 开仓请求未指定 paper 时默认走模拟盘
+    pub initial_balance: Option<Decimal>,
+    pub fee_rate: Option<Decimal>,
+}
+
+// 模拟盘账户及虚拟余额
+#[derive(Serialize)]
+pub struct PaperAccountResponse {
+    pub paper: bool,
+    pub enabled: bool,
+    pub initial_balance: Decimal,
+    pub fee_rate: Decimal,
+    pub balance: Decimal,     // 初始余额加已实现净盈亏
+    pub used_margin: Decimal, // 进行中的模拟交易占用的保证金
+    pub available: Decimal,
+}
+
+// 模拟盘平仓记录
+#[derive(Serialize)]
+pub struct PaperTradeRecord {
+    #[serde(flatten)]
+    pub record: paper_trades::Model,
+    pub paper: bool,
+}
+
+// 重置结果：删除的平仓记录数
+#[derive(Serialize)]
+pub struct PaperResetResponse {
+    pub paper: bool,
+    pub deleted: u64,
+}
@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{orm::adjustment_presets, trade::Adjustment};
+
+// 创建预设请求
+#[derive(Deserialize)]
+pub struct CreatePresetRequest {
+    pub name: String,
+    pub adjustments: Vec<Adjustment>,
+}
+
+// 更新预设请求，未提供的字段保持不变
+#[derive(Deserialize)]
+pub struct UpdatePresetRequest {
+    pub id: i64,
+    pub name: Option<String>,
+    pub adjustments: Option<Vec<Adjustment>>,
+}
+
+// 复制预设请求，name 为空时沿用原名
+#[derive(Deserialize)]
+pub struct ClonePresetRequest {
+    pub id: i64,
+    pub name: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct PresetIdParams {
+    pub id: i64,
+}
+
+// 导入导出格式
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PresetFormat {
+    #[default]
+    Json,
+    Toml,
+}
+
+#[derive(Deserialize)]
+pub struct ExportPresetParams {
+    pub id: i64,
+    #[serde(default)]
+    pub format: PresetFormat,
+}
+
+#[derive(Deserialize)]
+pub struct ImportPresetParams {
+    #[serde(default)]
+    pub format: PresetFormat,
+}
+
+// 导入导出的文档结构
+#[derive(Deserialize, Serialize)]
+pub struct PresetDocument {
+    pub name: String,
+    pub adjustments: Vec<Adjustment>,
+}
+
+#[derive(Serialize)]
+pub struct PresetResponse {
+    pub id: i64,
+    pub name: String,
+    pub owner_id: Option<i64>,
+    pub is_system: bool,
+    pub adjustments: Vec<Adjustment>,
+    pub created_at: u32,
+    pub updated_at: u32,
+}
+
+impl PresetResponse {
+    pub fn new(preset: adjustment_presets::Model, adjustments: Vec<Adjustment>) -> Self {
+        Self {
+            id: preset.id,
+            name: preset.name,
+            owner_id: preset.owner_id,
+            is_system: preset.owner_id.is_none(),
+            adjustments,
+            created_at: preset.created_at,
+            updated_at: preset.updated_at,
+        }
+    }
+}
@@ -0,0 +1,19 @@
+use serde::Serialize;
+
+use crate::trade::risk::RiskPolicy;
+
+// 用户风控策略及急停状态
+#[derive(Serialize)]
+pub struct RiskPolicyResponse {
+    pub policy: RiskPolicy,
+    pub kill_switch: bool,
+    pub updated_at: u32,
+}
+
+// 急停结果：撤销的未成交开仓单数、进入平仓的交易数和停止的网格数
+#[derive(Serialize)]
+pub struct KillSwitchResponse {
+    pub cancelled: usize,
+    pub closing: usize,
+    pub grids_stopped: usize,
+}
@@ -1,16 +1,99 @@
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
-use crate::trade::TradeDirection;
+use crate::trade::{
+    price::PriceSource, strategy::StopStrategyConfig, Adjustment, PriceStep, ReanchorPolicy,
+    TakeProfit, TradeDirection, TradeStatus,
+};
 
 #[derive(Debug, Deserialize, Validate)]
 pub struct CreateTradeRequest {
     pub symbol: String,
     pub direction: TradeDirection,
-    pub leverage: f64,
-    pub margin: f64,
-    pub stop_loss_percent: f64,
-    pub adjustment_id: u8,
+    pub leverage: Decimal,
+    #[serde(default)]
+    pub margin: Decimal, // 保证金，按风险计算仓位时忽略
+    #[serde(default)]
+    pub sizing: SizingMode, // 仓位计算方式，默认按保证金
+    pub stop_loss_percent: Decimal,
+    pub adjustment_id: i64, // 阶梯预设 ID
+    #[serde(default)]
+    pub stop_strategy: StopStrategyConfig, // 止损策略，默认为阶梯调整
+    #[serde(default)]
+    pub take_profits: Vec<TakeProfitRequest>, // 止盈目标（可选）
+    #[serde(default)]
+    pub price_source: PriceSource, // 止损触发价格来源，默认盘口价
+    #[serde(default)]
+    pub entry: EntryOrder, // 开仓方式，默认市价
+    pub entry_expiry_secs: Option<u64>, // 非市价开仓的有效期（秒），为空时一直挂单
+    #[serde(default)]
+    pub liquidation_guard: LiquidationGuard, // 止损越过强平价时的处理方式，默认拒绝
+    pub paper: Option<bool>, // 模拟盘开仓，为空时按模拟盘账户设置
+}
+
+// 止损越过预估强平价时的处理方式
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+pub enum LiquidationGuard {
+    #[default]
+    Reject,
+    // 收紧止损比例，使止损价留在强平价之前
+    Clamp,
+}
+
+// 仓位计算方式
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(tag = "type")]
+pub enum SizingMode {
+    // 数量 = 保证金 * 杠杆 / 价格
+    #[default]
+    Margin,
+    // 止损时亏损固定金额（USDT），含预估手续费
+    RiskAmount {
+        amount: Decimal,
+    },
+    // 止损时亏损账户权益的固定比例，例如 0.01 表示 1%
+    RiskPercent {
+        percent: Decimal,
+    },
+}
+
+// 开仓订单类型
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(tag = "type")]
+pub enum EntryOrder {
+    #[default]
+    Market,
+    // 限价单，post_only 时以 GTX 挂单，只做 Maker
+    Limit {
+        price: Decimal,
+        #[serde(default)]
+        post_only: bool,
+    },
+    // 条件单：价格突破 stop_price 后以市价（或 price 限价）开仓
+    Stop {
+        stop_price: Decimal,
+        price: Option<Decimal>,
+    },
+}
+
+impl EntryOrder {
+    // 用于计算数量和初始止损的预期开仓价，市价单为空
+    pub fn reference_price(&self) -> Option<Decimal> {
+        match self {
+            EntryOrder::Market => None,
+            EntryOrder::Limit { price, .. } => Some(*price),
+            EntryOrder::Stop { stop_price, price } => Some(price.unwrap_or(*stop_price)),
+        }
+    }
+}
+
+// 止盈目标：price 与 roi 二选一，fraction 为平仓比例
+#[derive(Debug, Deserialize, Clone)]
+pub struct TakeProfitRequest {
+    pub price: Option<Decimal>, // 目标价格
+    pub roi: Option<Decimal>,   // 杠杆收益率，例如 0.5 表示 50%
+    pub fraction: Decimal,      // 平仓比例 (0, 1]
 }
 
 #[derive(Debug, Serialize, Validate)]
@@ -18,11 +101,18 @@ pub struct CreateTradeResponse {
     pub id: usize,
     pub symbol: String,
     pub direction: TradeDirection,
-    pub leverage: f64,
-    pub margin: f64,
-    pub quantity: String,
-    pub entry_price: String,
-    pub stop_price: String,
+    pub leverage: Decimal,
+    pub margin: Decimal,
+    pub quantity: Decimal,
+    pub entry_price: Decimal,
+    pub stop_price: Decimal,
+    pub stop_strategy: StopStrategyConfig,
+    pub take_profits: Vec<TakeProfit>,
+    pub price_source: PriceSource,
+    pub status: TradeStatus,
+    pub liquidation_price: Option<Decimal>, // 成交后取交易所持仓强平价，否则为预估值；无法估算时为空
+    pub liquidation_distance: Option<Decimal>, // 开仓价到强平价的距离比例
+    pub paper: bool,
 }
 
 // 平仓请求结构体
@@ -38,9 +128,70 @@ pub struct CloseTradeResponse {
     pub id: usize,
     pub symbol: String,
     pub direction: TradeDirection,
-    pub entry_price: f64,
-    pub close_price: String,
-    pub quantity: String,
+    pub entry_price: Decimal,
+    pub close_price: Decimal,
+    pub quantity: Decimal,
+    pub paper: bool,
+}
+
+// 加仓请求结构体
+#[derive(Deserialize)]
+pub struct AddToTradeRequest {
+    pub id: usize,
+    pub symbol: String,
+    pub margin: Decimal, // 追加保证金，按交易原杠杆计算数量
+    #[serde(default)]
+    pub reanchor: ReanchorPolicy,
+}
+
+// 加仓响应结构体
+#[derive(Serialize)]
+pub struct AddToTradeResponse {
+    pub id: usize,
+    pub symbol: String,
+    pub fill_price: Decimal,     // 本次成交均价
+    pub added_quantity: Decimal, // 本次加仓数量
+    pub entry_price: Decimal,    // 加权平均开仓价
+    pub quantity: Decimal,       // 加仓后总数量
+    pub stop_price: Decimal,
+    pub paper: bool,
+}
+
+// 部分平仓请求：percent 与 quantity 二选一
+#[derive(Deserialize)]
+pub struct PartialCloseRequest {
+    pub id: usize,
+    pub symbol: String,
+    pub percent: Option<Decimal>,  // 剩余持仓的平仓比例 (0, 1]
+    pub quantity: Option<Decimal>, // 平仓数量，按数量步长向下取整
+}
+
+// 部分平仓响应结构体
+#[derive(Serialize)]
+pub struct PartialCloseResponse {
+    pub id: usize,
+    pub symbol: String,
+    pub close_price: Decimal,
+    pub closed_quantity: Decimal,
+    pub remaining_quantity: Decimal,
+    pub status: TradeStatus,
+    pub paper: bool,
+}
+
+// 修改进行中的交易，未提供的字段保持不变
+#[derive(Deserialize)]
+pub struct ModifyTradeRequest {
+    pub id: usize,
+    pub symbol: String,
+    pub stop_loss: Option<Decimal>, // 手动设置止损价
+    pub stop_strategy: Option<StopStrategyConfig>,
+    pub adjustment_id: Option<i64>, // 阶梯预设，切换到阶梯策略时必填
+    pub take_profits: Option<Vec<TakeProfitRequest>>, // 替换全部止盈目标，空数组表示清除
+}
+
+#[derive(Deserialize)]
+pub struct TradeLegQueryParams {
+    pub trade_id: i64,
 }
 
 #[derive(Deserialize)]
@@ -49,3 +200,42 @@ pub struct TradeQueryParams {
     pub start_time: Option<u32>, // 起始时间戳 (可选)
     pub end_time: Option<u32>,   // 结束时间戳 (可选)
 }
+
+// 交易统计筛选条件，时间范围按平仓时间
+#[derive(Deserialize)]
+pub struct AnalyticsQueryParams {
+    pub symbol: Option<String>,
+    pub direction: Option<TradeDirection>,
+    pub adjustment_id: Option<i64>, // 阶梯预设 ID
+    pub start_time: Option<u32>,
+    pub end_time: Option<u32>,
+}
+
+// 止损轨迹模拟请求：阶梯策略需提供 adjustment_id 或内联 adjustments
+#[derive(Deserialize)]
+pub struct SimulateStopRequest {
+    pub symbol: Option<String>, // 提供时按该品种的价格精度取整
+    pub direction: TradeDirection,
+    pub entry_price: Decimal,
+    pub leverage: Decimal,
+    pub stop_loss_percent: Decimal,
+    #[serde(default)]
+    pub stop_strategy: StopStrategyConfig,
+    pub adjustment_id: Option<i64>,
+    pub adjustments: Option<Vec<Adjustment>>,
+    pub prices: Vec<Decimal>, // 假设的价格路径
+}
+
+#[derive(Serialize)]
+pub struct SimulateStopResponse {
+    pub initial_stop: Decimal,
+    pub steps: Vec<PriceStep>,
+    pub exit: Option<SimulatedExit>, // None 表示路径结束时仍未止损
+}
+
+#[derive(Serialize)]
+pub struct SimulatedExit {
+    pub index: usize, // 触发止损的价格序号
+    pub price: Decimal,
+    pub roi: Decimal, // 按触发价计算的杠杆收益率
+}
@@ -0,0 +1,24 @@
+use serde::Deserialize;
+
+use crate::trade::trigger::{TriggerAction, TriggerCondition, TriggerSource};
+
+#[derive(Deserialize)]
+pub struct CreateTriggerRequest {
+    pub symbol: String,
+    #[serde(default)]
+    pub source: TriggerSource, // 比较价格，默认中间价
+    pub condition: TriggerCondition,
+    pub action: TriggerAction,
+    pub expires_in_secs: Option<u32>, // 有效期（秒），为空时一直有效
+}
+
+#[derive(Deserialize)]
+pub struct CancelTriggerRequest {
+    pub id: i64,
+}
+
+#[derive(Deserialize)]
+pub struct TriggerQueryParams {
+    pub symbol: Option<String>,
+    pub status: Option<String>, // 'Armed', 'Fired', 'Failed', 'Expired', 'Cancelled'
+}
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+use super::trade_model::CreateTradeRequest;
+
+// 告警推送的信号，密钥放在请求体中（TradingView 等无法设置请求头）
+#[derive(Deserialize)]
+pub struct WebhookSignal {
+    pub user_id: i64,
+    pub secret: String,
+    pub signal_id: Option<String>, // 去重用的信号 ID，为空时按请求体内容去重
+    pub timestamp: u32,            // 信号生成时间（秒），超出允许窗口的信号被拒绝
+    pub action: SignalAction,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+pub enum SignalAction {
+    // 开仓，字段与 /trade/create 相同
+    Open(CreateTradeRequest),
+    // 平掉指定交易
+    Close { symbol: String, id: usize },
+    // 平掉该用户的全部交易，symbol 为空时包括所有品种
+    Flatten { symbol: Option<String> },
+}
+
+impl SignalAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SignalAction::Open(_) => "Open",
+            SignalAction::Close { .. } => "Close",
+            SignalAction::Flatten { .. } => "Flatten",
+        }
+    }
+}
+
+// 新生成的密钥，只返回这一次
+#[derive(Serialize)]
+pub struct WebhookSecretResponse {
+    pub secret: String,
+}
+
+// 全部平仓结果，每笔交易一条
+#[derive(Serialize)]
+pub struct FlattenResult {
+    pub id: usize,
+    pub symbol: String,
+    pub closed: bool,
+    pub error: Option<String>,
+}
@@ -2,5 +2,16 @@
 
 pub mod prelude;
 
+pub mod adjustment_presets;
+pub mod equity_snapshots;
+pub mod grids;
+pub mod paper_accounts;
+pub mod paper_trades;
+pub mod risk_policies;
+pub mod symbols;
+pub mod trade_legs;
 pub mod trades;
+pub mod triggers;
 pub mod users;
+pub mod webhook_secrets;
+pub mod webhook_signals;
@@ -8,6 +8,9 @@ use serde::Serialize;
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = true)]
     pub id: i64,
+    pub trade_id: i64,
+    #[sea_orm(column_type = "Text")]
+    pub owner_id: String,
     #[sea_orm(column_type = "Text")]
     pub symbol: String,
     #[sea_orm(column_type = "Text")]
@@ -20,6 +23,20 @@ pub struct Model {
     pub quantity: String,
     #[sea_orm(column_type = "Text")]
     pub leverage: String,
+    #[sea_orm(column_type = "Text")]
+    pub close_reason: String,
+    #[sea_orm(column_type = "Text")]
+    pub trigger_price: String,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub realized_pnl: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub commission: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub commission_asset: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub slippage: Option<String>,
+    pub opened_at: Option<u32>,
+    pub adjustment_id: Option<i64>,
     pub created_at: u32,
 }
 
@@ -0,0 +1,12 @@
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+use crate::handlers::backtest_handler::{create_backtest, get_backtest};
+
+pub fn routes_backtest() -> Router {
+    Router::new()
+        .route("/run", post(create_backtest))
+        .route("/job", get(get_backtest))
+}
@@ -0,0 +1,7 @@
+use axum::{routing::get, Router};
+
+use crate::handlers::equity_handler::get_equity_curve;
+
+pub fn routes_equity() -> Router {
+    Router::new().route("/curve", get(get_equity_curve))
+}
@@ -0,0 +1,18 @@
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+use crate::handlers::grid_handler::{
+    create_grid, get_grid, list_grids, pause_grid, resume_grid, stop_grid,
+};
+
+pub fn routes_grid() -> Router {
+    Router::new()
+        .route("/create", post(create_grid))
+        .route("/list", get(list_grids))
+        .route("/detail", get(get_grid))
+        .route("/pause", post(pause_grid))
+        .route("/resume", post(resume_grid))
+        .route("/stop", post(stop_grid))
+}
@@ -1,15 +1,29 @@
 mod auth_route;
+mod backtest_route;
+mod equity_route;
 pub mod error;
+mod grid_route;
+mod paper_route;
+mod preset_route;
 mod record_route;
+mod risk_route;
+mod symbol_route;
 mod trade_route;
+mod trigger_route;
+mod webhook_route;
 
-use std::{collections::HashMap, sync::Arc};
+use std::sync::Arc;
 
 use crate::{
+    backtest::job::BacktestJobs,
+    binance::leverage::SymbolFilter,
+    grid::Grids,
     mw::{auth_mw, cors::create_cors},
     secret_key::KeyManager,
-    trade::{AdjustmentConfig, Trade},
+    symbol::SymbolMap,
+    trade::{event::EventBus, price::PriceBook, trigger::Triggers, Trade},
     utils::TradeIdGenerator,
+    websocket_lib::feed::MarketFeed,
 };
 
 use auth_route::routes_auth;
@@ -20,15 +34,19 @@ use service_utils_rs::services::jwt::Jwt;
 use tokio::sync::Mutex;
 use trade_route::routes_trade;
 
+#[allow(clippy::too_many_arguments)]
 pub fn create_routes(
-    trads: Arc<HashMap<String, Mutex<Vec<Trade>>>>,
-    prices: Arc<HashMap<String, Mutex<(String, String)>>>,
+    trads: Arc<SymbolMap<Mutex<Vec<Trade>>>>,
+    prices: Arc<SymbolMap<Mutex<PriceBook>>>,
     id_generator: Arc<TradeIdGenerator>,
     database: DatabaseConnection,
-    precisions: Arc<HashMap<String, u8>>,
-    adjustment: Arc<HashMap<u8, Mutex<AdjustmentConfig>>>,
+    events: EventBus,
+    filters: Arc<SymbolMap<SymbolFilter>>,
     jwt: Jwt,
     api_keys: Arc<KeyManager>,
+    triggers: Arc<Triggers>,
+    grids: Arc<Grids>,
+    feed: Arc<MarketFeed>,
 ) -> Router {
     let cors = create_cors();
 
@@ -36,15 +54,29 @@ pub fn create_routes(
         // .merge(routes_manage())
         .nest("/trade", routes_trade())
         .nest("/record", record_route::routes_record())
+        .nest("/preset", preset_route::routes_preset())
+        .nest("/risk", risk_route::routes_risk())
+        .nest("/backtest", backtest_route::routes_backtest())
+        .nest("/paper", paper_route::routes_paper())
+        .nest("/webhook", webhook_route::routes_webhook())
+        .nest("/trigger", trigger_route::routes_trigger())
+        .nest("/grid", grid_route::routes_grid())
+        .nest("/equity", equity_route::routes_equity())
+        .nest("/symbol", symbol_route::routes_symbol())
         .route_layer(middleware::from_fn(auth_mw::auth))
         .nest("/auth", routes_auth())
+        .nest("/webhook", webhook_route::routes_signal())
         .layer(Extension(trads))
         .layer(Extension(prices))
         .layer(Extension(id_generator))
-        .layer(Extension(precisions))
-        .layer(Extension(adjustment))
+        .layer(Extension(filters))
         .layer(Extension(database))
+        .layer(Extension(events))
         .layer(Extension(jwt))
         .layer(Extension(api_keys))
+        .layer(Extension(triggers))
+        .layer(Extension(grids))
+        .layer(Extension(feed))
+        .layer(Extension(Arc::new(BacktestJobs::default())))
         .layer(cors)
 }
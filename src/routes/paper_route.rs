@@ -0,0 +1,18 @@
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+use crate::handlers::paper_handler::{
+    get_paper_account, get_paper_history, reset_paper_account, update_paper_account,
+};
+
+pub fn routes_paper() -> Router {
+    Router::new()
+        .route(
+            "/account",
+            get(get_paper_account).post(update_paper_account),
+        )
+        .route("/history", get(get_paper_history))
+        .route("/reset", post(reset_paper_account))
+}
@@ -0,0 +1,21 @@
+use axum::{
+    routing::{delete, get, post},
+    Router,
+};
+
+use crate::handlers::preset_handler::{
+    clone_preset, create_preset, delete_preset, export_preset, get_preset, import_preset,
+    list_presets, update_preset,
+};
+
+pub fn routes_preset() -> Router {
+    Router::new()
+        .route("/list", get(list_presets))
+        .route("/get", get(get_preset))
+        .route("/create", post(create_preset))
+        .route("/update", post(update_preset))
+        .route("/delete", delete(delete_preset))
+        .route("/clone", post(clone_preset))
+        .route("/export", get(export_preset))
+        .route("/import", post(import_preset))
+}
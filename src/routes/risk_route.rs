@@ -0,0 +1,15 @@
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+use crate::handlers::risk_handler::{
+    activate_kill_switch, get_risk_policy, reset_kill_switch, update_risk_policy,
+};
+
+pub fn routes_risk() -> Router {
+    Router::new()
+        .route("/policy", get(get_risk_policy).post(update_risk_policy))
+        .route("/kill_switch", post(activate_kill_switch))
+        .route("/reset_kill_switch", post(reset_kill_switch))
+}
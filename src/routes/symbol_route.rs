@@ -0,0 +1,13 @@
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+use crate::handlers::symbol_handler::{disable_symbol, enable_symbol, list_symbols};
+
+pub fn routes_symbol() -> Router {
+    Router::new()
+        .route("/list", get(list_symbols))
+        .route("/enable", post(enable_symbol))
+        .route("/disable", post(disable_symbol))
+}
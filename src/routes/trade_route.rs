@@ -6,19 +6,28 @@ use axum::{
 // use validator::Validate;
 
 use crate::handlers::trade_hander::{
-    close_trade, create_trade, delete_trade_by_id, get_adjustments, get_all_history_trades,
-    get_price, get_trade, get_user_hold, update_adjustments,
+    add_to_trade, close_trade, create_trade, delete_trade_by_id, get_adjustments,
+    get_all_history_trades, get_price, get_trade, get_trade_analytics, get_trade_legs,
+    get_user_hold, modify_trade, partial_close_trade, simulate_stop, trade_events,
+    update_adjustments,
 };
 
 pub fn routes_trade() -> Router {
     Router::new()
         .route("/create_trade", post(create_trade))
         .route("/close_trade", post(close_trade))
+        .route("/partial_close", post(partial_close_trade))
+        .route("/modify_trade", post(modify_trade))
+        .route("/add_to_trade", post(add_to_trade))
+        .route("/get_trade_legs", get(get_trade_legs))
+        .route("/events", get(trade_events))
         .route("/get_trade", get(get_trade))
         .route("/get_price", get(get_price))
         .route("/get_all_history_trades", get(get_all_history_trades))
+        .route("/analytics", get(get_trade_analytics))
         .route("/delete_trade", delete(delete_trade_by_id))
         .route("/get_adjustments", get(get_adjustments))
         .route("/update_adjustments", post(update_adjustments))
         .route("/get_hold", get(get_user_hold))
+        .route("/simulate_stop", post(simulate_stop))
 }
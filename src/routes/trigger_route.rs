@@ -0,0 +1,16 @@
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+use crate::handlers::trigger_handler::{
+    cancel_trigger, create_trigger, list_triggers, trigger_events,
+};
+
+pub fn routes_trigger() -> Router {
+    Router::new()
+        .route("/create", post(create_trigger))
+        .route("/list", get(list_triggers))
+        .route("/cancel", post(cancel_trigger))
+        .route("/events", get(trigger_events))
+}
@@ -0,0 +1,18 @@
+use axum::{routing::post, Router};
+
+use crate::handlers::webhook_handler::{
+    delete_webhook_secret, receive_signal, rotate_webhook_secret,
+};
+
+// 密钥管理，需要登录
+pub fn routes_webhook() -> Router {
+    Router::new().route(
+        "/secret",
+        post(rotate_webhook_secret).delete(delete_webhook_secret),
+    )
+}
+
+// 告警推送入口，按请求体中的密钥认证
+pub fn routes_signal() -> Router {
+    Router::new().route("/signal", post(receive_signal))
+}
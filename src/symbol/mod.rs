@@ -0,0 +1,171 @@
+pub mod refresh;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, Set,
+};
+
+use crate::{orm::symbols, utils::unix_timestamp};
+
+// 交易对表为空时写入的初始交易对
+const DEFAULT_BASE_SYMBOLS: [&str; 19] = [
+    "ada", "crv", "doge", "dot", "hbar", "om", "xlm", "xrp", "sui", "wif", "render", "neiro",
+    "pnut", "act", "ltc", "trx", "bnb", "wld", "fil",
+];
+
+// 按交易对索引的共享状态，运行时可以增删交易对。
+// 读取时克隆出 Arc，不在持有读写锁时等待
+#[derive(Debug)]
+pub struct SymbolMap<V> {
+    map: RwLock<HashMap<String, Arc<V>>>,
+}
+
+impl<V> Default for SymbolMap<V> {
+    fn default() -> Self {
+        Self {
+            map: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+impl<V> SymbolMap<V> {
+    pub fn new(symbols: &[String], init: impl Fn() -> V) -> Arc<Self> {
+        let map = symbols
+            .iter()
+            .map(|symbol| (symbol.clone(), Arc::new(init())))
+            .collect();
+        Arc::new(Self {
+            map: RwLock::new(map),
+        })
+    }
+
+    pub fn from_map(map: HashMap<String, V>) -> Arc<Self> {
+        let map = map.into_iter().map(|(k, v)| (k, Arc::new(v))).collect();
+        Arc::new(Self {
+            map: RwLock::new(map),
+        })
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<Arc<V>> {
+        self.map.read().unwrap().get(symbol).cloned()
+    }
+
+    pub fn contains_key(&self, symbol: &str) -> bool {
+        self.map.read().unwrap().contains_key(symbol)
+    }
+
+    // 已存在时保留原值，返回是否新增
+    pub fn insert(&self, symbol: &str, value: V) -> bool {
+        let mut map = self.map.write().unwrap();
+        if map.contains_key(symbol) {
+            return false;
+        }
+        map.insert(symbol.to_string(), Arc::new(value));
+        true
+    }
+
+    // 覆盖已有的值，用于刷新交易规则
+    pub fn replace(&self, symbol: &str, value: V) {
+        self.map
+            .write()
+            .unwrap()
+            .insert(symbol.to_string(), Arc::new(value));
+    }
+
+    pub fn remove(&self, symbol: &str) -> Option<Arc<V>> {
+        self.map.write().unwrap().remove(symbol)
+    }
+
+    // 当前所有交易对的快照，遍历期间新增或删除的交易对不影响结果
+    pub fn entries(&self) -> Vec<(String, Arc<V>)> {
+        self.map
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect()
+    }
+}
+
+// 交易对统一为小写，只支持 USDT 永续合约
+pub fn normalize_symbol(symbol: &str) -> Result<String, String> {
+    let symbol = symbol.trim().to_lowercase();
+    let valid = symbol.len() > 4
+        && symbol.ends_with("usdt")
+        && symbol.chars().all(|c| c.is_ascii_alphanumeric());
+    if !valid {
+        return Err(format!("Invalid symbol: {}", symbol));
+    }
+    Ok(symbol)
+}
+
+// 首次启动时写入初始交易对，之后以数据库为准
+pub async fn seed_default_symbols(db: &DatabaseConnection) -> Result<(), DbErr> {
+    if symbols::Entity::find().count(db).await? > 0 {
+        return Ok(());
+    }
+    for base in DEFAULT_BASE_SYMBOLS {
+        symbols::ActiveModel {
+            symbol: Set(format!("{}usdt", base)),
+            enabled: Set(true),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+    }
+    Ok(())
+}
+
+pub async fn find_enabled_symbols(db: &DatabaseConnection) -> Result<Vec<String>, DbErr> {
+    let models = symbols::Entity::find()
+        .filter(symbols::Column::Enabled.eq(true))
+        .order_by_asc(symbols::Column::Symbol)
+        .all(db)
+        .await?;
+    Ok(models.into_iter().map(|m| m.symbol).collect())
+}
+
+// 写入交易对的启用状态，不存在时新增
+pub async fn save_symbol(
+    db: &DatabaseConnection,
+    symbol: &str,
+    enabled: bool,
+) -> Result<symbols::Model, DbErr> {
+    let now = unix_timestamp();
+    match symbols::Entity::find_by_id(symbol).one(db).await? {
+        Some(model) => {
+            let mut model: symbols::ActiveModel = model.into();
+            model.enabled = Set(enabled);
+            model.updated_at = Set(now);
+            model.update(db).await
+        }
+        None => {
+            symbols::ActiveModel {
+                symbol: Set(symbol.to_string()),
+                enabled: Set(enabled),
+                created_at: Set(now),
+                updated_at: Set(now),
+            }
+            .insert(db)
+            .await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_symbol() {
+        assert_eq!(normalize_symbol(" ADAUSDT ").unwrap(), "adausdt");
+        assert!(normalize_symbol("usdt").is_err());
+        assert!(normalize_symbol("adabusd").is_err());
+        assert!(normalize_symbol("ada/usdt").is_err());
+    }
+}
@@ -0,0 +1,235 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    sync::Arc,
+};
+
+use sea_orm::{DatabaseConnection, EntityTrait};
+use tokio::{
+    sync::Mutex,
+    time::{interval, Duration, MissedTickBehavior},
+};
+
+use super::{normalize_symbol, save_symbol, SymbolMap};
+use crate::{
+    binance::leverage::{get_exchange_info, SymbolFilter, SymbolInfo, PERPETUAL_DELIVERY_DATE},
+    orm::symbols,
+    trade::{
+        event::{EventBus, TradeEventKind},
+        Trade,
+    },
+    websocket_lib::feed::MarketFeed,
+};
+
+// 默认刷新间隔
+pub const DEFAULT_REFRESH_SECS: u64 = 3600;
+
+// 自动发现规则，SYMBOL_DISCOVERY_INCLUDE 未设置时不自动启用新交易对。
+// 规则为逗号分隔的通配符，'*' 匹配任意字符，例如 "*usdt" 或 "1000*"
+#[derive(Debug, Default)]
+pub struct DiscoveryRules {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl DiscoveryRules {
+    pub fn from_env() -> Self {
+        DiscoveryRules {
+            include: patterns("SYMBOL_DISCOVERY_INCLUDE"),
+            exclude: patterns("SYMBOL_DISCOVERY_EXCLUDE"),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        !self.include.is_empty()
+    }
+
+    pub fn matches(&self, symbol: &str) -> bool {
+        self.include.iter().any(|p| glob_match(p, symbol))
+            && !self.exclude.iter().any(|p| glob_match(p, symbol))
+    }
+}
+
+fn patterns(name: &str) -> Vec<String> {
+    env::var(name)
+        .unwrap_or_default()
+        .split(',')
+        .map(|p| p.trim().to_lowercase())
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let (pattern, text) = (pattern.as_bytes(), text.as_bytes());
+    let (mut p, mut t) = (0, 0);
+    // 最近一个 '*' 的位置和它当前匹配到的文本位置
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && pattern[p] == b'*' {
+            star = Some((p, t));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == text[t] {
+            p += 1;
+            t += 1;
+        } else if let Some((sp, st)) = star {
+            p = sp + 1;
+            t = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == b'*')
+}
+
+// 可自动启用的合约：交易中、未安排交割的 USDT 永续合约
+fn discoverable(info: &SymbolInfo) -> bool {
+    info.status == "TRADING"
+        && info.contract_type == "PERPETUAL"
+        && info.quote_asset == "USDT"
+        && info.delivery_date >= PERPETUAL_DELIVERY_DATE
+}
+
+// 定时刷新 exchangeInfo：更新交易规则，发现新合约，检查结算、下架和合约切换
+pub async fn refresh_symbols(
+    database: DatabaseConnection,
+    filters: Arc<SymbolMap<SymbolFilter>>,
+    trades: Arc<SymbolMap<Mutex<Vec<Trade>>>>,
+    feed: Arc<MarketFeed>,
+    events: EventBus,
+    rules: DiscoveryRules,
+    period: Duration,
+) {
+    let mut ticker = interval(period);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    // 启动时已读取过交易规则，跳过立即触发的第一次
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        if let Err(e) = refresh_once(&database, &filters, &trades, &feed, &events, &rules).await {
+            eprintln!("刷新交易规则失败：{}", e);
+        }
+    }
+}
+
+async fn refresh_once(
+    database: &DatabaseConnection,
+    filters: &SymbolMap<SymbolFilter>,
+    trades: &SymbolMap<Mutex<Vec<Trade>>>,
+    feed: &MarketFeed,
+    events: &EventBus,
+    rules: &DiscoveryRules,
+) -> Result<(), String> {
+    let info = get_exchange_info().await.map_err(|e| e.to_string())?;
+    let by_symbol: HashMap<String, &SymbolInfo> = info
+        .symbols
+        .iter()
+        .map(|s| (s.symbol.to_lowercase(), s))
+        .collect();
+
+    // 已订阅的交易对：更新精度和限制，状态变为不可交易时提醒持仓用户
+    for (symbol, old) in filters.entries() {
+        let new = match by_symbol.get(&symbol) {
+            Some(info) => SymbolFilter::from_info(info),
+            None => SymbolFilter {
+                status: "DELISTED".to_string(),
+                ..(*old).clone()
+            },
+        };
+        match (old.halt_reason(), new.halt_reason()) {
+            (None, Some(reason)) => alert_open_trades(trades, events, &symbol, &reason).await,
+            (Some(_), None) => println!("交易对 {} 恢复交易。", symbol),
+            _ => {}
+        }
+        filters.replace(&symbol, new);
+    }
+
+    let models = symbols::Entity::find()
+        .all(database)
+        .await
+        .map_err(|e| e.to_string())?;
+    // 已启用但启动时交易所找不到的交易对，重新出现后开始订阅
+    for model in models.iter().filter(|m| m.enabled) {
+        if filters.contains_key(&model.symbol) {
+            continue;
+        }
+        if let Some(info) = by_symbol.get(&model.symbol) {
+            filters.replace(&model.symbol, SymbolFilter::from_info(info));
+            feed.start(&model.symbol);
+            println!("交易对 {} 已在交易所出现，开始订阅。", model.symbol);
+        }
+    }
+
+    // 自动发现：只启用表中没有的交易对，管理员停用过的不会被重新启用
+    if !rules.enabled() {
+        return Ok(());
+    }
+    let known: HashSet<&str> = models.iter().map(|m| m.symbol.as_str()).collect();
+    let mut discovered: Vec<(&String, &&SymbolInfo)> = by_symbol
+        .iter()
+        .filter(|(symbol, info)| {
+            !known.contains(symbol.as_str())
+                && discoverable(info)
+                && rules.matches(symbol)
+                && normalize_symbol(symbol).is_ok()
+        })
+        .collect();
+    discovered.sort_by_key(|(symbol, _)| symbol.as_str());
+    for (symbol, info) in discovered {
+        save_symbol(database, symbol, true)
+            .await
+            .map_err(|e| e.to_string())?;
+        filters.replace(symbol, SymbolFilter::from_info(info));
+        feed.start(symbol);
+        println!("自动启用交易对 {}。", symbol);
+    }
+    Ok(())
+}
+
+// 新仓已由交易规则拦截，进行中的交易通过事件提醒用户手动处理
+async fn alert_open_trades(
+    trades: &SymbolMap<Mutex<Vec<Trade>>>,
+    events: &EventBus,
+    symbol: &str,
+    reason: &str,
+) {
+    let mut count = 0;
+    if let Some(mutex_vec) = trades.get(symbol) {
+        for trade in mutex_vec.lock().await.iter().filter(|t| !t.is_closed()) {
+            events.publish(
+                trade,
+                TradeEventKind::SymbolHalted {
+                    reason: reason.to_string(),
+                },
+            );
+            count += 1;
+        }
+    }
+    eprintln!(
+        "交易对 {} 暂停开仓（{}），进行中的交易 {} 笔。",
+        symbol, reason, count
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discovery_rules() {
+        assert!(glob_match("*usdt", "adausdt"));
+        assert!(glob_match("1000*usdt", "1000pepeusdt"));
+        assert!(!glob_match("1000*usdt", "pepeusdt"));
+        assert!(glob_match("ada*", "ada"));
+
+        let rules = DiscoveryRules {
+            include: vec!["*usdt".to_string()],
+            exclude: vec!["1000*".to_string(), "btcdomusdt".to_string()],
+        };
+        assert!(rules.enabled());
+        assert!(rules.matches("suiusdt"));
+        assert!(!rules.matches("1000pepeusdt"));
+        assert!(!rules.matches("btcdomusdt"));
+        assert!(!DiscoveryRules::default().enabled());
+    }
+}
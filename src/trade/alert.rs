@@ -0,0 +1,94 @@
+use reqwest::Client;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use super::event::{TradeEvent, TradeEventKind};
+
+// 需要人工处理的事件：平仓或止盈多次重试仍失败、交易对暂停交易
+fn needs_attention(kind: &TradeEventKind) -> bool {
+    matches!(
+        kind,
+        TradeEventKind::CloseFailed { .. }
+            | TradeEventKind::TakeProfitFailed { .. }
+            | TradeEventKind::SymbolHalted { .. }
+    )
+}
+
+fn describe(event: &TradeEvent) -> String {
+    let detail = match &event.kind {
+        TradeEventKind::CloseFailed { quantity } => format!("平仓失败，剩余数量 {}", quantity),
+        TradeEventKind::TakeProfitFailed { price, quantity } => {
+            format!("止盈单失败，价格 {}，数量 {}", price, quantity)
+        }
+        TradeEventKind::SymbolHalted { reason } => format!("交易对暂停交易：{}", reason),
+        kind => format!("{:?}", kind),
+    };
+    format!(
+        "用户 {} 交易 {}（{} {:?}）{}，需要手动处理",
+        event.owner_id, event.trade_id, event.symbol, event.direction, detail
+    )
+}
+
+// 告警订阅者：写入错误日志，配置了 ALERT_WEBHOOK_URL 时同时以 JSON 推送事件
+pub async fn alert_failures(
+    mut receiver: broadcast::Receiver<TradeEvent>,
+    webhook_url: Option<String>,
+) {
+    let client = Client::new();
+    loop {
+        let event = match receiver.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                eprintln!("告警订阅落后，丢失 {} 个事件", skipped);
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+        if !needs_attention(&event.kind) {
+            continue;
+        }
+        eprintln!("[告警] {}", describe(&event));
+        if let Some(url) = &webhook_url {
+            let request = client.post(url).json(&event);
+            tokio::spawn(async move {
+                if let Err(e) = request.send().await.and_then(|r| r.error_for_status()) {
+                    eprintln!("告警推送失败：{}", e);
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::trade::TradeDirection;
+
+    #[test]
+    fn test_only_failures_raise_alerts() {
+        let event = TradeEvent {
+            trade_id: 7,
+            owner_id: "1".to_string(),
+            entry_order_id: 1,
+            symbol: "adausdt".to_string(),
+            direction: TradeDirection::Long,
+            entry_price: dec!(1),
+            leverage: dec!(5),
+            paper: false,
+            opened_at: 0,
+            adjustment_id: None,
+            timestamp: 0,
+            kind: TradeEventKind::CloseFailed { quantity: dec!(10) },
+        };
+        assert!(needs_attention(&event.kind));
+        assert_eq!(
+            describe(&event),
+            "用户 1 交易 7（adausdt Long）平仓失败，剩余数量 10，需要手动处理"
+        );
+        assert!(!needs_attention(&TradeEventKind::StopMoved {
+            from: dec!(0.9),
+            to: dec!(0.95),
+        }));
+    }
+}
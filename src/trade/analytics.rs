@@ -0,0 +1,252 @@
+use std::collections::{BTreeMap, HashMap};
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::{orm::trades, utils::parse_decimal};
+
+const WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+// 一笔已平仓的交易，分批平仓的多条记录合并为一笔
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClosedTrade {
+    pub symbol: String,
+    pub pnl: Decimal, // 扣除手续费后的盈亏
+    pub opened_at: Option<u32>,
+    pub closed_at: u32, // 最后一条平仓记录的时间
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BreakdownStats {
+    pub key: String,
+    pub trades: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub win_rate: Decimal,
+    pub net_pnl: Decimal,
+}
+
+impl BreakdownStats {
+    fn add(&mut self, pnl: Decimal) {
+        self.trades += 1;
+        if pnl > Decimal::ZERO {
+            self.wins += 1;
+        } else if pnl < Decimal::ZERO {
+            self.losses += 1;
+        }
+        self.net_pnl += pnl;
+        self.win_rate = ratio(Decimal::from(self.wins), Decimal::from(self.trades));
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TradeAnalytics {
+    pub total_trades: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub win_rate: Decimal,
+    pub net_pnl: Decimal,
+    pub gross_profit: Decimal,
+    pub gross_loss: Decimal, // 负数
+    pub average_win: Decimal,
+    pub average_loss: Decimal,             // 负数
+    pub expectancy: Decimal,               // 平均每笔盈亏
+    pub profit_factor: Option<Decimal>,    // 没有亏损交易时为空
+    pub max_drawdown: Decimal,             // 累计盈亏曲线从高点的最大回撤
+    pub average_holding_secs: Option<u64>, // 缺少开仓时间的旧记录不计入
+    pub longest_win_streak: u32,
+    pub longest_loss_streak: u32,
+    pub by_symbol: Vec<BreakdownStats>,
+    pub by_weekday: Vec<BreakdownStats>, // 按平仓时间（UTC）
+}
+
+// 单条平仓记录的净盈亏：优先使用交易所返回的盈亏，缺失时按成交价估算
+pub fn record_pnl(record: &trades::Model) -> Decimal {
+    let pnl = record
+        .realized_pnl
+        .as_deref()
+        .and_then(parse_decimal)
+        .unwrap_or_else(|| {
+            let entry = parse_decimal(&record.entry_price).unwrap_or_default();
+            let close = parse_decimal(&record.close_price).unwrap_or_default();
+            let quantity = parse_decimal(&record.quantity).unwrap_or_default();
+            match record.direction.as_str() {
+                "Short" => (entry - close) * quantity,
+                _ => (close - entry) * quantity,
+            }
+        });
+    let commission = record
+        .commission
+        .as_deref()
+        .and_then(parse_decimal)
+        .unwrap_or_default();
+    pnl - commission
+}
+
+// 按交易合并平仓记录，记录需按时间升序。交易 ID 重启后会重复，
+// 因此同时比较开仓时间；旧记录的 trade_id 为 0，每条单独计算
+pub fn group_closed_trades(records: &[trades::Model]) -> Vec<ClosedTrade> {
+    let mut result: Vec<ClosedTrade> = Vec::new();
+    let mut index: HashMap<(&str, &str, i64, Option<u32>), usize> = HashMap::new();
+    for record in records {
+        let pnl = record_pnl(record);
+        let key = (
+            record.owner_id.as_str(),
+            record.symbol.as_str(),
+            record.trade_id,
+            record.opened_at,
+        );
+        if record.trade_id != 0 {
+            if let Some(&i) = index.get(&key) {
+                result[i].pnl += pnl;
+                result[i].closed_at = result[i].closed_at.max(record.created_at);
+                continue;
+            }
+            index.insert(key, result.len());
+        }
+        result.push(ClosedTrade {
+            symbol: record.symbol.clone(),
+            pnl,
+            opened_at: record.opened_at,
+            closed_at: record.created_at,
+        });
+    }
+    result.sort_by_key(|t| t.closed_at);
+    result
+}
+
+// 交易需按平仓时间升序
+pub fn analyze(trades: &[ClosedTrade]) -> TradeAnalytics {
+    let mut stats = TradeAnalytics::default();
+    let mut by_symbol: BTreeMap<&str, BreakdownStats> = BTreeMap::new();
+    let mut by_weekday: BTreeMap<usize, BreakdownStats> = BTreeMap::new();
+    let (mut equity, mut peak) = (Decimal::ZERO, Decimal::ZERO);
+    let (mut win_streak, mut loss_streak) = (0, 0);
+    let (mut holding_total, mut holding_count) = (0u64, 0u64);
+
+    for trade in trades {
+        stats.total_trades += 1;
+        if trade.pnl > Decimal::ZERO {
+            stats.wins += 1;
+            stats.gross_profit += trade.pnl;
+            win_streak += 1;
+            loss_streak = 0;
+        } else if trade.pnl < Decimal::ZERO {
+            stats.losses += 1;
+            stats.gross_loss += trade.pnl;
+            loss_streak += 1;
+            win_streak = 0;
+        } else {
+            win_streak = 0;
+            loss_streak = 0;
+        }
+        stats.longest_win_streak = stats.longest_win_streak.max(win_streak);
+        stats.longest_loss_streak = stats.longest_loss_streak.max(loss_streak);
+
+        equity += trade.pnl;
+        peak = peak.max(equity);
+        stats.max_drawdown = stats.max_drawdown.max(peak - equity);
+
+        if let Some(opened_at) = trade.opened_at.filter(|at| *at <= trade.closed_at) {
+            holding_total += (trade.closed_at - opened_at) as u64;
+            holding_count += 1;
+        }
+
+        by_symbol
+            .entry(&trade.symbol)
+            .or_insert_with(|| BreakdownStats {
+                key: trade.symbol.clone(),
+                ..Default::default()
+            })
+            .add(trade.pnl);
+        let weekday = weekday(trade.closed_at);
+        by_weekday
+            .entry(weekday)
+            .or_insert_with(|| BreakdownStats {
+                key: WEEKDAYS[weekday].to_string(),
+                ..Default::default()
+            })
+            .add(trade.pnl);
+    }
+
+    stats.net_pnl = stats.gross_profit + stats.gross_loss;
+    stats.win_rate = ratio(Decimal::from(stats.wins), Decimal::from(stats.total_trades));
+    stats.average_win = ratio(stats.gross_profit, Decimal::from(stats.wins));
+    stats.average_loss = ratio(stats.gross_loss, Decimal::from(stats.losses));
+    stats.expectancy = ratio(stats.net_pnl, Decimal::from(stats.total_trades));
+    stats.profit_factor = (stats.losses > 0).then(|| ratio(stats.gross_profit, -stats.gross_loss));
+    stats.average_holding_secs = (holding_count > 0).then(|| holding_total / holding_count);
+    stats.by_symbol = by_symbol.into_values().collect();
+    stats.by_weekday = by_weekday.into_values().collect();
+    stats
+}
+
+// 1970-01-01 是星期四，返回 0 = 星期一
+fn weekday(timestamp: u32) -> usize {
+    ((timestamp / 86_400 + 3) % 7) as usize
+}
+
+fn ratio(value: Decimal, total: Decimal) -> Decimal {
+    if total.is_zero() {
+        return Decimal::ZERO;
+    }
+    (value / total).round_dp(4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn closed(symbol: &str, pnl: Decimal, closed_at: u32) -> ClosedTrade {
+        ClosedTrade {
+            symbol: symbol.to_string(),
+            pnl,
+            opened_at: Some(closed_at - 600),
+            closed_at,
+        }
+    }
+
+    #[test]
+    fn test_analyze_closed_trades() {
+        // 2024-01-01 为星期一
+        let monday = 1_704_067_200;
+        let trades = vec![
+            closed("adausdt", dec!(10), monday),
+            closed("adausdt", dec!(5), monday + 60),
+            closed("dogeusdt", dec!(-8), monday + 86_400),
+            closed("dogeusdt", dec!(-4), monday + 86_460),
+            closed("adausdt", dec!(6), monday + 86_520),
+        ];
+        let stats = analyze(&trades);
+        assert_eq!(stats.total_trades, 5);
+        assert_eq!(stats.win_rate, dec!(0.6));
+        assert_eq!(stats.net_pnl, dec!(9));
+        assert_eq!(stats.average_win, dec!(7));
+        assert_eq!(stats.average_loss, dec!(-6));
+        assert_eq!(stats.expectancy, dec!(1.8));
+        assert_eq!(stats.profit_factor, Some(dec!(1.75)));
+        // 高点 15 回撤到 3
+        assert_eq!(stats.max_drawdown, dec!(12));
+        assert_eq!(stats.average_holding_secs, Some(600));
+        assert_eq!(stats.longest_win_streak, 2);
+        assert_eq!(stats.longest_loss_streak, 2);
+        assert_eq!(stats.by_symbol[0].key, "adausdt");
+        assert_eq!(stats.by_symbol[0].net_pnl, dec!(21));
+        assert_eq!(stats.by_weekday[0].key, "Monday");
+        assert_eq!(stats.by_weekday[1].key, "Tuesday");
+        assert_eq!(stats.by_weekday[1].trades, 3);
+
+        let empty = analyze(&[]);
+        assert_eq!(empty.win_rate, Decimal::ZERO);
+        assert_eq!(empty.profit_factor, None);
+    }
+}
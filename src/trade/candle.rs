@@ -0,0 +1,63 @@
+use std::{collections::HashSet, sync::Arc};
+
+use tokio::{
+    sync::Mutex,
+    time::{interval, Duration, MissedTickBehavior},
+};
+
+use super::{event::EventBus, Trade, TradeStatus};
+use crate::{binance::market::get_klines, symbol::SymbolMap};
+
+// 拉取收盘 K 线的间隔，小于最短 K 线周期即可
+pub const CANDLE_POLL_SECS: u64 = 30;
+// 每次拉取的 K 线数量，包含尚未收盘的最后一根；漏掉几次轮询也能补齐
+const CANDLE_FETCH_LIMIT: u32 = 5;
+
+// 定时为依赖 K 线的止损策略推送收盘 K 线。网络请求不持有交易列表锁，
+// 重复推送的 K 线由策略自行忽略
+pub async fn feed_closed_klines(
+    trades: Arc<SymbolMap<Mutex<Vec<Trade>>>>,
+    events: EventBus,
+    period: Duration,
+) {
+    let mut ticker = interval(period);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    loop {
+        ticker.tick().await;
+        for (symbol, mutex_vec) in trades.entries() {
+            let intervals: HashSet<String> = mutex_vec
+                .lock()
+                .await
+                .iter()
+                .filter(|t| t.status == TradeStatus::Open)
+                .filter_map(|t| t.stop_strategy.kline_interval().map(str::to_string))
+                .collect();
+
+            for kline_interval in intervals {
+                let mut klines =
+                    match get_klines(&symbol, &kline_interval, CANDLE_FETCH_LIMIT).await {
+                        Ok(klines) => klines,
+                        Err(e) => {
+                            eprintln!(
+                                "交易对 {} 的 {} K 线获取失败：{}",
+                                symbol, kline_interval, e
+                            );
+                            continue;
+                        }
+                    };
+                // 最后一根尚未收盘
+                klines.pop();
+
+                let mut vec = mutex_vec.lock().await;
+                for t in vec
+                    .iter_mut()
+                    .filter(|t| t.stop_strategy.kline_interval() == Some(kline_interval.as_str()))
+                {
+                    for kline in &klines {
+                        t.on_kline_close(kline, &events);
+                    }
+                }
+            }
+        }
+    }
+}
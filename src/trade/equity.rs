@@ -0,0 +1,149 @@
+use rust_decimal::Decimal;
+use sea_orm::{ActiveModelTrait, DatabaseConnection, DbErr, EntityTrait, Set};
+use serde::Serialize;
+use tokio::time::{interval, Duration, MissedTickBehavior};
+
+use crate::{
+    binance::account::{get_balance, get_risk, AssetBalance, Position},
+    orm::{equity_snapshots, users},
+    utils::{parse_decimal, unix_timestamp},
+};
+
+// 默认快照间隔
+pub const DEFAULT_SNAPSHOT_SECS: u64 = 300;
+// 钱包余额按 USDT 统计
+const MARGIN_ASSET: &str = "USDT";
+
+// 权益曲线上的一个点
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EquityPoint {
+    pub timestamp: u32,
+    pub wallet_balance: Decimal,
+    pub unrealized_pnl: Decimal,
+    pub equity: Decimal, // 钱包余额 + 未实现盈亏
+    pub margin_used: Decimal,
+    pub open_trades: u32,
+}
+
+impl EquityPoint {
+    pub fn from_model(model: &equity_snapshots::Model) -> Self {
+        let wallet_balance = parse_decimal(&model.wallet_balance).unwrap_or_default();
+        let unrealized_pnl = parse_decimal(&model.unrealized_pnl).unwrap_or_default();
+        EquityPoint {
+            timestamp: model.created_at,
+            wallet_balance,
+            unrealized_pnl,
+            equity: wallet_balance + unrealized_pnl,
+            margin_used: parse_decimal(&model.margin_used).unwrap_or_default(),
+            open_trades: model.open_trades,
+        }
+    }
+
+    // 由余额和持仓接口的返回值汇总
+    pub fn from_account(balances: &[AssetBalance], positions: &[Position]) -> Self {
+        let wallet_balance = balances
+            .iter()
+            .find(|b| b.asset == MARGIN_ASSET)
+            .map(|b| b.balance)
+            .unwrap_or_default();
+        let open: Vec<&Position> = positions
+            .iter()
+            .filter(|p| !p.position_amt.is_zero())
+            .collect();
+        let unrealized_pnl = open.iter().map(|p| p.unrealized_profit).sum();
+        EquityPoint {
+            timestamp: unix_timestamp(),
+            wallet_balance,
+            unrealized_pnl,
+            equity: wallet_balance + unrealized_pnl,
+            // 挂单占用的保证金也计入
+            margin_used: positions.iter().map(|p| p.initial_margin).sum(),
+            open_trades: open.len() as u32,
+        }
+    }
+}
+
+// 按时间均匀分桶，每桶保留最后一个点；首点始终保留，便于确定曲线起点
+pub fn downsample(points: Vec<EquityPoint>, max_points: usize) -> Vec<EquityPoint> {
+    if max_points < 2 || points.len() <= max_points {
+        return points;
+    }
+    let first = points[0].clone();
+    let rest = &points[1..];
+    let bucket = rest.len().div_ceil(max_points - 1);
+    let mut result = vec![first];
+    result.extend(rest.chunks(bucket).filter_map(|c| c.last().cloned()));
+    result
+}
+
+// 定时为每个配置了 API Key 的用户写入权益快照
+pub async fn record_equity(database: DatabaseConnection, period: Duration) {
+    let mut ticker = interval(period);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    loop {
+        ticker.tick().await;
+        if let Err(e) = snapshot_users(&database).await {
+            eprintln!("权益快照读取用户失败：{}", e);
+        }
+    }
+}
+
+async fn snapshot_users(database: &DatabaseConnection) -> Result<(), DbErr> {
+    let users = users::Entity::find().all(database).await?;
+    for user in users.iter().filter(|u| !u.apikey.is_empty()) {
+        let (balances, positions) = tokio::join!(
+            get_balance(&user.apikey, &user.secret),
+            get_risk(&user.apikey, &user.secret)
+        );
+        let (balances, positions) = match (balances, positions) {
+            (Ok(balances), Ok(positions)) => (balances, positions),
+            (Err(e), _) | (_, Err(e)) => {
+                eprintln!("用户 {} 权益快照获取失败：{}", user.id, e);
+                continue;
+            }
+        };
+        let point = EquityPoint::from_account(&balances, &positions);
+        let result = equity_snapshots::ActiveModel {
+            owner_id: Set(user.id.to_string()),
+            wallet_balance: Set(point.wallet_balance.to_string()),
+            unrealized_pnl: Set(point.unrealized_pnl.to_string()),
+            margin_used: Set(point.margin_used.to_string()),
+            open_trades: Set(point.open_trades),
+            created_at: Set(point.timestamp),
+            ..Default::default()
+        }
+        .insert(database)
+        .await;
+        if let Err(e) = result {
+            eprintln!("用户 {} 权益快照写入失败：{}", user.id, e);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn point(timestamp: u32) -> EquityPoint {
+        EquityPoint {
+            timestamp,
+            wallet_balance: dec!(100),
+            unrealized_pnl: Decimal::ZERO,
+            equity: dec!(100),
+            margin_used: Decimal::ZERO,
+            open_trades: 0,
+        }
+    }
+
+    #[test]
+    fn test_downsample_keeps_first_and_last() {
+        let points: Vec<EquityPoint> = (0..10).map(point).collect();
+        let sampled = downsample(points.clone(), 4);
+        let times: Vec<u32> = sampled.iter().map(|p| p.timestamp).collect();
+        assert_eq!(times, vec![0, 3, 6, 9]);
+        assert_eq!(downsample(points.clone(), 20).len(), 10);
+        assert_eq!(downsample(points, 0).len(), 10);
+    }
+}
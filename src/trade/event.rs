@@ -0,0 +1,118 @@
+use rust_decimal::Decimal;
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use super::{CloseReason, Trade, TradeDirection};
+use crate::utils::unix_timestamp;
+
+// 事件总线缓冲区大小，订阅者落后超过该数量时会丢失最早的事件
+const EVENT_BUS_CAPACITY: usize = 1024;
+
+// 交易生命周期事件
+#[derive(Debug, Clone, Serialize)]
+pub struct TradeEvent {
+    pub trade_id: usize,
+    pub owner_id: String,
+    pub entry_order_id: u64, // 开仓单 ID，加仓单见 trade_legs
+    pub symbol: String,
+    pub direction: TradeDirection,
+    pub entry_price: Decimal,
+    pub leverage: Decimal,
+    pub paper: bool, // 模拟盘交易
+    pub opened_at: u32,
+    pub adjustment_id: Option<i64>,
+    pub timestamp: u32,
+    #[serde(flatten)]
+    pub kind: TradeEventKind,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+pub enum TradeEventKind {
+    // 开仓成交（限价单首次成交时）
+    TradeOpened {
+        quantity: Decimal,
+        stop_loss: Decimal,
+    },
+    // 止损价移动，包括手动修改
+    StopMoved {
+        from: Decimal,
+        to: Decimal,
+    },
+    // 部分平仓成交，持仓仍有剩余
+    PartialClose {
+        order_id: u64,
+        price: Decimal,
+        trigger_price: Decimal,
+        quantity: Decimal,
+        remaining_quantity: Decimal,
+        reason: CloseReason,
+    },
+    // 止损触发或手动平仓，平仓单尚未成交
+    CloseRequested {
+        price: Decimal,
+        reason: CloseReason,
+    },
+    // 最后一笔平仓成交，交易结束
+    Closed {
+        order_id: u64,
+        price: Decimal,
+        trigger_price: Decimal,
+        quantity: Decimal,
+        reason: CloseReason,
+    },
+    // 多次重试后平仓仍失败
+    CloseFailed {
+        quantity: Decimal,
+    },
+    // 多次重试后止盈单仍失败，目标恢复为未触发
+    TakeProfitFailed {
+        price: Decimal,
+        quantity: Decimal,
+    },
+    // 交易所暂停该交易对或安排下架，需要手动处理持仓
+    SymbolHalted {
+        reason: String,
+    },
+}
+
+// 交易事件总线：引擎只负责发布，持久化、通知和推送各自订阅
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<TradeEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUS_CAPACITY);
+        Self { sender }
+    }
+
+    // 没有订阅者时事件直接丢弃
+    pub fn publish(&self, trade: &Trade, kind: TradeEventKind) {
+        let _ = self.sender.send(TradeEvent {
+            trade_id: trade.id,
+            owner_id: trade.owner_id.clone(),
+            entry_order_id: trade.order_id,
+            symbol: trade.symbol.clone(),
+            direction: trade.direction.clone(),
+            entry_price: trade.entry_price,
+            leverage: trade.leverage,
+            paper: trade.paper,
+            opened_at: trade.opened_at,
+            adjustment_id: trade.adjustment_id,
+            timestamp: unix_timestamp(),
+            kind,
+        });
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<TradeEvent> {
+        self.sender.subscribe()
+    }
+}
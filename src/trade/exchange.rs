@@ -0,0 +1,183 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex,
+};
+
+use rust_decimal::Decimal;
+
+use super::{CloseFill, Trade, TradeDirection};
+use crate::binance::order::cancel_order;
+
+// 交易逻辑用到的交易所操作：实盘走币安接口，模拟盘由 PaperExchange、回测由 SimExchange 撮合
+pub trait Exchange {
+    // 市价减仓，成功后返回成交结果
+    async fn close_position(
+        &self,
+        trade: &Trade,
+        quantity: Decimal,
+        price: Decimal,
+    ) -> Option<CloseFill>;
+
+    // 撤销交易所托管的止损单，失败时忽略
+    async fn cancel_stop(&self, trade: &Trade, order_id: u64);
+}
+
+// 币安实盘，使用交易自带的 API Key
+pub struct LiveExchange;
+
+impl Exchange for LiveExchange {
+    async fn close_position(
+        &self,
+        trade: &Trade,
+        quantity: Decimal,
+        price: Decimal,
+    ) -> Option<CloseFill> {
+        trade.close_position(quantity, price).await
+    }
+
+    async fn cancel_stop(&self, trade: &Trade, order_id: u64) {
+        let _ = cancel_order(&trade.symbol, order_id, &trade.api_key, &trade.api_secret).await;
+    }
+}
+
+// 模拟盘订单 ID 计数器，进程内唯一
+static PAPER_ORDER_ID: AtomicU64 = AtomicU64::new(0);
+
+// 模拟盘：使用实时价格按触发价成交，手续费在写入 paper_trades 时按账户费率计算
+pub struct PaperExchange;
+
+impl PaperExchange {
+    pub fn next_order_id() -> u64 {
+        PAPER_ORDER_ID.fetch_add(1, Ordering::SeqCst) + 1
+    }
+}
+
+impl Exchange for PaperExchange {
+    async fn close_position(
+        &self,
+        _trade: &Trade,
+        _quantity: Decimal,
+        price: Decimal,
+    ) -> Option<CloseFill> {
+        Some(CloseFill {
+            order_id: Self::next_order_id(),
+            price,
+            trigger_price: price,
+        })
+    }
+
+    async fn cancel_stop(&self, _trade: &Trade, _order_id: u64) {}
+}
+
+// 按交易是否为模拟盘选择实盘或模拟撮合
+pub struct TradeExchange;
+
+impl Exchange for TradeExchange {
+    async fn close_position(
+        &self,
+        trade: &Trade,
+        quantity: Decimal,
+        price: Decimal,
+    ) -> Option<CloseFill> {
+        if trade.paper {
+            PaperExchange.close_position(trade, quantity, price).await
+        } else {
+            LiveExchange.close_position(trade, quantity, price).await
+        }
+    }
+
+    async fn cancel_stop(&self, trade: &Trade, order_id: u64) {
+        if trade.paper {
+            PaperExchange.cancel_stop(trade, order_id).await
+        } else {
+            LiveExchange.cancel_stop(trade, order_id).await
+        }
+    }
+}
+
+// 模拟成交记录
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimFill {
+    pub order_id: u64,
+    pub trade_id: usize,
+    pub price: Decimal,
+    pub quantity: Decimal,
+    pub fee: Decimal,
+}
+
+// 模拟撮合：市价单按触发价成交，叠加不利方向的滑点并按 taker 费率收取手续费
+#[derive(Debug)]
+pub struct SimExchange {
+    pub fee_rate: Decimal, // taker 手续费率
+    pub slippage: Decimal, // 滑点，占成交价的比例
+    fills: Mutex<Vec<SimFill>>,
+}
+
+impl SimExchange {
+    pub fn new(fee_rate: Decimal, slippage: Decimal) -> Self {
+        Self {
+            fee_rate,
+            slippage,
+            fills: Mutex::new(Vec::new()),
+        }
+    }
+
+    // 开仓市价单：做多买入、做空卖出
+    pub fn fill_entry(
+        &self,
+        trade_id: usize,
+        direction: &TradeDirection,
+        price: Decimal,
+        quantity: Decimal,
+    ) -> SimFill {
+        self.fill(
+            trade_id,
+            *direction == TradeDirection::Long,
+            price,
+            quantity,
+        )
+    }
+
+    pub fn fill_by_order(&self, order_id: u64) -> Option<SimFill> {
+        let fills = self.fills.lock().unwrap();
+        fills.iter().find(|f| f.order_id == order_id).cloned()
+    }
+
+    // 订单 ID 按成交顺序从 1 递增，同一数据集的结果可复现
+    fn fill(&self, trade_id: usize, is_buy: bool, price: Decimal, quantity: Decimal) -> SimFill {
+        let price = if is_buy {
+            price * (Decimal::ONE + self.slippage)
+        } else {
+            price * (Decimal::ONE - self.slippage)
+        };
+        let mut fills = self.fills.lock().unwrap();
+        let fill = SimFill {
+            order_id: fills.len() as u64 + 1,
+            trade_id,
+            price,
+            quantity,
+            fee: price * quantity * self.fee_rate,
+        };
+        fills.push(fill.clone());
+        fill
+    }
+}
+
+impl Exchange for SimExchange {
+    async fn close_position(
+        &self,
+        trade: &Trade,
+        quantity: Decimal,
+        price: Decimal,
+    ) -> Option<CloseFill> {
+        let is_buy = trade.direction == TradeDirection::Short;
+        let fill = self.fill(trade.id, is_buy, price, quantity);
+        Some(CloseFill {
+            order_id: fill.order_id,
+            price: fill.price,
+            trigger_price: price,
+        })
+    }
+
+    async fn cancel_stop(&self, _trade: &Trade, _order_id: u64) {}
+}
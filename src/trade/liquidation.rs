@@ -0,0 +1,151 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use super::TradeDirection;
+use crate::binance::leverage::{bracket_for_notional, LeverageBracket, MarginType};
+
+// 止损与强平价之间至少保留的价格比例，覆盖标记价格与成交价的偏差
+pub const LIQUIDATION_BUFFER: Decimal = dec!(0.005);
+
+// 估算强平价所需的账户数据，开仓前从交易所读取
+#[derive(Debug)]
+pub struct LiquidationContext {
+    pub brackets: Vec<LeverageBracket>,
+    pub margin_type: MarginType,
+    pub equity: Decimal, // 全仓时作为可承担亏损的保证金，逐仓时不使用
+}
+
+impl LiquidationContext {
+    // 按单一持仓估算强平价：逐仓以初始保证金计，全仓以账户权益计（忽略其他持仓的维持保证金）
+    pub fn estimate(
+        &self,
+        direction: &TradeDirection,
+        price: Decimal,
+        quantity: Decimal,
+        leverage: Decimal,
+    ) -> Option<Decimal> {
+        let notional = price * quantity;
+        let bracket = bracket_for_notional(&self.brackets, notional)?;
+        let margin = match self.margin_type {
+            MarginType::Isolated => notional / leverage,
+            MarginType::Cross => self.equity,
+        };
+        liquidation_price(
+            direction,
+            price,
+            quantity,
+            margin,
+            bracket.maint_margin_ratio,
+            bracket.cum,
+        )
+    }
+}
+
+// 单一持仓强平价：保证金 + 浮动盈亏 = 维持保证金；做多强平价不为正时视为不会强平
+pub fn liquidation_price(
+    direction: &TradeDirection,
+    price: Decimal,
+    quantity: Decimal,
+    margin: Decimal,
+    maint_margin_ratio: Decimal,
+    cum: Decimal,
+) -> Option<Decimal> {
+    if quantity <= Decimal::ZERO {
+        return None;
+    }
+    let liquidation = match direction {
+        TradeDirection::Long => {
+            (quantity * price - margin - cum) / (quantity * (Decimal::ONE - maint_margin_ratio))
+        }
+        TradeDirection::Short => {
+            (quantity * price + margin + cum) / (quantity * (Decimal::ONE + maint_margin_ratio))
+        }
+    };
+    (liquidation > Decimal::ZERO).then_some(liquidation)
+}
+
+// 止损价是否留有足够的强平缓冲
+pub fn stop_before_liquidation(
+    direction: &TradeDirection,
+    stop: Decimal,
+    liquidation: Decimal,
+) -> bool {
+    match direction {
+        TradeDirection::Long => stop >= liquidation * (Decimal::ONE + LIQUIDATION_BUFFER),
+        TradeDirection::Short => stop <= liquidation * (Decimal::ONE - LIQUIDATION_BUFFER),
+    }
+}
+
+// 止损价不越过强平缓冲时允许的最大止损比例
+pub fn max_stop_loss_percent(
+    direction: &TradeDirection,
+    price: Decimal,
+    leverage: Decimal,
+    liquidation: Decimal,
+) -> Decimal {
+    let percent = match direction {
+        TradeDirection::Long => {
+            leverage * (Decimal::ONE - liquidation * (Decimal::ONE + LIQUIDATION_BUFFER) / price)
+        }
+        TradeDirection::Short => {
+            leverage * (liquidation * (Decimal::ONE - LIQUIDATION_BUFFER) / price - Decimal::ONE)
+        }
+    };
+    percent.max(Decimal::ZERO)
+}
+
+// 开仓价到强平价的距离，占开仓价的比例
+pub fn liquidation_distance(price: Decimal, liquidation: Decimal) -> Decimal {
+    if price <= Decimal::ZERO {
+        return Decimal::ZERO;
+    }
+    (price - liquidation).abs() / price
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trade::calculate_stop_price;
+
+    #[test]
+    fn test_liquidation_and_clamp() {
+        // 逐仓 10 倍：保证金 10，维持保证金率 0.5%
+        let long = TradeDirection::Long;
+        let short = TradeDirection::Short;
+        let liq_long =
+            liquidation_price(&long, dec!(100), dec!(1), dec!(10), dec!(0.005), dec!(0)).unwrap();
+        let liq_short =
+            liquidation_price(&short, dec!(100), dec!(1), dec!(10), dec!(0.005), dec!(0)).unwrap();
+        assert_eq!(liq_long.round_dp(4), dec!(90.4523));
+        assert_eq!(liq_short.round_dp(4), dec!(109.4527));
+        assert_eq!(
+            liquidation_distance(dec!(100), liq_long).round_dp(4),
+            dec!(0.0955)
+        );
+        // 全仓权益远大于仓位时做多不会强平
+        assert!(
+            liquidation_price(&long, dec!(100), dec!(1), dec!(1000), dec!(0.005), dec!(0))
+                .is_none()
+        );
+
+        // 止损比例 1（亏光保证金）越过强平价，收紧后留出缓冲
+        let stop = calculate_stop_price(&long, dec!(100), dec!(10), dec!(1));
+        assert!(!stop_before_liquidation(&long, stop, liq_long));
+        let percent = max_stop_loss_percent(&long, dec!(100), dec!(10), liq_long);
+        assert!(percent < Decimal::ONE);
+        let clamped = calculate_stop_price(&long, dec!(100), dec!(10), percent);
+        assert!(stop_before_liquidation(
+            &long,
+            clamped + dec!(0.0001),
+            liq_long
+        ));
+
+        let percent = max_stop_loss_percent(&short, dec!(100), dec!(10), liq_short);
+        let clamped = calculate_stop_price(&short, dec!(100), dec!(10), percent);
+        assert!(stop_before_liquidation(
+            &short,
+            clamped - dec!(0.0001),
+            liq_short
+        ));
+    }
+}
@@ -1,12 +1,42 @@
-use std::fmt;
+pub mod alert;
+pub mod analytics;
+pub mod candle;
+pub mod equity;
+pub mod event;
+pub mod exchange;
+pub mod liquidation;
+pub mod paper;
+pub mod preset;
+pub mod price;
+pub mod record;
+pub mod risk;
+pub mod sizing;
+pub mod strategy;
+pub mod trigger;
 
+use std::{fmt, sync::Arc};
+
+use rust_decimal::Decimal;
 use sea_orm::{ActiveModelTrait, DatabaseConnection, Set};
 use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::Mutex,
+    time::{sleep, Duration, Instant},
+};
 
 use crate::binance::account::get_order_api;
-use crate::binance::order::create_order;
+use crate::binance::leverage::SymbolFilter;
+use crate::binance::market::Kline;
+use crate::binance::order::{cancel_order, create_order};
+use crate::symbol::SymbolMap;
 
-use crate::orm::trades;
+use crate::models::trade_model::TakeProfitRequest;
+use crate::orm::trade_legs;
+use crate::utils::{parse_decimal, round_to_step, round_to_tick, unix_timestamp};
+use event::{EventBus, TradeEventKind};
+use exchange::{Exchange, TradeExchange};
+use price::{PriceBook, PriceSource};
+use strategy::{serialize_strategy, StopContext, StopStrategy};
 
 // 模拟的交易方向
 #[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
@@ -24,6 +54,103 @@ impl fmt::Display for TradeDirection {
     }
 }
 
+// 平仓原因，写入历史记录
+#[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
+pub enum CloseReason {
+    StopLoss,   // 止损触发
+    TakeProfit, // 止盈分批平仓
+    Manual,     // 手动平仓
+    KillSwitch, // 熔断强制平仓
+}
+
+impl fmt::Display for CloseReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CloseReason::StopLoss => write!(f, "StopLoss"),
+            CloseReason::TakeProfit => write!(f, "TakeProfit"),
+            CloseReason::Manual => write!(f, "Manual"),
+            CloseReason::KillSwitch => write!(f, "KillSwitch"),
+        }
+    }
+}
+
+// 交易状态：只有 Open 状态参与止损跟踪
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub enum TradeStatus {
+    Pending, // 开仓单未成交
+    #[default]
+    Open, // 已有持仓，止损生效
+    Closing, // 已触发平仓，平仓任务执行中
+    Closed,  // 已平仓，行情任务会将其移除
+    CloseFailed, // 多次重试后平仓仍失败，持仓留在交易所，需要人工处理
+}
+
+// 加仓后止损的重新锚定方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub enum ReanchorPolicy {
+    // 止损和阶梯进度保持不变
+    #[default]
+    Keep,
+    // 取当前止损与按新均价计算的初始止损中更紧的一个
+    Tighter,
+    // 以新均价为起点重新开始：初始止损、极值价格和阶梯全部重置
+    Reset,
+}
+
+impl fmt::Display for ReanchorPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReanchorPolicy::Keep => write!(f, "Keep"),
+            ReanchorPolicy::Tighter => write!(f, "Tighter"),
+            ReanchorPolicy::Reset => write!(f, "Reset"),
+        }
+    }
+}
+
+// 止盈目标，ROI 目标在创建交易时已换算为价格
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TakeProfit {
+    pub price: Decimal,    // 触发价格
+    pub fraction: Decimal, // 平仓比例，相对于原始数量
+    pub is_hit: bool,      // 是否已成交
+    #[serde(default)]
+    pub pending: bool, // 平仓单已发出，等待成交结果
+}
+
+// 到价的止盈平仓单，由调用方在不持有交易列表锁时下单
+#[derive(Debug, Clone, PartialEq)]
+pub struct TakeProfitOrder {
+    pub index: usize,
+    pub quantity: Decimal,
+    pub price: Decimal, // 触发价
+}
+
+// 单个价格 tick 需要执行的平仓动作
+#[derive(Debug, Default)]
+pub struct PriceActions {
+    pub take_profits: Vec<TakeProfitOrder>,
+    pub stop: Option<Decimal>,    // 触发止损时的触发价
+    pub cancel_stop: Option<u64>, // 平仓前需撤销的交易所托管止损单
+}
+
+// 平仓单成交结果
+#[derive(Debug, Clone)]
+pub struct CloseFill {
+    pub order_id: u64,
+    pub price: Decimal,         // 成交均价，查询失败时为触发价
+    pub trigger_price: Decimal, // 下单时的触发价，用于计算滑点
+}
+
+// 单个价格 tick 的处理结果
+#[derive(Debug, Clone, Serialize)]
+pub struct PriceStep {
+    pub price: Decimal,
+    pub stop_loss: Decimal,       // 处理后的止损价
+    pub stop_moved: bool,         // 本次是否移动了止损
+    pub rung: Option<Adjustment>, // 阶梯策略本次命中的档位
+    pub stop_hit: bool,           // 是否触发止损
+}
+
 // 模拟的交易类型
 #[derive(Debug, Clone, Serialize)]
 pub struct Trade {
@@ -32,36 +159,73 @@ pub struct Trade {
     pub order_id: u64,
     pub stop_order: u64,           // 唯一ID字段，用于唯一标识每笔交易
     pub symbol: String, // 货币或资产符号，表示此交易涉及的交易品种，如 "EUR/USD" 或 "AAPL"
-    pub entry_price: f64, // 入场价格，交易开始时的初始价格
-    pub stop_loss: f64, // 止损点位，如果当前价格达到该值，交易将自动平仓以限制损失
-    highest_price: f64, // 记录历史最高价格，用于动态调整止损点和判断利润情况（做多时）
-    lowest_price: f64,  // 记录历史最低价格，用于动态调整止损点和判断利润情况（做空时）
+    pub entry_price: Decimal, // 入场价格，交易开始时的初始价格
+    pub stop_loss: Decimal, // 止损点位，如果当前价格达到该值，交易将自动平仓以限制损失
+    highest_price: Decimal, // 记录历史最高价格，用于动态调整止损点和判断利润情况（做多时）
+    lowest_price: Decimal, // 记录历史最低价格，用于动态调整止损点和判断利润情况（做空时）
     pub direction: TradeDirection, // 交易方向，标识是做多还是做空
-    pub quantity: String,
-    pub leverage: f64,
-    pub adjustment: Vec<Adjustment>,
-    pub is_closed: bool, // 杠杆倍数
+    pub quantity: Decimal,
+    pub remaining_quantity: Decimal, // 剩余持仓数量，分批止盈后递减
+    tick_size: Decimal,              // 价格最小变动单位
+    step_size: Decimal,              // 数量最小变动单位
+    pub leverage: Decimal,
+    #[serde(serialize_with = "serialize_strategy")]
+    pub stop_strategy: Box<dyn StopStrategy>,
+    pub take_profits: Vec<TakeProfit>,
+    pub price_source: PriceSource, // 止损触发价格来源
+    pub status: TradeStatus,
+    pub stop_loss_percent: Decimal, // 初始止损比例，成交后按实际均价重新计算
+    pub paper: bool,                // 模拟盘交易，不向交易所下单
+    pub opened_at: u32,             // 开仓成交时间
+    pub adjustment_id: Option<i64>, // 使用的阶梯预设，非阶梯策略为空
+    #[serde(skip_serializing)] // 接口返回交易时不能带出用户的交易所密钥
     api_key: String,
+    #[serde(skip_serializing)]
     api_secret: String,
 }
 
+// 创建交易的参数，价格精度由 Trade::new 的交易规则提供
+pub struct TradeParams {
+    pub id: usize,
+    pub owner_id: String,
+    pub order_id: u64,
+    pub symbol: String,
+    pub entry_price: Decimal,
+    pub direction: TradeDirection,
+    pub quantity: Decimal,
+    pub leverage: Decimal,
+    pub stop_loss_percent: Decimal,
+    pub stop_strategy: Box<dyn StopStrategy>,
+    pub take_profits: Vec<TakeProfit>,
+    pub price_source: PriceSource,
+    pub api_key: String,
+    pub api_secret: String,
+}
+
 impl Trade {
     // 创建一个新的交易，自动设置止损为-5%（即95%）
-    pub async fn new(
-        id: usize,
-        owner_id: String,
-        order_id: u64,
-        symbol: String,
-        entry_price: f64,
-        direction: TradeDirection,
-        quantity: String,
-        leverage: f64,
-        stop_loss_percent: f64,
-        mut adjustment: Vec<Adjustment>,
-        api_key: String,
-        api_secret: String,
-    ) -> Self {
-        let stop_loss = calculate_stop_price(&direction, entry_price, leverage, stop_loss_percent);
+    pub async fn new(params: TradeParams, filter: &SymbolFilter) -> Self {
+        let TradeParams {
+            id,
+            owner_id,
+            order_id,
+            symbol,
+            entry_price,
+            direction,
+            quantity,
+            leverage,
+            stop_loss_percent,
+            stop_strategy,
+            take_profits,
+            price_source,
+            api_key,
+            api_secret,
+        } = params;
+        let stop_loss = round_stop_price(
+            &direction,
+            calculate_stop_price(&direction, entry_price, leverage, stop_loss_percent),
+            filter.tick_size,
+        );
         // let (side, position_side) = match direction {
         //     TradeDirection::Long => ("SELL", "LONG"),
         //     TradeDirection::Short => ("BUY", "SHORT"),
@@ -80,12 +244,6 @@ impl Trade {
 
         let stop_order_id = order_id;
 
-        adjustment.push(Adjustment {
-            min: 1.1,
-            max: None,
-            adjustment: 0.1,
-        });
-
         Self {
             id,
             owner_id,
@@ -97,189 +255,768 @@ impl Trade {
             highest_price: entry_price, // 做多时初始为入场价
             lowest_price: entry_price,  // 做空时初始为入场价
             direction,
+            remaining_quantity: quantity,
             quantity,
+            tick_size: filter.tick_size,
+            step_size: filter.step_size,
             leverage,
-            adjustment,
-            is_closed: false,
+            stop_strategy,
+            take_profits,
+            price_source,
+            status: TradeStatus::Open,
+            stop_loss_percent,
+            paper: false,
+            opened_at: unix_timestamp(),
+            adjustment_id: None,
             api_key,
             api_secret,
         }
     }
 
-    // 更新价格并调整历史最高或最低价和止损
-    pub async fn update_price(
-        &mut self,
-        book_price: (String, String),
-        database: &DatabaseConnection,
-    ) {
-        let price = match self.direction {
-            TradeDirection::Long => {
-                let price = book_price.1;
-                let price_f64: f64 = price.parse().unwrap();
-                if price_f64 > self.highest_price {
-                    self.highest_price = price_f64;
-                    let profit_percentage =
-                        (self.highest_price - self.entry_price) / self.entry_price;
-                    self.update_stop_loss(profit_percentage, true).await;
-                }
-                price
+    pub fn is_closed(&self) -> bool {
+        self.status == TradeStatus::Closed
+    }
+
+    // 转为等待开仓单成交，持仓数量从 0 开始累计
+    pub fn mark_pending(&mut self) {
+        self.status = TradeStatus::Pending;
+        self.quantity = Decimal::ZERO;
+        self.remaining_quantity = Decimal::ZERO;
+    }
+
+    // 开仓单（部分）成交：按累计成交量和成交均价更新持仓，并以实际均价重新锚定止损
+    pub fn apply_entry_fill(&mut self, avg_price: Decimal, executed_qty: Decimal) {
+        if avg_price <= Decimal::ZERO || executed_qty <= self.quantity {
+            return;
+        }
+        self.remaining_quantity += executed_qty - self.quantity;
+        self.quantity = executed_qty;
+        self.entry_price = avg_price;
+
+        let initial_stop = self.initial_stop();
+        if self.status == TradeStatus::Pending {
+            self.highest_price = avg_price;
+            self.lowest_price = avg_price;
+            self.stop_loss = initial_stop;
+            self.status = TradeStatus::Open;
+            self.opened_at = unix_timestamp();
+        } else {
+            // 追加成交时只收紧已移动的止损，不回退
+            self.stop_loss = match self.direction {
+                TradeDirection::Long => self.stop_loss.max(initial_stop),
+                TradeDirection::Short => self.stop_loss.min(initial_stop),
+            };
+        }
+    }
+
+    // 部分平仓成交后扣减剩余数量，全部平完时标记为已平仓
+    pub fn reduce_position(&mut self, quantity: Decimal) {
+        self.remaining_quantity = (self.remaining_quantity - quantity).max(Decimal::ZERO);
+        if self.remaining_quantity <= Decimal::ZERO {
+            self.status = TradeStatus::Closed;
+        }
+    }
+
+    // 加仓成交：按加权平均更新开仓价和数量，再按策略重新锚定止损
+    pub fn add_fill(&mut self, price: Decimal, quantity: Decimal, policy: ReanchorPolicy) {
+        if price <= Decimal::ZERO || quantity <= Decimal::ZERO {
+            return;
+        }
+        let total = self.quantity + quantity;
+        self.entry_price = (self.entry_price * self.quantity + price * quantity) / total;
+        self.quantity = total;
+        self.remaining_quantity += quantity;
+
+        let initial_stop = self.initial_stop();
+        match policy {
+            ReanchorPolicy::Keep => {}
+            ReanchorPolicy::Tighter => {
+                self.stop_loss = match self.direction {
+                    TradeDirection::Long => self.stop_loss.max(initial_stop),
+                    TradeDirection::Short => self.stop_loss.min(initial_stop),
+                };
             }
-            TradeDirection::Short => {
-                let price = book_price.0;
-                let price_f64: f64 = price.parse().unwrap();
-                if price_f64 < self.lowest_price {
-                    self.lowest_price = price_f64;
-                    let profit_percentage =
-                        (self.entry_price - self.lowest_price) / self.entry_price;
-                    self.update_stop_loss(profit_percentage, false).await;
-                }
-                price
+            ReanchorPolicy::Reset => {
+                self.stop_loss = initial_stop;
+                self.highest_price = self.entry_price;
+                self.lowest_price = self.entry_price;
+                self.stop_strategy.reset();
             }
+        }
+    }
+
+    // 按当前开仓价计算的初始止损
+    fn initial_stop(&self) -> Decimal {
+        round_stop_price(
+            &self.direction,
+            calculate_stop_price(
+                &self.direction,
+                self.entry_price,
+                self.leverage,
+                self.stop_loss_percent,
+            ),
+            self.tick_size,
+        )
+    }
+
+    // 更新价格并调整历史最高或最低价和止损，返回到价的止盈单和止损触发价，由调用方启动平仓任务
+    pub fn update_price(&mut self, book: &PriceBook, events: &EventBus) -> PriceActions {
+        let mut actions = PriceActions::default();
+        if self.status != TradeStatus::Open {
+            return actions;
+        }
+        // 所选来源尚无有效价格时跳过本次 tick
+        let Some(price) = book.trigger_price(self.price_source, &self.direction) else {
+            return actions;
         };
-        self.check_exit_conditions(&price, database).await;
-    }
-
-    async fn update_stop_loss(&mut self, profit_percentage: f64, is_long: bool) {
-        let new_stop_price = self.calculate_new_stop_loss(profit_percentage, is_long);
-
-        if new_stop_price != self.stop_loss {
-            self.stop_loss = new_stop_price;
-            // let _ = cancel_order_with_retry(&self.symbol, self.stop_order).await;
-            // let (side, position_side) = if is_long {
-            //     ("SELL", "LONG")
-            // } else {
-            //     ("BUY", "SHORT")
-            // };
-            // if let Ok(stop_order_response) = create_order_with_retry(
-            //     &self.symbol,                      // 交易对
-            //     side,                              // 方向 (卖出止损或买入止损)
-            //     position_side,                     // 仓位方向 (LONG/SHORT)
-            //     &self.quantity,                    // 下单数量
-            //     format_stop_price(self.stop_loss), // 止损价格
-            // )
-            // .await
-            // {
-            //     self.stop_order = stop_order_response.orderId;
-            // }
-        }
-    }
-
-    fn calculate_new_stop_loss(&mut self, profit_percentage: f64, is_long: bool) -> f64 {
-        let actual_price_change_percentage = profit_percentage * self.leverage;
-
-        let adjustment = get_adjustment(actual_price_change_percentage, &mut self.adjustment);
-
-        if adjustment == 0.0 {
-            return self.stop_loss;
-        }
-        // let adjustment = match actual_price_change_percentage {
-        //     x if x >= 0.10 && x < 0.19 => 0.02,
-        //     x if x >= 0.20 && x < 0.29 => 0.04,
-        //     x if x >= 0.30 && x < 0.39 => 0.09,
-        //     x if x >= 0.40 && x < 0.49 => 0.16,
-        //     x if x >= 0.50 && x < 0.59 => 0.25,
-        //     x if x >= 0.60 && x < 0.69 => 0.36,
-        //     x if x >= 0.70 && x < 0.79 => 0.49,
-        //     x if x >= 0.7999 && x < 0.89 => 0.64,
-        //     x if x >= 0.8999 && x < 1.0 => 0.81,
-        //     x if x >= 0.9999 && x < 1.1 => 0.90,
-        //     x if x >= 1.1 => 0.1,
-        //     _ => return self.stop_loss,
-        // };
 
-        if is_long {
-            if actual_price_change_percentage >= 1.09 {
-                self.highest_price * (1.0 - adjustment / self.leverage)
-            } else {
-                self.entry_price * (1.0 + adjustment / self.leverage)
+        let previous_stop = self.stop_loss;
+        if self.track_price(price).stop_moved {
+            events.publish(
+                self,
+                TradeEventKind::StopMoved {
+                    from: previous_stop,
+                    to: self.stop_loss,
+                },
+            );
+        }
+        actions.take_profits = self.due_take_profits(price);
+        // 止盈单将平掉全部剩余仓位时不再检查止损
+        let scheduled: Decimal = actions.take_profits.iter().map(|o| o.quantity).sum();
+        if scheduled >= self.remaining_quantity || !self.check_exit_conditions(price) {
+            return actions;
+        }
+        // 本地保护止损先触发时，由平仓任务撤销交易所托管的跟踪止损单
+        if self.stop_strategy.is_exchange_managed() {
+            actions.cancel_stop = Some(self.stop_order);
+        }
+        events.publish(
+            self,
+            TradeEventKind::CloseRequested {
+                price,
+                reason: CloseReason::StopLoss,
+            },
+        );
+        actions.stop = Some(price);
+        actions
+    }
+
+    // 收盘 K 线交给止损策略，策略状态变化后按当前极值重新计算止损；触发检查仍由下一个行情完成
+    pub fn on_kline_close(&mut self, kline: &Kline, events: &EventBus) {
+        if self.status != TradeStatus::Open {
+            return;
+        }
+        self.stop_strategy.on_kline_close(kline);
+        let profit_percentage = match self.direction {
+            TradeDirection::Long => (self.highest_price - self.entry_price) / self.entry_price,
+            TradeDirection::Short => (self.entry_price - self.lowest_price) / self.entry_price,
+        };
+        let previous_stop = self.stop_loss;
+        self.stop_loss = self.calculate_new_stop_loss(profit_percentage);
+        if self.stop_loss != previous_stop {
+            events.publish(
+                self,
+                TradeEventKind::StopMoved {
+                    from: previous_stop,
+                    to: self.stop_loss,
+                },
+            );
+        }
+    }
+
+    // 跟踪极值价格并移动止损，不涉及下单；实盘与止损模拟共用
+    pub fn track_price(&mut self, price: Decimal) -> PriceStep {
+        let previous_stop = self.stop_loss;
+        let mut rung = None;
+
+        let profit_percentage = match self.direction {
+            TradeDirection::Long if price > self.highest_price => {
+                self.highest_price = price;
+                Some((self.highest_price - self.entry_price) / self.entry_price)
             }
-        } else {
-            if actual_price_change_percentage >= 1.09 {
-                self.lowest_price * (1.0 + adjustment / self.leverage)
-            } else {
-                self.entry_price * (1.0 - adjustment / self.leverage)
+            TradeDirection::Short if price < self.lowest_price => {
+                self.lowest_price = price;
+                Some((self.entry_price - self.lowest_price) / self.entry_price)
             }
+            _ => None,
+        };
+        if let Some(profit_percentage) = profit_percentage {
+            self.stop_loss = self.calculate_new_stop_loss(profit_percentage);
+            rung = self.stop_strategy.fired_rung();
+        }
+
+        PriceStep {
+            price,
+            stop_loss: self.stop_loss,
+            stop_moved: self.stop_loss != previous_stop,
+            rung,
+            stop_hit: self.status == TradeStatus::Open && self.is_stop_hit(price),
         }
     }
 
-    // 检查是否应平仓
-    async fn check_exit_conditions(&mut self, price: &str, database: &DatabaseConnection) {
-        // 如果交易已平仓，直接返回，不打印
-        if self.is_closed {
-            return;
+    fn is_stop_hit(&self, price: Decimal) -> bool {
+        match self.direction {
+            TradeDirection::Long => price <= self.stop_loss,
+            TradeDirection::Short => price >= self.stop_loss,
         }
-        let price_f64: f64 = price.parse().unwrap();
+    }
+
+    // 将到价的止盈目标标记为待成交并返回平仓单，成交后调用 settle_take_profit，失败时 release_take_profit
+    fn due_take_profits(&mut self, price: Decimal) -> Vec<TakeProfitOrder> {
+        let mut orders = Vec::new();
+        if self.status != TradeStatus::Open {
+            return orders;
+        }
+        let total_fraction: Decimal = self.take_profits.iter().map(|t| t.fraction).sum();
+        // 扣除仍在等待成交的止盈单
+        let in_flight: Decimal = self
+            .take_profits
+            .iter()
+            .filter(|t| t.pending)
+            .map(|t| round_to_step(self.quantity * t.fraction, self.step_size))
+            .sum();
+        let mut available = self.remaining_quantity - in_flight;
+
+        for i in 0..self.take_profits.len() {
+            let tp = &self.take_profits[i];
+            let reached = match self.direction {
+                TradeDirection::Long => price >= tp.price,
+                TradeDirection::Short => price <= tp.price,
+            };
+            if tp.is_hit || tp.pending || !reached {
+                continue;
+            }
+
+            // 所有目标比例合计为全部仓位时，最后一个目标平掉剩余数量，避免步长截断留下零头
+            let is_last = total_fraction >= Decimal::ONE
+                && self
+                    .take_profits
+                    .iter()
+                    .enumerate()
+                    .all(|(j, t)| j == i || t.is_hit || t.pending);
+            let mut quantity = round_to_step(self.quantity * tp.fraction, self.step_size);
+            if is_last || quantity >= available {
+                quantity = available;
+            }
+            if quantity <= Decimal::ZERO {
+                // 数量不足一个步长，没有可平的仓位
+                self.take_profits[i].is_hit = true;
+                continue;
+            }
 
-        if (self.direction == TradeDirection::Long && price_f64 <= self.stop_loss)
-            || (self.direction == TradeDirection::Short && price_f64 >= self.stop_loss)
-        {
             println!(
-                "止损触发于 {}，交易对 {}， 方向{:?}, 开仓价格: {}, 关闭交易 ID {}。",
-                price, self.symbol, self.direction, self.entry_price, self.id
+                "止盈触发于 {}，交易对 {}， 方向{:?}, 平仓数量: {}, 交易 ID {}。",
+                price, self.symbol, self.direction, quantity, self.id
             );
-            let (side, position_side) = match self.direction {
-                TradeDirection::Long => ("SELL", "LONG"),
-                TradeDirection::Short => ("BUY", "SHORT"),
-            };
-            match create_order(
-                &self.symbol,
-                side,
-                position_side,
-                "MARKET",       // 假设使用市价单
-                &self.quantity, // 将数量格式化为字符串
-                None,           // 市价单无需价格
-                None,           // 此示例未设置止损价格
-                &self.api_key,
-                &self.api_secret,
-            )
+            self.take_profits[i].pending = true;
+            available -= quantity;
+            orders.push(TakeProfitOrder {
+                index: i,
+                quantity,
+                price,
+            });
+        }
+        orders
+    }
+
+    // 止盈单成交：目标标记为已成交并减仓
+    pub fn settle_take_profit(
+        &mut self,
+        order: &TakeProfitOrder,
+        fill: &CloseFill,
+        events: &EventBus,
+    ) {
+        if let Some(tp) = self.take_profits.get_mut(order.index) {
+            tp.pending = false;
+            tp.is_hit = true;
+        }
+        let quantity = order.quantity.min(self.remaining_quantity);
+        self.reduce_position(quantity);
+        events.publish(
+            self,
+            self.close_event(fill, quantity, CloseReason::TakeProfit),
+        );
+    }
+
+    // 止盈单失败：恢复目标，价格再次到达时重新触发
+    pub fn release_take_profit(&mut self, index: usize) {
+        if let Some(tp) = self.take_profits.get_mut(index) {
+            tp.pending = false;
+        }
+    }
+
+    // 平仓成交后的事件：仍有剩余持仓时为部分平仓，需在 reduce_position 之后调用
+    pub fn close_event(
+        &self,
+        fill: &CloseFill,
+        quantity: Decimal,
+        reason: CloseReason,
+    ) -> TradeEventKind {
+        if self.is_closed() {
+            TradeEventKind::Closed {
+                order_id: fill.order_id,
+                price: fill.price,
+                trigger_price: fill.trigger_price,
+                quantity,
+                reason,
+            }
+        } else {
+            TradeEventKind::PartialClose {
+                order_id: fill.order_id,
+                price: fill.price,
+                trigger_price: fill.trigger_price,
+                quantity,
+                remaining_quantity: self.remaining_quantity,
+                reason,
+            }
+        }
+    }
+
+    // 按数量平仓（对冲模式下反向下单 + positionSide 即为只减仓），成功后返回成交结果
+    pub async fn close_position(&self, quantity: Decimal, price: Decimal) -> Option<CloseFill> {
+        let (side, position_side) = match self.direction {
+            TradeDirection::Long => ("SELL", "LONG"),
+            TradeDirection::Short => ("BUY", "SHORT"),
+        };
+        match create_order(
+            &self.symbol,
+            side,
+            position_side,
+            "MARKET", // 假设使用市价单
+            &quantity.to_string(),
+            None, // 市价单无需价格
+            None, // 此示例未设置止损价格
+            &self.api_key,
+            &self.api_secret,
+        )
+        .await
+        {
+            Ok(order) => {
+                let close_price = match get_order_api(
+                    &self.symbol,
+                    order.orderId,
+                    &self.api_key,
+                    &self.api_secret,
+                )
+                .await
+                {
+                    Ok(b_order) => parse_decimal(&b_order.avgPrice)
+                        .filter(|p| *p > Decimal::ZERO)
+                        .unwrap_or(price),
+                    Err(_) => price,
+                };
+                Some(CloseFill {
+                    order_id: order.orderId,
+                    price: close_price,
+                    trigger_price: price,
+                })
+            }
+            Err(e) => {
+                eprintln!(
+                    "平仓下单失败，交易对 {}，交易 ID {}：{}",
+                    self.symbol, self.id, e
+                );
+                None
+            }
+        }
+    }
+
+    fn calculate_new_stop_loss(&mut self, profit_percentage: Decimal) -> Decimal {
+        let ctx = StopContext {
+            direction: &self.direction,
+            entry_price: self.entry_price,
+            highest_price: self.highest_price,
+            lowest_price: self.lowest_price,
+            leverage: self.leverage,
+            stop_loss: self.stop_loss,
+            profit_percentage,
+        };
+        match self.stop_strategy.next_stop(&ctx) {
+            Some(stop) => round_stop_price(&self.direction, stop, self.tick_size),
+            None => self.stop_loss,
+        }
+    }
+
+    // 检查是否应平仓：触发后进入 Closing，实际下单由 close_with_retry 完成
+    fn check_exit_conditions(&mut self, price: Decimal) -> bool {
+        if self.status != TradeStatus::Open || !self.is_stop_hit(price) {
+            return false;
+        }
+        println!(
+            "止损触发于 {}，交易对 {}， 方向{:?}, 开仓价格: {}, 关闭交易 ID {}。",
+            price, self.symbol, self.direction, self.entry_price, self.id
+        );
+        self.status = TradeStatus::Closing;
+        true
+    }
+}
+
+// 平仓最多重试次数及首次退避时间，之后每次翻倍
+const CLOSE_MAX_ATTEMPTS: u32 = 5;
+const CLOSE_RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+// 平仓任务：对 Closing 状态的交易市价平仓，失败时指数退避重试，全部失败后标记为 CloseFailed。
+// 对冲模式下超出持仓的平仓单会被交易所拒绝，重试不会造成反向开仓。
+pub async fn close_with_retry(
+    trades: Arc<SymbolMap<Mutex<Vec<Trade>>>>,
+    events: EventBus,
+    symbol: String,
+    trade_id: usize,
+    price: Decimal,
+    reason: CloseReason,
+) {
+    let Some(mutex_vec) = trades.get(&symbol) else {
+        return;
+    };
+
+    for attempt in 1..=CLOSE_MAX_ATTEMPTS {
+        // 下单期间不持有锁，避免阻塞行情任务
+        let trade = {
+            let vec = mutex_vec.lock().await;
+            match vec
+                .iter()
+                .find(|t| t.id == trade_id && t.status == TradeStatus::Closing)
+            {
+                Some(t) => t.clone(),
+                None => return,
+            }
+        };
+
+        if let Some(fill) = TradeExchange
+            .close_position(&trade, trade.remaining_quantity, price)
+            .await
+        {
+            let mut vec = mutex_vec.lock().await;
+            if let Some(t) = vec.iter_mut().find(|t| t.id == trade_id) {
+                let quantity = t.remaining_quantity;
+                t.reduce_position(quantity);
+                events.publish(t, t.close_event(&fill, quantity, reason));
+            }
+            return;
+        }
+
+        eprintln!(
+            "平仓失败（第 {}/{} 次），交易对 {}，交易 ID {}",
+            attempt, CLOSE_MAX_ATTEMPTS, symbol, trade_id
+        );
+        if attempt < CLOSE_MAX_ATTEMPTS {
+            sleep(CLOSE_RETRY_BACKOFF * 2u32.pow(attempt - 1)).await;
+        }
+    }
+
+    let mut vec = mutex_vec.lock().await;
+    if let Some(t) = vec
+        .iter_mut()
+        .find(|t| t.id == trade_id && t.status == TradeStatus::Closing)
+    {
+        t.status = TradeStatus::CloseFailed;
+        events.publish(
+            t,
+            TradeEventKind::CloseFailed {
+                quantity: t.remaining_quantity,
+            },
+        );
+        eprintln!(
+            "!!!!! 平仓失败，持仓仍在交易所，需要人工处理：交易对 {}，交易 ID {}，方向 {}，数量 {} !!!!!",
+            symbol, trade_id, t.direction, t.remaining_quantity
+        );
+    }
+}
+
+// 止盈平仓任务：与 close_with_retry 相同的退避重试，全部失败后恢复目标并发布失败事件
+pub async fn close_take_profit(
+    trades: Arc<SymbolMap<Mutex<Vec<Trade>>>>,
+    events: EventBus,
+    symbol: String,
+    trade_id: usize,
+    order: TakeProfitOrder,
+) {
+    let Some(mutex_vec) = trades.get(&symbol) else {
+        return;
+    };
+
+    for attempt in 1..=CLOSE_MAX_ATTEMPTS {
+        // 下单期间不持有锁；交易已止损或手动平仓时放弃止盈
+        let trade = {
+            let vec = mutex_vec.lock().await;
+            match vec
+                .iter()
+                .find(|t| t.id == trade_id && t.status == TradeStatus::Open)
+            {
+                Some(t) => t.clone(),
+                None => return,
+            }
+        };
+
+        if let Some(fill) = TradeExchange
+            .close_position(&trade, order.quantity, order.price)
             .await
+        {
+            let mut vec = mutex_vec.lock().await;
+            if let Some(t) = vec.iter_mut().find(|t| t.id == trade_id) {
+                t.settle_take_profit(&order, &fill, &events);
+            }
+            return;
+        }
+
+        eprintln!(
+            "止盈平仓失败（第 {}/{} 次），交易对 {}，交易 ID {}",
+            attempt, CLOSE_MAX_ATTEMPTS, symbol, trade_id
+        );
+        if attempt < CLOSE_MAX_ATTEMPTS {
+            sleep(CLOSE_RETRY_BACKOFF * 2u32.pow(attempt - 1)).await;
+        }
+    }
+
+    let mut vec = mutex_vec.lock().await;
+    if let Some(t) = vec.iter_mut().find(|t| t.id == trade_id) {
+        t.release_take_profit(order.index);
+        events.publish(
+            t,
+            TradeEventKind::TakeProfitFailed {
+                price: order.price,
+                quantity: order.quantity,
+            },
+        );
+    }
+}
+
+// 轮询交易所托管的止损单，成交后将交易标记为已平仓并写入历史记录
+pub async fn watch_exchange_stop(
+    trades: Arc<SymbolMap<Mutex<Vec<Trade>>>>,
+    events: EventBus,
+    symbol: String,
+    trade_id: usize,
+) {
+    loop {
+        sleep(Duration::from_secs(5)).await;
+
+        let Some(mutex_vec) = trades.get(&symbol) else {
+            return;
+        };
+        let trade = {
+            let vec = mutex_vec.lock().await;
+            match vec
+                .iter()
+                .find(|t| t.id == trade_id && t.status == TradeStatus::Open)
             {
-                Ok(order) => {
-                    match get_order_api(
-                        &self.symbol,
-                        order.orderId,
-                        &self.api_key,
-                        &self.api_secret,
-                    )
+                Some(t) => t.clone(),
+                None => return, // 交易已被本地平仓或手动平仓
+            }
+        };
+
+        let order =
+            match get_order_api(&symbol, trade.stop_order, &trade.api_key, &trade.api_secret).await
+            {
+                Ok(order) => order,
+                Err(_) => continue,
+            };
+
+        match order.status.as_str() {
+            "FILLED" => {
+                let mut vec = mutex_vec.lock().await;
+                if let Some(t) = vec
+                    .iter_mut()
+                    .find(|t| t.id == trade_id && t.status == TradeStatus::Open)
+                {
+                    println!(
+                        "交易所跟踪止损成交于 {}，交易对 {}，交易 ID {}。",
+                        order.avgPrice, symbol, trade_id
+                    );
+                    let close_price = parse_decimal(&order.avgPrice).unwrap_or(t.stop_loss);
+                    let quantity =
+                        parse_decimal(&order.executedQty).unwrap_or(t.remaining_quantity);
+                    t.status = TradeStatus::Closed;
+                    events.publish(
+                        t,
+                        TradeEventKind::Closed {
+                            order_id: t.stop_order,
+                            price: close_price,
+                            trigger_price: t.stop_loss,
+                            quantity,
+                            reason: CloseReason::StopLoss,
+                        },
+                    );
+                }
+                return;
+            }
+            "CANCELED" | "EXPIRED" | "REJECTED" => {
+                // 改单或部分平仓时止损单会被替换，继续轮询新订单
+                let replaced = mutex_vec
+                    .lock()
                     .await
-                    {
-                        Ok(b_order) => {
-                            create_trade_record(database, &self, &b_order.avgPrice).await
-                        }
-                        Err(_) => create_trade_record(database, &self, price).await,
+                    .iter()
+                    .any(|t| t.id == trade_id && t.stop_order != trade.stop_order);
+                if replaced {
+                    continue;
+                }
+                eprintln!(
+                    "交易所跟踪止损单失效 ({})，交易对 {}，交易 ID {}，仅保留本地止损。",
+                    order.status, symbol, trade_id
+                );
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+// 轮询限价/条件开仓单：成交（含部分成交）后激活交易，到期未成交则撤单
+pub async fn watch_pending_entry(
+    trades: Arc<SymbolMap<Mutex<Vec<Trade>>>>,
+    events: EventBus,
+    symbol: String,
+    trade_id: usize,
+    expires_at: Option<Instant>,
+) {
+    let Some(mutex_vec) = trades.get(&symbol) else {
+        return;
+    };
+    let (order_id, api_key, api_secret) = {
+        let vec = mutex_vec.lock().await;
+        match vec.iter().find(|t| t.id == trade_id) {
+            Some(t) => (t.order_id, t.api_key.clone(), t.api_secret.clone()),
+            None => return,
+        }
+    };
+
+    loop {
+        sleep(Duration::from_secs(2)).await;
+
+        let order = match get_order_api(&symbol, order_id, &api_key, &api_secret).await {
+            Ok(order) => order,
+            Err(_) => continue,
+        };
+        let executed = parse_decimal(&order.executedQty).unwrap_or_default();
+        let avg_price = parse_decimal(&order.avgPrice).unwrap_or_default();
+
+        let finished = matches!(
+            order.status.as_str(),
+            "FILLED" | "CANCELED" | "EXPIRED" | "REJECTED"
+        );
+        let should_cancel = {
+            let mut vec = mutex_vec.lock().await;
+            match vec.iter_mut().find(|t| {
+                t.id == trade_id && matches!(t.status, TradeStatus::Pending | TradeStatus::Open)
+            }) {
+                Some(t) => {
+                    let was_pending = t.status == TradeStatus::Pending;
+                    t.apply_entry_fill(avg_price, executed);
+                    if was_pending && t.status == TradeStatus::Open {
+                        events.publish(
+                            t,
+                            TradeEventKind::TradeOpened {
+                                quantity: t.quantity,
+                                stop_loss: t.stop_loss,
+                            },
+                        );
+                    }
+                    if finished && t.status == TradeStatus::Pending {
+                        println!(
+                            "开仓单未成交即失效 ({})，交易对 {}，交易 ID {}。",
+                            order.status, symbol, trade_id
+                        );
+                        t.status = TradeStatus::Closed;
                     }
+                    !finished && expires_at.is_some_and(|at| Instant::now() >= at)
                 }
-                Err(_) => {}
+                // 交易已平仓或被移除，剩余开仓单不再需要
+                None => !finished,
             }
+        };
 
-            // 设置为已平仓状态
-            self.is_closed = true;
+        if finished {
+            return;
+        }
+        if should_cancel {
+            // 撤单后下一轮读取最终成交量
+            let _ = cancel_order(&symbol, order_id, &api_key, &api_secret).await;
         }
     }
 }
 
-pub async fn create_trade_record(database: &DatabaseConnection, trade: &Trade, price: &str) {
-    let new_pool = trades::ActiveModel {
+// 记录一次加仓
+pub async fn create_leg_record(
+    database: &DatabaseConnection,
+    trade: &Trade,
+    order_id: u64,
+    price: Decimal,
+    quantity: Decimal,
+    policy: ReanchorPolicy,
+) {
+    let leg = trade_legs::ActiveModel {
+        trade_id: Set(trade.id as i64),
+        owner_id: Set(trade.owner_id.clone()),
         symbol: Set(trade.symbol.clone()),
+        order_id: Set(order_id as i64),
+        price: Set(price.to_string()),
+        quantity: Set(quantity.to_string()),
         entry_price: Set(trade.entry_price.to_string()),
-        close_price: Set(price.to_string()),
-        direction: Set(trade.direction.to_string()),
-        quantity: Set(trade.quantity.clone()),
-        leverage: Set(trade.leverage.to_string()),
+        total_quantity: Set(trade.quantity.to_string()),
+        reanchor: Set(policy.to_string()),
         ..Default::default()
     };
-    let _ = new_pool.insert(database).await.unwrap();
+    if let Err(e) = leg.insert(database).await {
+        eprintln!("加仓记录写入失败，交易 ID {}：{}", trade.id, e);
+    }
 }
 
 pub fn calculate_stop_price(
     direction: &TradeDirection,
-    price: f64,
-    leverage: f64,
-    stop_loss_percent: f64,
-) -> f64 {
+    price: Decimal,
+    leverage: Decimal,
+    stop_loss_percent: Decimal,
+) -> Decimal {
     match direction {
-        TradeDirection::Long => price * (1.0 - stop_loss_percent / leverage), // 做多时根据杠杆倍数和调整参数设置止损
-        TradeDirection::Short => price * (1.0 + stop_loss_percent / leverage), // 做空时根据杠杆倍数和调整参数设置止损
+        TradeDirection::Long => price * (Decimal::ONE - stop_loss_percent / leverage), // 做多时根据杠杆倍数和调整参数设置止损
+        TradeDirection::Short => price * (Decimal::ONE + stop_loss_percent / leverage), // 做空时根据杠杆倍数和调整参数设置止损
+    }
+}
+
+// 止损价按最小价格单位取整：做多向下、做空向上，取整后不会比计算值更紧
+pub fn round_stop_price(direction: &TradeDirection, stop: Decimal, tick_size: Decimal) -> Decimal {
+    round_to_tick(stop, tick_size, *direction == TradeDirection::Short)
+}
+
+// 止盈请求换算为价格并按最小价格单位取整
+pub fn build_take_profits(
+    take_profits: &[TakeProfitRequest],
+    direction: &TradeDirection,
+    leverage: Decimal,
+    entry_price: Decimal,
+    filter: &SymbolFilter,
+) -> Vec<TakeProfit> {
+    take_profits
+        .iter()
+        .map(|tp| {
+            let price = tp.price.unwrap_or_else(|| {
+                calculate_take_profit_price(
+                    direction,
+                    entry_price,
+                    leverage,
+                    tp.roi.unwrap_or_default(),
+                )
+            });
+            TakeProfit {
+                price: round_to_tick(price, filter.tick_size, *direction == TradeDirection::Long),
+                fraction: tp.fraction,
+                is_hit: false,
+                pending: false,
+            }
+        })
+        .collect()
+}
+
+// 将杠杆收益率目标换算为止盈价格
+pub fn calculate_take_profit_price(
+    direction: &TradeDirection,
+    price: Decimal,
+    leverage: Decimal,
+    roi: Decimal,
+) -> Decimal {
+    match direction {
+        TradeDirection::Long => price * (Decimal::ONE + roi / leverage),
+        TradeDirection::Short => price * (Decimal::ONE - roi / leverage),
     }
 }
 
@@ -334,133 +1071,178 @@ pub fn calculate_stop_price(
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Adjustment {
-    pub min: f64,
-    pub max: Option<f64>,
-    pub adjustment: f64,
+    pub min: Decimal,
+    pub max: Option<Decimal>,
+    pub adjustment: Decimal,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
-pub struct AdjustmentConfig {
-    pub adjustments: Vec<Adjustment>,
+// 校验阶梯配置：区间连续且不重叠，min < max，调整幅度不递减且不超过档位下限
+pub fn validate_adjustments(adjustments: &[Adjustment]) -> Result<(), String> {
+    if adjustments.is_empty() {
+        return Err("Adjustments must not be empty".to_string());
+    }
+
+    for (i, adj) in adjustments.iter().enumerate() {
+        if adj.min < Decimal::ZERO || adj.adjustment < Decimal::ZERO {
+            return Err(format!(
+                "Rung {}: min and adjustment must not be negative",
+                i
+            ));
+        }
+        if adj.adjustment > adj.min {
+            return Err(format!("Rung {}: adjustment exceeds range min", i));
+        }
+        match adj.max {
+            Some(max) if max <= adj.min => {
+                return Err(format!("Rung {}: min must be less than max", i));
+            }
+            None if i + 1 != adjustments.len() => {
+                return Err(format!("Rung {}: only the last rung may be open-ended", i));
+            }
+            _ => {}
+        }
+
+        if let Some(prev) = i.checked_sub(1).map(|p| &adjustments[p]) {
+            // 上一档已校验过 max 存在
+            let prev_max = prev.max.unwrap_or_default();
+            if adj.min < prev_max {
+                return Err(format!("Rung {}: overlaps previous range", i));
+            }
+            if adj.min > prev_max {
+                return Err(format!("Rung {}: gap after previous range", i));
+            }
+            if adj.adjustment < prev.adjustment {
+                return Err(format!("Rung {}: adjustment decreases", i));
+            }
+        }
+    }
+    Ok(())
 }
 
-fn get_adjustment(percentage: f64, adjustments: &mut Vec<Adjustment>) -> f64 {
-    adjustments.retain(|adj| percentage <= adj.max.unwrap_or(f64::INFINITY));
+// 返回当前收益率命中的档位；低于当前收益率的档位会被移除，止损只会沿阶梯上移
+fn get_adjustment(percentage: Decimal, adjustments: &mut Vec<Adjustment>) -> Option<Adjustment> {
+    adjustments.retain(|adj| adj.max.is_none_or(|max| percentage <= max));
 
     adjustments
         .iter()
-        .find(|adj| percentage >= adj.min && adj.max.map_or(true, |max| percentage < max))
-        .map_or_else(|| 0.0, |adj| adj.adjustment)
+        .find(|adj| percentage >= adj.min && adj.max.is_none_or(|max| percentage < max))
+        .cloned()
 }
 
 #[cfg(test)]
 mod tests {
+    use super::strategy::LadderStop;
     use super::*;
-    // use std::f64::EPSILON;
-    const EPSILON: f64 = 1e-5;
+    use rust_decimal_macros::dec;
 
     #[test]
     fn test_calculate_new_stop_loss_long() {
         let adjustment = vec![
             Adjustment {
-                min: 0.10,
-                max: Some(0.19),
-                adjustment: 0.02,
+                min: dec!(0.10),
+                max: Some(dec!(0.19)),
+                adjustment: dec!(0.02),
             },
             Adjustment {
-                min: 0.20,
-                max: Some(0.29),
-                adjustment: 0.04,
+                min: dec!(0.20),
+                max: Some(dec!(0.29)),
+                adjustment: dec!(0.04),
             },
             Adjustment {
-                min: 0.30,
-                max: Some(0.39),
-                adjustment: 0.09,
+                min: dec!(0.30),
+                max: Some(dec!(0.39)),
+                adjustment: dec!(0.09),
             },
             Adjustment {
-                min: 0.40,
-                max: Some(0.49),
-                adjustment: 0.16,
+                min: dec!(0.40),
+                max: Some(dec!(0.49)),
+                adjustment: dec!(0.16),
             },
             Adjustment {
-                min: 0.50,
-                max: Some(0.59),
-                adjustment: 0.25,
+                min: dec!(0.50),
+                max: Some(dec!(0.59)),
+                adjustment: dec!(0.25),
             },
             Adjustment {
-                min: 0.60,
-                max: Some(0.69),
-                adjustment: 0.36,
+                min: dec!(0.60),
+                max: Some(dec!(0.69)),
+                adjustment: dec!(0.36),
             },
             Adjustment {
-                min: 0.70,
-                max: Some(0.79),
-                adjustment: 0.49,
+                min: dec!(0.70),
+                max: Some(dec!(0.79)),
+                adjustment: dec!(0.49),
             },
             Adjustment {
-                min: 0.7999,
-                max: Some(0.89),
-                adjustment: 0.64,
+                min: dec!(0.7999),
+                max: Some(dec!(0.89)),
+                adjustment: dec!(0.64),
             },
             Adjustment {
-                min: 0.8999,
-                max: Some(1.0),
-                adjustment: 0.81,
+                min: dec!(0.8999),
+                max: Some(dec!(1.0)),
+                adjustment: dec!(0.81),
             },
             Adjustment {
-                min: 0.9999,
-                max: Some(1.1),
-                adjustment: 0.90,
+                min: dec!(0.9999),
+                max: Some(dec!(1.1)),
+                adjustment: dec!(0.90),
             },
             Adjustment {
-                min: 1.1,
+                min: dec!(1.1),
                 max: None,
-                adjustment: 0.1,
+                adjustment: dec!(0.1),
             },
         ];
         let mut trade = Trade {
             owner_id: "".to_string(),
-            entry_price: 4.5,
-            highest_price: 5.0,
-            lowest_price: 4.0,
-            leverage: 10.0,
-            stop_loss: 4.0,
+            entry_price: dec!(4.5),
+            highest_price: dec!(5.0),
+            lowest_price: dec!(4.0),
+            leverage: dec!(10.0),
+            stop_loss: dec!(4.0),
             id: 1,
             order_id: 1,
             stop_order: 1,
             symbol: "Filusdt".to_string(),
             direction: TradeDirection::Long,
-            quantity: "1.0".to_string(),
-            adjustment,
-            is_closed: false,
+            quantity: dec!(1.0),
+            remaining_quantity: dec!(1.0),
+            tick_size: dec!(0.0001),
+            step_size: dec!(0.1),
+            stop_strategy: Box::new(LadderStop::new(adjustment)),
+            take_profits: vec![],
+            price_source: PriceSource::Book,
+            status: TradeStatus::Open,
+            stop_loss_percent: dec!(0.5),
+            paper: false,
+            opened_at: 0,
+            adjustment_id: None,
             api_key: "".to_string(),
             api_secret: "".to_string(),
         };
 
         let test_cases = vec![
-            (0.009, 4.0, "No change for profit < 10%"),
-            (0.010, 4.509, "Profit 10%"),
-            (0.020, 4.518, "Profit 20%"),
-            (0.030, 4.5405, "Profit 30%"),
-            (0.040, 4.572, "Profit 40%"),
-            (0.050, 4.6125, "Profit 50%"),
-            (0.060, 4.662, "Profit 60%"),
-            (0.070, 4.7205, "Profit 70%"),
-            (0.080, 4.788, "Profit 80%"),
-            (0.090, 4.8645, "Profit 90%"),
-            (0.1, 4.905, "Profit 100%"),
-            (0.12, 4.95, "Profit 120%"),
+            (dec!(0.009), dec!(4.0), "No change for profit < 10%"),
+            (dec!(0.010), dec!(4.509), "Profit 10%"),
+            (dec!(0.020), dec!(4.518), "Profit 20%"),
+            (dec!(0.030), dec!(4.5405), "Profit 30%"),
+            (dec!(0.040), dec!(4.572), "Profit 40%"),
+            (dec!(0.050), dec!(4.6125), "Profit 50%"),
+            (dec!(0.060), dec!(4.662), "Profit 60%"),
+            (dec!(0.070), dec!(4.7205), "Profit 70%"),
+            (dec!(0.080), dec!(4.788), "Profit 80%"),
+            (dec!(0.090), dec!(4.8645), "Profit 90%"),
+            (dec!(0.1), dec!(4.905), "Profit 100%"),
+            (dec!(0.12), dec!(4.95), "Profit 120%"),
         ];
 
         for (profit, expected, description) in test_cases {
-            let result = trade.calculate_new_stop_loss(profit, true);
-            // let EPSILON = 1e-9;
-            assert!(
-                (result - expected).abs() <= EPSILON,
-                "{}: Expected {:.4}, got {:.4}",
-                description,
-                expected,
-                result
+            let result = trade.calculate_new_stop_loss(profit);
+            assert_eq!(
+                result, expected,
+                "{}: Expected {}, got {}",
+                description, expected, result
             );
         }
     }
@@ -469,76 +1251,85 @@ mod tests {
     fn test_calculate_new_stop_loss_short() {
         let adjustment = vec![
             Adjustment {
-                min: 0.10,
-                max: Some(0.19),
-                adjustment: 0.02,
+                min: dec!(0.10),
+                max: Some(dec!(0.19)),
+                adjustment: dec!(0.02),
             },
             Adjustment {
-                min: 0.20,
-                max: Some(0.29),
-                adjustment: 0.04,
+                min: dec!(0.20),
+                max: Some(dec!(0.29)),
+                adjustment: dec!(0.04),
             },
             Adjustment {
-                min: 0.30,
-                max: Some(0.39),
-                adjustment: 0.09,
+                min: dec!(0.30),
+                max: Some(dec!(0.39)),
+                adjustment: dec!(0.09),
             },
             Adjustment {
-                min: 0.40,
-                max: Some(0.49),
-                adjustment: 0.16,
+                min: dec!(0.40),
+                max: Some(dec!(0.49)),
+                adjustment: dec!(0.16),
             },
             Adjustment {
-                min: 0.50,
-                max: Some(0.59),
-                adjustment: 0.25,
+                min: dec!(0.50),
+                max: Some(dec!(0.59)),
+                adjustment: dec!(0.25),
             },
             Adjustment {
-                min: 0.60,
-                max: Some(0.69),
-                adjustment: 0.36,
+                min: dec!(0.60),
+                max: Some(dec!(0.69)),
+                adjustment: dec!(0.36),
             },
             Adjustment {
-                min: 0.70,
-                max: Some(0.79),
-                adjustment: 0.49,
+                min: dec!(0.70),
+                max: Some(dec!(0.79)),
+                adjustment: dec!(0.49),
             },
             Adjustment {
-                min: 0.7999,
-                max: Some(0.89),
-                adjustment: 0.64,
+                min: dec!(0.7999),
+                max: Some(dec!(0.89)),
+                adjustment: dec!(0.64),
             },
             Adjustment {
-                min: 0.8999,
-                max: Some(1.0),
-                adjustment: 0.81,
+                min: dec!(0.8999),
+                max: Some(dec!(1.0)),
+                adjustment: dec!(0.81),
             },
             Adjustment {
-                min: 0.9999,
-                max: Some(1.1),
-                adjustment: 0.90,
+                min: dec!(0.9999),
+                max: Some(dec!(1.1)),
+                adjustment: dec!(0.90),
             },
             Adjustment {
-                min: 1.1,
+                min: dec!(1.1),
                 max: None,
-                adjustment: 0.1,
+                adjustment: dec!(0.1),
             },
         ];
         let mut trade = Trade {
             owner_id: "".to_string(),
-            entry_price: 4.5,
-            highest_price: 5.0,
-            lowest_price: 4.0,
-            leverage: 10.0,
-            stop_loss: 5.0,
+            entry_price: dec!(4.5),
+            highest_price: dec!(5.0),
+            lowest_price: dec!(4.0),
+            leverage: dec!(10.0),
+            stop_loss: dec!(5.0),
             id: 1,
             order_id: 1,
             stop_order: 1,
             symbol: "Filusdt".to_string(),
             direction: TradeDirection::Short,
-            quantity: "1.0".to_string(),
-            adjustment,
-            is_closed: false,
+            quantity: dec!(1.0),
+            remaining_quantity: dec!(1.0),
+            tick_size: dec!(0.0001),
+            step_size: dec!(0.1),
+            stop_strategy: Box::new(LadderStop::new(adjustment)),
+            take_profits: vec![],
+            price_source: PriceSource::Book,
+            status: TradeStatus::Open,
+            stop_loss_percent: dec!(0.5),
+            paper: false,
+            opened_at: 0,
+            adjustment_id: None,
             api_key: "".to_string(),
             api_secret: "".to_string(),
         };
@@ -557,29 +1348,342 @@ mod tests {
         // _ => return self.stop_loss,
 
         let test_cases = vec![
-            (0.009, 5.0, "No change for profit < 10%"),
-            (0.010, 4.491, "Profit 10%"),
-            (0.020, 4.482, "Profit 20%"),
-            (0.030, 4.4595, "Profit 30%"),
-            (0.040, 4.428, "Profit 40%"),
-            (0.050, 4.3875, "Profit 50%"),
-            (0.060, 4.338, "Profit 60%"),
-            (0.070, 4.2795, "Profit 70%"),
-            (0.080, 4.212, "Profit 80%"),
-            (0.090, 4.1355, "Profit 90%"),
-            (0.10, 4.095, "Profit 100%"),
-            (0.12, 4.04, "Profit 120%"),
+            (dec!(0.009), dec!(5.0), "No change for profit < 10%"),
+            (dec!(0.010), dec!(4.491), "Profit 10%"),
+            (dec!(0.020), dec!(4.482), "Profit 20%"),
+            (dec!(0.030), dec!(4.4595), "Profit 30%"),
+            (dec!(0.040), dec!(4.428), "Profit 40%"),
+            (dec!(0.050), dec!(4.3875), "Profit 50%"),
+            (dec!(0.060), dec!(4.338), "Profit 60%"),
+            (dec!(0.070), dec!(4.2795), "Profit 70%"),
+            (dec!(0.080), dec!(4.212), "Profit 80%"),
+            (dec!(0.090), dec!(4.1355), "Profit 90%"),
+            (dec!(0.10), dec!(4.095), "Profit 100%"),
+            (dec!(0.12), dec!(4.04), "Profit 120%"),
         ];
 
         for (profit, expected, description) in test_cases {
-            let result = trade.calculate_new_stop_loss(profit, false);
+            let result = trade.calculate_new_stop_loss(profit);
+            assert_eq!(
+                result, expected,
+                "{}: Expected {}, got {}",
+                description, expected, result
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_adjustments() {
+        let rung = |min, max: Option<Decimal>, adjustment| Adjustment {
+            min,
+            max,
+            adjustment,
+        };
+
+        assert!(validate_adjustments(&preset::default_adjustments()).is_ok());
+        assert!(validate_adjustments(&[]).is_err());
+
+        let cases = vec![
+            (
+                vec![
+                    rung(dec!(0.1), Some(dec!(0.3)), dec!(0.02)),
+                    rung(dec!(0.2), Some(dec!(0.4)), dec!(0.04)),
+                ],
+                "overlap",
+            ),
+            (
+                vec![
+                    rung(dec!(0.1), Some(dec!(0.19)), dec!(0.02)),
+                    rung(dec!(0.2), Some(dec!(0.3)), dec!(0.04)),
+                ],
+                "gap",
+            ),
+            (
+                vec![rung(dec!(0.3), Some(dec!(0.2)), dec!(0.02))],
+                "min > max",
+            ),
+            (
+                vec![
+                    rung(dec!(0.1), Some(dec!(0.2)), dec!(0.04)),
+                    rung(dec!(0.2), Some(dec!(0.3)), dec!(0.02)),
+                ],
+                "decreasing",
+            ),
+            (
+                vec![
+                    rung(dec!(0.1), None, dec!(0.02)),
+                    rung(dec!(0.2), Some(dec!(0.3)), dec!(0.04)),
+                ],
+                "open-ended middle rung",
+            ),
+            (
+                vec![rung(dec!(0.1), Some(dec!(0.2)), dec!(0.15))],
+                "adjustment > min",
+            ),
+        ];
+
+        for (adjustments, description) in cases {
             assert!(
-                (result - expected).abs() < EPSILON,
-                "{}: Expected {:.4}, got {:.4}",
-                description,
-                expected,
-                result
+                validate_adjustments(&adjustments).is_err(),
+                "{} should be rejected",
+                description
             );
         }
     }
+
+    // 入场 4.5、10 倍杠杆、默认阶梯的做多交易
+    fn ladder_trade() -> Trade {
+        Trade {
+            owner_id: "".to_string(),
+            entry_price: dec!(4.5),
+            highest_price: dec!(4.5),
+            lowest_price: dec!(4.5),
+            leverage: dec!(10),
+            stop_loss: dec!(4.275),
+            id: 1,
+            order_id: 1,
+            stop_order: 1,
+            symbol: "Filusdt".to_string(),
+            direction: TradeDirection::Long,
+            quantity: dec!(1.0),
+            remaining_quantity: dec!(1.0),
+            tick_size: dec!(0.0001),
+            step_size: dec!(0.1),
+            stop_strategy: strategy::build_stop_strategy(
+                &strategy::StopStrategyConfig::Ladder,
+                preset::default_adjustments(),
+                &[],
+            ),
+            take_profits: vec![],
+            price_source: PriceSource::Book,
+            status: TradeStatus::Open,
+            stop_loss_percent: dec!(0.5),
+            paper: false,
+            opened_at: 0,
+            adjustment_id: None,
+            api_key: "".to_string(),
+            api_secret: "".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_track_price_path() {
+        let mut trade = ladder_trade();
+
+        // 收益 22.2% 命中 0.2 档，止损上移到 4.518
+        let step = trade.track_price(dec!(4.6));
+        assert!(step.stop_moved && !step.stop_hit);
+        assert_eq!(step.stop_loss, dec!(4.518));
+        assert_eq!(step.rung.map(|r| r.min), Some(dec!(0.20)));
+
+        // 回落但未破止损，止损不动
+        let step = trade.track_price(dec!(4.55));
+        assert!(!step.stop_moved && !step.stop_hit);
+        assert!(step.rung.is_none());
+
+        let step = trade.track_price(dec!(4.51));
+        assert!(step.stop_hit);
+        assert_eq!(step.stop_loss, dec!(4.518));
+    }
+
+    #[test]
+    fn test_apply_entry_fill() {
+        let mut trade = ladder_trade();
+        trade.mark_pending();
+
+        assert_eq!(trade.status, TradeStatus::Pending);
+        assert_eq!(trade.remaining_quantity, Decimal::ZERO);
+
+        // 部分成交后按实际均价激活，止损重新锚定
+        trade.apply_entry_fill(dec!(4.4), dec!(0.4));
+        assert_eq!(trade.status, TradeStatus::Open);
+        assert_eq!(trade.remaining_quantity, dec!(0.4));
+        assert_eq!(trade.stop_loss, dec!(4.18));
+
+        // 继续成交：数量累加，止损不回退
+        trade.apply_entry_fill(dec!(4.42), dec!(1.0));
+        assert_eq!(trade.quantity, dec!(1.0));
+        assert_eq!(trade.remaining_quantity, dec!(1.0));
+        assert_eq!(trade.entry_price, dec!(4.42));
+        assert_eq!(trade.stop_loss, dec!(4.199));
+    }
+
+    #[test]
+    fn test_add_fill_reanchor() {
+        // 先上涨到 4.6，阶梯止损移动到 4.518
+        let mut keep = ladder_trade();
+        keep.track_price(dec!(4.6));
+        let mut tighter = keep.clone();
+        let mut reset = keep.clone();
+
+        keep.add_fill(dec!(4.7), dec!(1.0), ReanchorPolicy::Keep);
+        assert_eq!(keep.entry_price, dec!(4.6));
+        assert_eq!(keep.quantity, dec!(2.0));
+        assert_eq!(keep.remaining_quantity, dec!(2.0));
+        assert_eq!(keep.stop_loss, dec!(4.518));
+
+        // 新均价 4.6 的初始止损 4.37 比当前止损宽，保持不变
+        tighter.add_fill(dec!(4.7), dec!(1.0), ReanchorPolicy::Tighter);
+        assert_eq!(tighter.stop_loss, dec!(4.518));
+
+        // 重置后阶梯从新均价重新开始
+        reset.add_fill(dec!(4.7), dec!(1.0), ReanchorPolicy::Reset);
+        assert_eq!(reset.stop_loss, dec!(4.37));
+        let step = reset.track_price(dec!(4.7));
+        assert_eq!(step.rung.map(|r| r.min), Some(dec!(0.20)));
+    }
+
+    #[test]
+    fn test_stop_hit_enters_closing() {
+        let mut trade = ladder_trade();
+        assert!(!trade.check_exit_conditions(dec!(4.3)));
+        assert_eq!(trade.status, TradeStatus::Open);
+
+        // 触发止损后进入 Closing，不再重复触发，直到平仓任务确认成交
+        assert!(trade.check_exit_conditions(dec!(4.2)));
+        assert_eq!(trade.status, TradeStatus::Closing);
+        assert!(!trade.check_exit_conditions(dec!(4.1)));
+        assert!(!trade.is_closed());
+        assert!(!trade.track_price(dec!(4.1)).stop_hit);
+    }
+
+    #[test]
+    fn test_stop_hit_returns_exchange_stop_to_cancel() {
+        let events = event::EventBus::new();
+        let mut trade = ladder_trade();
+        trade.stop_order = 7;
+        trade.stop_strategy = strategy::build_stop_strategy(
+            &strategy::StopStrategyConfig::ExchangeTrailing {
+                callback_rate: dec!(1),
+                activation_price: None,
+            },
+            vec![],
+            &[],
+        );
+        let book = PriceBook {
+            bid: dec!(4.2),
+            ask: dec!(4.21),
+            ..Default::default()
+        };
+
+        // 托管止损单由平仓任务在锁外撤销，行情处理只返回订单 ID
+        let actions = trade.update_price(&book, &events);
+        assert_eq!(actions.stop, Some(dec!(4.2)));
+        assert_eq!(actions.cancel_stop, Some(7));
+        assert_eq!(trade.status, TradeStatus::Closing);
+    }
+
+    #[tokio::test]
+    async fn test_take_profit_fires_once() {
+        let events = event::EventBus::new();
+        let mut trade = ladder_trade();
+        trade.take_profits = vec![
+            TakeProfit {
+                price: dec!(4.6),
+                fraction: dec!(0.4),
+                is_hit: false,
+                pending: false,
+            },
+            TakeProfit {
+                price: dec!(4.8),
+                fraction: dec!(0.6),
+                is_hit: false,
+                pending: false,
+            },
+        ];
+        let book = PriceBook {
+            bid: dec!(4.62),
+            ask: dec!(4.63),
+            ..Default::default()
+        };
+
+        // 第一个目标到价，下单期间同一目标不再触发
+        let actions = trade.update_price(&book, &events);
+        assert_eq!(
+            actions.take_profits,
+            vec![TakeProfitOrder {
+                index: 0,
+                quantity: dec!(0.4),
+                price: dec!(4.62),
+            }]
+        );
+        assert!(actions.stop.is_none());
+        assert!(trade.due_take_profits(dec!(4.65)).is_empty());
+
+        // 下单失败后恢复目标，再次到价时重新触发
+        trade.release_take_profit(0);
+        let order = trade.due_take_profits(dec!(4.65)).remove(0);
+        let fill = CloseFill {
+            order_id: 2,
+            price: dec!(4.65),
+            trigger_price: dec!(4.65),
+        };
+        trade.settle_take_profit(&order, &fill, &events);
+        assert_eq!(trade.remaining_quantity, dec!(0.6));
+        assert!(trade.take_profits[0].is_hit && !trade.take_profits[0].pending);
+        assert!(trade.due_take_profits(dec!(4.7)).is_empty());
+
+        // 最后一个目标平掉剩余全部数量
+        let last = trade.due_take_profits(dec!(4.8));
+        assert_eq!(last[0].quantity, dec!(0.6));
+    }
+
+    #[test]
+    fn test_reduce_position() {
+        let mut trade = ladder_trade();
+        trade.reduce_position(dec!(0.4));
+        assert_eq!(trade.remaining_quantity, dec!(0.6));
+        assert_eq!(trade.status, TradeStatus::Open);
+
+        // 平掉剩余全部数量后标记为已平仓
+        trade.reduce_position(dec!(0.6));
+        assert_eq!(trade.remaining_quantity, Decimal::ZERO);
+        assert!(trade.is_closed());
+    }
+
+    #[test]
+    fn test_serialize_hides_api_keys() {
+        let mut trade = ladder_trade();
+        trade.api_key = "user-api-key".to_string();
+        trade.api_secret = "user-api-secret".to_string();
+
+        let json = serde_json::to_string(&trade).unwrap();
+        assert!(!json.contains("api_key") && !json.contains("api_secret"));
+        assert!(!json.contains("user-api-key") && !json.contains("user-api-secret"));
+    }
+
+    #[tokio::test]
+    async fn test_close_events() {
+        let events = event::EventBus::new();
+        let mut receiver = events.subscribe();
+        let mut trade = ladder_trade();
+
+        let fill = CloseFill {
+            order_id: 2,
+            price: dec!(4.6),
+            trigger_price: dec!(4.6),
+        };
+        trade.reduce_position(dec!(0.4));
+        events.publish(
+            &trade,
+            trade.close_event(&fill, dec!(0.4), CloseReason::TakeProfit),
+        );
+        trade.reduce_position(dec!(0.6));
+        events.publish(
+            &trade,
+            trade.close_event(&fill, dec!(0.6), CloseReason::Manual),
+        );
+
+        let first = receiver.recv().await.unwrap();
+        assert!(matches!(
+            first.kind,
+            TradeEventKind::PartialClose { remaining_quantity, .. } if remaining_quantity == dec!(0.6)
+        ));
+        let second = receiver.recv().await.unwrap();
+        assert_eq!(second.trade_id, trade.id);
+        assert!(matches!(
+            second.kind,
+            TradeEventKind::Closed { quantity, .. } if quantity == dec!(0.6)
+        ));
+    }
 }
@@ -0,0 +1,190 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set,
+};
+use serde::Serialize;
+use tokio::sync::Mutex;
+
+use super::{
+    event::{TradeEvent, TradeEventKind},
+    sizing::DEFAULT_TAKER_FEE,
+    Trade, TradeDirection,
+};
+use crate::{
+    orm::{paper_accounts, paper_trades},
+    symbol::SymbolMap,
+    utils::parse_decimal,
+};
+
+// 未设置模拟盘账户时的初始虚拟余额（USDT）
+pub const DEFAULT_PAPER_BALANCE: Decimal = dec!(10000);
+
+// 模拟盘账户设置，未保存时使用默认值
+#[derive(Debug, Clone, Serialize)]
+pub struct PaperAccount {
+    pub enabled: bool,
+    pub initial_balance: Decimal,
+    pub fee_rate: Decimal,
+}
+
+impl Default for PaperAccount {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            initial_balance: DEFAULT_PAPER_BALANCE,
+            fee_rate: DEFAULT_TAKER_FEE,
+        }
+    }
+}
+
+impl PaperAccount {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.initial_balance <= Decimal::ZERO {
+            return Err("initial_balance must be positive".to_string());
+        }
+        if self.fee_rate < Decimal::ZERO || self.fee_rate >= dec!(0.01) {
+            return Err("fee_rate must be in [0, 0.01)".to_string());
+        }
+        Ok(())
+    }
+}
+
+pub async fn find_paper_account(
+    db: &DatabaseConnection,
+    owner_id: &str,
+) -> Result<PaperAccount, DbErr> {
+    let Ok(id) = owner_id.parse::<i64>() else {
+        return Ok(PaperAccount::default());
+    };
+    let account = paper_accounts::Entity::find_by_id(id).one(db).await?;
+    Ok(account
+        .map(|m| PaperAccount {
+            enabled: m.enabled,
+            initial_balance: parse_decimal(&m.initial_balance).unwrap_or(DEFAULT_PAPER_BALANCE),
+            fee_rate: parse_decimal(&m.fee_rate).unwrap_or(DEFAULT_TAKER_FEE),
+        })
+        .unwrap_or_default())
+}
+
+// 虚拟余额 = 初始余额 + 全部模拟平仓的净盈亏
+pub async fn paper_balance(
+    db: &DatabaseConnection,
+    owner_id: &str,
+    account: &PaperAccount,
+) -> Result<Decimal, DbErr> {
+    let records = paper_trades::Entity::find()
+        .filter(paper_trades::Column::OwnerId.eq(owner_id))
+        .all(db)
+        .await?;
+    Ok(account.initial_balance + records.iter().map(paper_net_pnl).sum::<Decimal>())
+}
+
+pub fn paper_net_pnl(record: &paper_trades::Model) -> Decimal {
+    parse_decimal(&record.realized_pnl).unwrap_or_default()
+        - parse_decimal(&record.commission).unwrap_or_default()
+}
+
+// 进行中的模拟交易占用的保证金
+pub async fn paper_used_margin(trades: &SymbolMap<Mutex<Vec<Trade>>>, owner_id: &str) -> Decimal {
+    let mut used = Decimal::ZERO;
+    for (_, mutex_vec) in trades.entries() {
+        let vec = mutex_vec.lock().await;
+        used += vec
+            .iter()
+            .filter(|t| t.paper && t.owner_id == owner_id && !t.is_closed())
+            .map(|t| t.remaining_quantity * t.entry_price / t.leverage)
+            .sum::<Decimal>();
+    }
+    used
+}
+
+// 模拟平仓的盈亏和手续费：开平仓各按成交额收取一次 taker 手续费
+pub fn paper_close_costs(
+    direction: &TradeDirection,
+    entry_price: Decimal,
+    close_price: Decimal,
+    quantity: Decimal,
+    fee_rate: Decimal,
+) -> (Decimal, Decimal) {
+    let pnl = match direction {
+        TradeDirection::Long => (close_price - entry_price) * quantity,
+        TradeDirection::Short => (entry_price - close_price) * quantity,
+    };
+    (pnl, (entry_price + close_price) * quantity * fee_rate)
+}
+
+// 每笔模拟平仓成交写入 paper_trades，不查询交易所成交明细
+pub async fn write_paper_record(event: TradeEvent, database: DatabaseConnection) {
+    let (price, quantity, reason) = match &event.kind {
+        TradeEventKind::PartialClose {
+            price,
+            quantity,
+            reason,
+            ..
+        }
+        | TradeEventKind::Closed {
+            price,
+            quantity,
+            reason,
+            ..
+        } => (*price, *quantity, reason.clone()),
+        _ => return,
+    };
+    let account = find_paper_account(&database, &event.owner_id)
+        .await
+        .unwrap_or_default();
+    let (pnl, commission) = paper_close_costs(
+        &event.direction,
+        event.entry_price,
+        price,
+        quantity,
+        account.fee_rate,
+    );
+
+    let record = paper_trades::ActiveModel {
+        trade_id: Set(event.trade_id as i64),
+        owner_id: Set(event.owner_id.clone()),
+        symbol: Set(event.symbol.clone()),
+        entry_price: Set(event.entry_price.to_string()),
+        close_price: Set(price.to_string()),
+        direction: Set(event.direction.to_string()),
+        quantity: Set(quantity.to_string()),
+        leverage: Set(event.leverage.to_string()),
+        close_reason: Set(reason.to_string()),
+        realized_pnl: Set(pnl.to_string()),
+        commission: Set(commission.to_string()),
+        ..Default::default()
+    };
+    if let Err(e) = record.insert(&database).await {
+        eprintln!("模拟平仓记录写入失败，交易 ID {}：{}", event.trade_id, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paper_close_costs() {
+        let (pnl, fee) = paper_close_costs(
+            &TradeDirection::Long,
+            dec!(100),
+            dec!(110),
+            dec!(2),
+            dec!(0.0005),
+        );
+        assert_eq!(pnl, dec!(20));
+        assert_eq!(fee, dec!(0.21));
+
+        let (pnl, fee) = paper_close_costs(
+            &TradeDirection::Short,
+            dec!(100),
+            dec!(110),
+            dec!(2),
+            dec!(0.0005),
+        );
+        assert_eq!(pnl, dec!(-20));
+        assert_eq!(fee, dec!(0.21));
+    }
+}
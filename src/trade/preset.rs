@@ -0,0 +1,88 @@
+use rust_decimal_macros::dec;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    Set,
+};
+
+use super::{validate_adjustments, Adjustment};
+use crate::orm::adjustment_presets;
+
+// 系统预设的固定 ID，兼容旧客户端传入的 adjustment_id 1 / 2
+const SYSTEM_PRESETS: [(i64, &str); 2] = [(1, "default"), (2, "default-alt")];
+
+// 默认阶梯：收益率区间首尾相接
+pub fn default_adjustments() -> Vec<Adjustment> {
+    let rungs = [
+        (dec!(0.10), dec!(0.20), dec!(0.02)),
+        (dec!(0.20), dec!(0.30), dec!(0.04)),
+        (dec!(0.30), dec!(0.40), dec!(0.09)),
+        (dec!(0.40), dec!(0.50), dec!(0.16)),
+        (dec!(0.50), dec!(0.60), dec!(0.25)),
+        (dec!(0.60), dec!(0.70), dec!(0.36)),
+        (dec!(0.70), dec!(0.80), dec!(0.49)),
+        (dec!(0.80), dec!(0.90), dec!(0.64)),
+        (dec!(0.90), dec!(1.00), dec!(0.81)),
+        (dec!(1.00), dec!(1.10), dec!(0.90)),
+    ];
+
+    rungs
+        .iter()
+        .map(|&(min, max, adjustment)| Adjustment {
+            min,
+            max: Some(max),
+            adjustment,
+        })
+        .collect()
+}
+
+// 启动时写入缺失的系统预设
+pub async fn seed_system_presets(db: &DatabaseConnection) -> Result<(), DbErr> {
+    let adjustments =
+        serde_json::to_string(&default_adjustments()).map_err(|e| DbErr::Custom(e.to_string()))?;
+
+    for (id, name) in SYSTEM_PRESETS {
+        if adjustment_presets::Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .is_some()
+        {
+            continue;
+        }
+        adjustment_presets::ActiveModel {
+            id: Set(id),
+            owner_id: Set(None),
+            name: Set(name.to_string()),
+            adjustments: Set(adjustments.clone()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+    }
+    Ok(())
+}
+
+// 用户可见的预设：系统预设或自己的预设
+pub fn visible_to(owner_id: i64) -> Condition {
+    Condition::any()
+        .add(adjustment_presets::Column::OwnerId.is_null())
+        .add(adjustment_presets::Column::OwnerId.eq(owner_id))
+}
+
+pub async fn find_preset(
+    db: &DatabaseConnection,
+    id: i64,
+    owner_id: i64,
+) -> Result<Option<adjustment_presets::Model>, DbErr> {
+    adjustment_presets::Entity::find_by_id(id)
+        .filter(visible_to(owner_id))
+        .one(db)
+        .await
+}
+
+// 解析并校验存储的阶梯配置
+pub fn parse_adjustments(preset: &adjustment_presets::Model) -> Result<Vec<Adjustment>, String> {
+    let adjustments: Vec<Adjustment> = serde_json::from_str(&preset.adjustments)
+        .map_err(|e| format!("Preset {} is corrupted: {}", preset.id, e))?;
+    validate_adjustments(&adjustments)?;
+    Ok(adjustments)
+}
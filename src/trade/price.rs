@@ -0,0 +1,155 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use super::TradeDirection;
+
+// 止损触发价格来源
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub enum PriceSource {
+    // 盘口价：做多取买一，做空取卖一
+    #[default]
+    Book,
+    // 买一卖一中间价
+    Mid,
+    // 最新成交价（aggTrade）
+    Last,
+    // 标记价格
+    Mark,
+}
+
+impl PriceSource {
+    // 交易所条件单的 workingType，需与本地触发价格一致
+    pub fn working_type(&self) -> &'static str {
+        match self {
+            PriceSource::Mark => "MARK_PRICE",
+            _ => "CONTRACT_PRICE",
+        }
+    }
+
+    // 该行情事件是否会改变此来源的价格
+    pub fn follows(&self, event: &PriceEvent) -> bool {
+        matches!(
+            (self, event),
+            (
+                PriceSource::Book | PriceSource::Mid,
+                PriceEvent::Book { .. }
+            ) | (PriceSource::Last, PriceEvent::Last(_))
+                | (PriceSource::Mark, PriceEvent::Mark(_))
+        )
+    }
+}
+
+// 行情流推送的单条价格更新
+#[derive(Debug, Clone)]
+pub enum PriceEvent {
+    Book { ask: Decimal, bid: Decimal },
+    Last(Decimal),
+    Mark(Decimal),
+}
+
+// 每个交易对的最新价格，未收到推送的字段为 0
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PriceBook {
+    pub ask: Decimal,
+    pub bid: Decimal,
+    pub last: Decimal,
+    pub mark: Decimal,
+}
+
+impl PriceBook {
+    pub fn apply(&mut self, event: &PriceEvent) {
+        match *event {
+            PriceEvent::Book { ask, bid } => {
+                self.ask = ask;
+                self.bid = bid;
+            }
+            PriceEvent::Last(price) => self.last = price,
+            PriceEvent::Mark(price) => self.mark = price,
+        }
+    }
+
+    // 按来源和方向取触发价格，尚无有效价格时返回 None
+    pub fn trigger_price(
+        &self,
+        source: PriceSource,
+        direction: &TradeDirection,
+    ) -> Option<Decimal> {
+        let price = match (source, direction) {
+            (PriceSource::Book, TradeDirection::Long) => self.bid,
+            (PriceSource::Book, TradeDirection::Short) => self.ask,
+            (PriceSource::Mid, _) if self.ask > Decimal::ZERO && self.bid > Decimal::ZERO => {
+                (self.ask + self.bid) / Decimal::TWO
+            }
+            (PriceSource::Mid, _) => Decimal::ZERO,
+            (PriceSource::Last, _) => self.last,
+            (PriceSource::Mark, _) => self.mark,
+        };
+        (price > Decimal::ZERO).then_some(price)
+    }
+
+    // 市价开仓的参考价：做多取卖一，做空取买一
+    pub fn entry_price(&self, direction: &TradeDirection) -> Option<Decimal> {
+        let price = match direction {
+            TradeDirection::Long => self.ask,
+            TradeDirection::Short => self.bid,
+        };
+        (price > Decimal::ZERO).then_some(price)
+    }
+
+    // 市价平仓的参考价，与开仓相反
+    pub fn exit_price(&self, direction: &TradeDirection) -> Option<Decimal> {
+        let price = match direction {
+            TradeDirection::Long => self.bid,
+            TradeDirection::Short => self.ask,
+        };
+        (price > Decimal::ZERO).then_some(price)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_trigger_price_by_source() {
+        let mut book = PriceBook::default();
+        assert_eq!(
+            book.trigger_price(PriceSource::Book, &TradeDirection::Long),
+            None
+        );
+
+        book.apply(&PriceEvent::Book {
+            ask: dec!(1.02),
+            bid: dec!(1.00),
+        });
+        book.apply(&PriceEvent::Last(dec!(1.01)));
+
+        let long = TradeDirection::Long;
+        let short = TradeDirection::Short;
+        assert_eq!(
+            book.trigger_price(PriceSource::Book, &long),
+            Some(dec!(1.00))
+        );
+        assert_eq!(
+            book.trigger_price(PriceSource::Book, &short),
+            Some(dec!(1.02))
+        );
+        assert_eq!(
+            book.trigger_price(PriceSource::Mid, &long),
+            Some(dec!(1.01))
+        );
+        assert_eq!(
+            book.trigger_price(PriceSource::Last, &short),
+            Some(dec!(1.01))
+        );
+        // 尚未收到标记价格
+        assert_eq!(book.trigger_price(PriceSource::Mark, &long), None);
+
+        assert!(PriceSource::Mid.follows(&PriceEvent::Book {
+            ask: dec!(1),
+            bid: dec!(1)
+        }));
+        assert!(!PriceSource::Mark.follows(&PriceEvent::Last(dec!(1))));
+    }
+}
@@ -0,0 +1,304 @@
+use std::sync::Arc;
+
+use rust_decimal::Decimal;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use tokio::{
+    sync::broadcast::{self, error::RecvError},
+    time::{sleep, Duration},
+};
+
+use super::{
+    event::{TradeEvent, TradeEventKind},
+    paper::write_paper_record,
+    TradeDirection,
+};
+use crate::{
+    binance::record_api::get_order_record_api,
+    models::record_model::TradeRecord,
+    orm::{trade_legs, trades},
+    secret_key::{KeyManager, SecretKey},
+    utils::parse_decimal,
+};
+
+// 市价单成交明细可能稍有延迟，查询为空时重试
+const FILL_FETCH_ATTEMPTS: u32 = 3;
+const FILL_FETCH_DELAY: Duration = Duration::from_secs(1);
+
+// 一个或多个订单的成交明细汇总
+#[derive(Debug, Default, PartialEq)]
+pub struct FillSummary {
+    pub quantity: Decimal,
+    pub avg_price: Decimal,
+    pub realized_pnl: Decimal,
+    pub commission: Decimal,      // 只累计与第一笔成交相同资产的手续费
+    pub commission_asset: String, // 无成交时为空
+}
+
+pub fn summarize_fills(fills: &[TradeRecord]) -> FillSummary {
+    let mut summary = FillSummary {
+        commission_asset: fills
+            .first()
+            .map(|f| f.commission_asset.clone())
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+    let mut notional = Decimal::ZERO;
+    for fill in fills {
+        let qty = parse_decimal(&fill.qty).unwrap_or_default();
+        summary.quantity += qty;
+        notional += parse_decimal(&fill.price).unwrap_or_default() * qty;
+        summary.realized_pnl += parse_decimal(&fill.realized_pnl).unwrap_or_default();
+        if fill.commission_asset == summary.commission_asset {
+            summary.commission += parse_decimal(&fill.commission).unwrap_or_default();
+        }
+    }
+    if summary.quantity > Decimal::ZERO {
+        summary.avg_price = notional / summary.quantity;
+    }
+    summary
+}
+
+// 平仓滑点成本：做多平仓为卖出，成交价低于触发价为不利；做空相反
+pub fn slippage_cost(
+    direction: &TradeDirection,
+    trigger_price: Decimal,
+    fill_price: Decimal,
+    quantity: Decimal,
+) -> Decimal {
+    match direction {
+        TradeDirection::Long => (trigger_price - fill_price) * quantity,
+        TradeDirection::Short => (fill_price - trigger_price) * quantity,
+    }
+}
+
+// 平仓成本，成交明细不可用时整体为空
+struct CloseCosts {
+    realized_pnl: Decimal,
+    commission: Decimal,
+    commission_asset: String,
+    slippage: Decimal,
+}
+
+// 持久化订阅者：每笔平仓成交（部分或全部）写入一条历史记录
+pub async fn record_closes(
+    mut receiver: broadcast::Receiver<TradeEvent>,
+    database: DatabaseConnection,
+    api_keys: Arc<KeyManager>,
+) {
+    loop {
+        let event = match receiver.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                eprintln!("交易记录订阅落后，丢失 {} 个事件", skipped);
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+        if matches!(
+            event.kind,
+            TradeEventKind::PartialClose { .. } | TradeEventKind::Closed { .. }
+        ) {
+            // 模拟盘成交单独记录
+            if event.paper {
+                tokio::spawn(write_paper_record(event, database.clone()));
+                continue;
+            }
+            // 查询成交明细较慢，每条记录独立写入
+            tokio::spawn(write_close_record(
+                event,
+                database.clone(),
+                api_keys.clone(),
+            ));
+        }
+    }
+}
+
+async fn write_close_record(
+    event: TradeEvent,
+    database: DatabaseConnection,
+    api_keys: Arc<KeyManager>,
+) {
+    let (order_id, price, trigger_price, quantity, reason) = match &event.kind {
+        TradeEventKind::PartialClose {
+            order_id,
+            price,
+            trigger_price,
+            quantity,
+            reason,
+            ..
+        }
+        | TradeEventKind::Closed {
+            order_id,
+            price,
+            trigger_price,
+            quantity,
+            reason,
+        } => (*order_id, *price, *trigger_price, *quantity, reason.clone()),
+        _ => return,
+    };
+
+    let costs = match api_keys.get_key(&event.owner_id) {
+        Some(key) => {
+            load_close_costs(&database, &event, order_id, trigger_price, quantity, &key).await
+        }
+        None => None,
+    };
+    if costs.is_none() {
+        eprintln!(
+            "平仓成交明细不可用，交易 ID {} 的盈亏和手续费留空",
+            event.trade_id
+        );
+    }
+
+    let record = trades::ActiveModel {
+        trade_id: Set(event.trade_id as i64),
+        owner_id: Set(event.owner_id.clone()),
+        symbol: Set(event.symbol.clone()),
+        entry_price: Set(event.entry_price.to_string()),
+        close_price: Set(price.to_string()),
+        direction: Set(event.direction.to_string()),
+        quantity: Set(quantity.to_string()),
+        leverage: Set(event.leverage.to_string()),
+        close_reason: Set(reason.to_string()),
+        trigger_price: Set(trigger_price.to_string()),
+        realized_pnl: Set(costs.as_ref().map(|c| c.realized_pnl.to_string())),
+        commission: Set(costs.as_ref().map(|c| c.commission.to_string())),
+        commission_asset: Set(costs.as_ref().map(|c| c.commission_asset.clone())),
+        slippage: Set(costs.as_ref().map(|c| c.slippage.to_string())),
+        opened_at: Set(Some(event.opened_at)),
+        adjustment_id: Set(event.adjustment_id),
+        ..Default::default()
+    };
+    if let Err(e) = record.insert(&database).await {
+        eprintln!("平仓记录写入失败，交易 ID {}：{}", event.trade_id, e);
+    }
+}
+
+// 按 userTrades 成交明细计算本次平仓的盈亏、手续费和滑点
+async fn load_close_costs(
+    database: &DatabaseConnection,
+    event: &TradeEvent,
+    order_id: u64,
+    trigger_price: Decimal,
+    quantity: Decimal,
+    key: &SecretKey,
+) -> Option<CloseCosts> {
+    let exit = summarize_fills(&fetch_fills(&event.symbol, order_id, key).await?);
+
+    // 开仓单和所有加仓单
+    let legs = trade_legs::Entity::find()
+        .filter(trade_legs::Column::TradeId.eq(event.trade_id as i64))
+        .filter(trade_legs::Column::OwnerId.eq(event.owner_id.as_str()))
+        .filter(trade_legs::Column::Symbol.eq(event.symbol.as_str()))
+        .all(database)
+        .await
+        .unwrap_or_default();
+    let mut entry_fills = Vec::new();
+    for id in std::iter::once(event.entry_order_id).chain(legs.iter().map(|l| l.order_id as u64)) {
+        entry_fills.extend(
+            fetch_fills(&event.symbol, id, key)
+                .await
+                .unwrap_or_default(),
+        );
+    }
+    let entry = summarize_fills(&entry_fills);
+
+    // 开仓手续费按本次平仓数量分摊，资产不同时无法合并
+    let entry_commission =
+        if entry.quantity > Decimal::ZERO && entry.commission_asset == exit.commission_asset {
+            entry.commission * (quantity / entry.quantity).min(Decimal::ONE)
+        } else {
+            Decimal::ZERO
+        };
+
+    Some(CloseCosts {
+        realized_pnl: exit.realized_pnl,
+        commission: exit.commission + entry_commission,
+        commission_asset: exit.commission_asset,
+        slippage: slippage_cost(
+            &event.direction,
+            trigger_price,
+            exit.avg_price,
+            exit.quantity,
+        ),
+    })
+}
+
+async fn fetch_fills(symbol: &str, order_id: u64, key: &SecretKey) -> Option<Vec<TradeRecord>> {
+    for attempt in 1..=FILL_FETCH_ATTEMPTS {
+        match get_order_record_api(
+            &symbol.to_uppercase(),
+            order_id,
+            &key.api_key,
+            &key.api_secret,
+        )
+        .await
+        {
+            Ok(fills) if !fills.is_empty() => return Some(fills),
+            _ if attempt < FILL_FETCH_ATTEMPTS => sleep(FILL_FETCH_DELAY).await,
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn fill(price: &str, qty: &str, pnl: &str, commission: &str, asset: &str) -> TradeRecord {
+        TradeRecord {
+            buyer: false,
+            commission: commission.to_string(),
+            commission_asset: asset.to_string(),
+            id: 1,
+            maker: false,
+            order_id: 1,
+            price: price.to_string(),
+            qty: qty.to_string(),
+            quote_qty: "0".to_string(),
+            realized_pnl: pnl.to_string(),
+            side: "SELL".to_string(),
+            position_side: "LONG".to_string(),
+            symbol: "FILUSDT".to_string(),
+            time: 0,
+        }
+    }
+
+    #[test]
+    fn test_summarize_fills_and_slippage() {
+        let fills = vec![
+            fill("4.50", "6", "1.2", "0.0108", "USDT"),
+            fill("4.40", "4", "0.4", "0.0070", "USDT"),
+            fill("4.40", "0", "0", "0.0001", "BNB"),
+        ];
+        let summary = summarize_fills(&fills);
+        assert_eq!(summary.quantity, dec!(10));
+        assert_eq!(summary.avg_price, dec!(4.46));
+        assert_eq!(summary.realized_pnl, dec!(1.6));
+        assert_eq!(summary.commission, dec!(0.0178));
+        assert_eq!(summary.commission_asset, "USDT");
+
+        // 做多触发价 4.5，成交均价 4.46，不利滑点 0.04 * 10
+        assert_eq!(
+            slippage_cost(
+                &TradeDirection::Long,
+                dec!(4.5),
+                summary.avg_price,
+                dec!(10)
+            ),
+            dec!(0.4)
+        );
+        assert_eq!(
+            slippage_cost(
+                &TradeDirection::Short,
+                dec!(4.5),
+                summary.avg_price,
+                dec!(10)
+            ),
+            dec!(-0.4)
+        );
+        assert_eq!(summarize_fills(&[]), FillSummary::default());
+    }
+}
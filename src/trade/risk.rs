@@ -0,0 +1,311 @@
+use rust_decimal::Decimal;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use super::{Trade, TradeDirection};
+use crate::{
+    grid::Grids,
+    orm::{risk_policies, trades},
+    symbol::SymbolMap,
+    utils::parse_decimal,
+};
+
+// 计算连续亏损时最多回看的平仓记录数
+const LOSS_STREAK_LOOKBACK: u64 = 200;
+
+// 用户级风控策略，字段为空表示不限制
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RiskPolicy {
+    pub max_open_trades: Option<usize>,       // 同时进行中的交易数
+    pub max_symbol_notional: Option<Decimal>, // 单个交易对的持仓名义价值
+    pub max_total_notional: Option<Decimal>,  // 全部持仓名义价值
+    pub max_leverage: Option<Decimal>,        // 最高杠杆
+    pub daily_loss_limit: Option<Decimal>,    // 当日（UTC）已实现亏损上限，正数
+    pub max_consecutive_losses: Option<u32>,  // 连续亏损笔数达到后进入冷却
+    pub cooldown_secs: Option<u32>,           // 冷却时长
+}
+
+impl RiskPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        let positive = [
+            self.max_symbol_notional,
+            self.max_total_notional,
+            self.daily_loss_limit,
+        ];
+        if positive.iter().flatten().any(|v| *v <= Decimal::ZERO) {
+            return Err("Notional and loss limits must be positive".to_string());
+        }
+        if self.max_leverage.is_some_and(|l| l < Decimal::ONE) {
+            return Err("max_leverage must be >= 1".to_string());
+        }
+        if self.max_open_trades == Some(0) || self.max_consecutive_losses == Some(0) {
+            return Err("Count limits must be positive".to_string());
+        }
+        if self.max_consecutive_losses.is_some() != self.cooldown_secs.is_some() {
+            return Err(
+                "max_consecutive_losses and cooldown_secs must be set together".to_string(),
+            );
+        }
+        Ok(())
+    }
+}
+
+// 用户当前的持仓暴露
+#[derive(Debug, Default)]
+pub struct Exposure {
+    pub open_trades: usize,
+    pub symbol_notional: Decimal,
+    pub total_notional: Decimal,
+}
+
+// 统计用户未结束的交易和网格；未成交的开仓单计入笔数，名义价值按已成交数量计算，
+// 每个网格计为一笔，名义价值为网格当前持仓
+pub async fn user_exposure(
+    trades: &SymbolMap<Mutex<Vec<Trade>>>,
+    grids: &Grids,
+    user_id: &str,
+    symbol: &str,
+) -> Exposure {
+    let mut exposure = Exposure::default();
+    for (trade_symbol, mutex_vec) in trades.entries() {
+        let vec = mutex_vec.lock().await;
+        for t in vec
+            .iter()
+            .filter(|t| !t.paper && t.owner_id == user_id && !t.is_closed())
+        {
+            let notional = t.remaining_quantity * t.entry_price;
+            exposure.open_trades += 1;
+            exposure.total_notional += notional;
+            if trade_symbol == symbol {
+                exposure.symbol_notional += notional;
+            }
+        }
+    }
+    for bot in grids.owned_by(user_id).await {
+        let bot = bot.lock().await;
+        let notional = bot.notional();
+        exposure.open_trades += 1;
+        exposure.total_notional += notional;
+        if bot.symbol == symbol {
+            exposure.symbol_notional += notional;
+        }
+    }
+    exposure
+}
+
+// 平仓记录的净盈亏：优先使用交易所已实现盈亏扣除 USDT 手续费，缺失时按价格估算
+pub fn record_net_pnl(record: &trades::Model) -> Decimal {
+    let realized = record.realized_pnl.as_deref().and_then(parse_decimal);
+    match realized {
+        Some(pnl) => {
+            let commission = match record.commission_asset.as_deref() {
+                Some("USDT") => record
+                    .commission
+                    .as_deref()
+                    .and_then(parse_decimal)
+                    .unwrap_or_default(),
+                _ => Decimal::ZERO,
+            };
+            pnl - commission
+        }
+        None => {
+            let entry = parse_decimal(&record.entry_price).unwrap_or_default();
+            let close = parse_decimal(&record.close_price).unwrap_or_default();
+            let quantity = parse_decimal(&record.quantity).unwrap_or_default();
+            if record.direction == TradeDirection::Short.to_string() {
+                (entry - close) * quantity
+            } else {
+                (close - entry) * quantity
+            }
+        }
+    }
+}
+
+// 最近连续亏损的交易笔数和最后一笔亏损时间；records 按时间倒序，同一交易的分批平仓合并计算
+pub fn consecutive_losses(records: &[trades::Model]) -> (u32, Option<u32>) {
+    let mut losses = 0;
+    let mut last_loss_at = None;
+    for group in records.chunk_by(|a, b| a.trade_id == b.trade_id) {
+        let pnl: Decimal = group.iter().map(record_net_pnl).sum();
+        if pnl >= Decimal::ZERO {
+            break;
+        }
+        losses += 1;
+        last_loss_at.get_or_insert(group[0].created_at);
+    }
+    (losses, last_loss_at)
+}
+
+// 开仓前的账户级检查：杠杆、当日亏损和连续亏损冷却
+pub fn check_entry(
+    policy: &RiskPolicy,
+    leverage: Decimal,
+    daily_pnl: Decimal,
+    losses: (u32, Option<u32>),
+    now: u32,
+) -> Result<(), String> {
+    if let Some(max) = policy.max_leverage {
+        if leverage > max {
+            return Err(format!("Leverage {} exceeds limit {}", leverage, max));
+        }
+    }
+    if let Some(limit) = policy.daily_loss_limit {
+        if -daily_pnl >= limit {
+            return Err(format!(
+                "Daily loss {} reached limit {}",
+                (-daily_pnl).round_dp(2),
+                limit
+            ));
+        }
+    }
+    if let (Some(max), Some(cooldown)) = (policy.max_consecutive_losses, policy.cooldown_secs) {
+        let (count, last_loss_at) = losses;
+        let until = last_loss_at.unwrap_or_default().saturating_add(cooldown);
+        if count >= max && now < until {
+            return Err(format!(
+                "{} consecutive losses, cooling down for {}s",
+                count,
+                until - now
+            ));
+        }
+    }
+    Ok(())
+}
+
+// 按新交易的名义价值检查持仓笔数和名义价值上限
+pub fn check_exposure(
+    policy: &RiskPolicy,
+    exposure: &Exposure,
+    notional: Decimal,
+) -> Result<(), String> {
+    if let Some(max) = policy.max_open_trades {
+        if exposure.open_trades >= max {
+            return Err(format!("Open trades limit {} reached", max));
+        }
+    }
+    if let Some(max) = policy.max_symbol_notional {
+        if exposure.symbol_notional + notional > max {
+            return Err(format!(
+                "Symbol notional {} exceeds limit {}",
+                (exposure.symbol_notional + notional).round_dp(2),
+                max
+            ));
+        }
+    }
+    if let Some(max) = policy.max_total_notional {
+        if exposure.total_notional + notional > max {
+            return Err(format!(
+                "Total notional {} exceeds limit {}",
+                (exposure.total_notional + notional).round_dp(2),
+                max
+            ));
+        }
+    }
+    Ok(())
+}
+
+pub async fn find_policy(
+    db: &DatabaseConnection,
+    owner_id: i64,
+) -> Result<Option<risk_policies::Model>, DbErr> {
+    risk_policies::Entity::find_by_id(owner_id).one(db).await
+}
+
+pub fn parse_policy(model: &risk_policies::Model) -> Result<RiskPolicy, String> {
+    serde_json::from_str(&model.policy).map_err(|e| format!("Invalid risk policy: {}", e))
+}
+
+// 用户自 since 起的平仓记录，按时间倒序
+pub async fn closed_records_since(
+    db: &DatabaseConnection,
+    user_id: &str,
+    since: u32,
+) -> Result<Vec<trades::Model>, DbErr> {
+    trades::Entity::find()
+        .filter(trades::Column::OwnerId.eq(user_id))
+        .filter(trades::Column::CreatedAt.gte(since))
+        .order_by_desc(trades::Column::CreatedAt)
+        .order_by_desc(trades::Column::Id)
+        .all(db)
+        .await
+}
+
+// 计算连续亏损用的最近平仓记录，按时间倒序
+pub async fn recent_closed_records(
+    db: &DatabaseConnection,
+    user_id: &str,
+) -> Result<Vec<trades::Model>, DbErr> {
+    trades::Entity::find()
+        .filter(trades::Column::OwnerId.eq(user_id))
+        .order_by_desc(trades::Column::CreatedAt)
+        .order_by_desc(trades::Column::Id)
+        .limit(LOSS_STREAK_LOOKBACK)
+        .all(db)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn record(trade_id: i64, entry: &str, close: &str, created_at: u32) -> trades::Model {
+        trades::Model {
+            id: 0,
+            trade_id,
+            owner_id: "1".to_string(),
+            symbol: "filusdt".to_string(),
+            entry_price: entry.to_string(),
+            close_price: close.to_string(),
+            direction: "Long".to_string(),
+            quantity: "1".to_string(),
+            leverage: "10".to_string(),
+            close_reason: "StopLoss".to_string(),
+            trigger_price: "0".to_string(),
+            realized_pnl: None,
+            commission: None,
+            commission_asset: None,
+            slippage: None,
+            opened_at: None,
+            adjustment_id: None,
+            created_at,
+        }
+    }
+
+    #[test]
+    fn test_entry_checks() {
+        let policy = RiskPolicy {
+            max_leverage: Some(dec!(20)),
+            daily_loss_limit: Some(dec!(50)),
+            max_consecutive_losses: Some(2),
+            cooldown_secs: Some(600),
+            max_open_trades: Some(2),
+            ..Default::default()
+        };
+        assert!(policy.validate().is_ok());
+
+        // 倒序：交易 3 的两笔分批平仓合计亏损，交易 2 亏损，交易 1 盈利
+        let records = vec![
+            record(3, "5", "4.8", 1000),
+            record(3, "5", "5.1", 990),
+            record(2, "5", "4.9", 900),
+            record(1, "5", "5.5", 800),
+        ];
+        let losses = consecutive_losses(&records);
+        assert_eq!(losses, (2, Some(1000)));
+
+        assert!(check_entry(&policy, dec!(25), Decimal::ZERO, (0, None), 0).is_err());
+        assert!(check_entry(&policy, dec!(10), dec!(-50), (0, None), 0).is_err());
+        assert!(check_entry(&policy, dec!(10), dec!(-10), losses, 1500).is_err());
+        assert!(check_entry(&policy, dec!(10), dec!(-10), losses, 1600).is_ok());
+
+        let exposure = Exposure {
+            open_trades: 2,
+            ..Default::default()
+        };
+        assert!(check_exposure(&policy, &exposure, dec!(100)).is_err());
+    }
+}
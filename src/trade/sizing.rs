@@ -0,0 +1,140 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use super::{calculate_stop_price, round_stop_price, TradeDirection};
+use crate::{binance::leverage::SymbolFilter, utils::round_to_step};
+
+// 查询不到用户费率时使用的 taker 手续费率
+pub const DEFAULT_TAKER_FEE: Decimal = dec!(0.0005);
+
+// 按风险计算仓位所需的账户数据，开仓前从交易所读取
+#[derive(Debug, Clone)]
+pub struct RiskContext {
+    pub equity: Decimal,               // 账户权益
+    pub available_balance: Decimal,    // 可用余额
+    pub fee_rate: Decimal,             // taker 手续费率
+    pub max_notional: Option<Decimal>, // 当前杠杆分层允许的最大名义价值
+}
+
+// 止损成交时的单位亏损：开仓价到止损价的距离加上开平仓手续费
+pub fn loss_per_unit(
+    direction: &TradeDirection,
+    price: Decimal,
+    leverage: Decimal,
+    stop_loss_percent: Decimal,
+    fee_rate: Decimal,
+    tick_size: Decimal,
+) -> Decimal {
+    let stop = round_stop_price(
+        direction,
+        calculate_stop_price(direction, price, leverage, stop_loss_percent),
+        tick_size,
+    );
+    (price - stop).abs() + fee_rate * (price + stop)
+}
+
+// 按止损风险计算数量，止损时亏损不超过 risk，按数量步长向下取整
+pub fn risk_quantity(
+    direction: &TradeDirection,
+    price: Decimal,
+    leverage: Decimal,
+    stop_loss_percent: Decimal,
+    risk: Decimal,
+    fee_rate: Decimal,
+    filter: &SymbolFilter,
+) -> Decimal {
+    let loss = loss_per_unit(
+        direction,
+        price,
+        leverage,
+        stop_loss_percent,
+        fee_rate,
+        filter.tick_size,
+    );
+    if loss <= Decimal::ZERO || risk <= Decimal::ZERO {
+        return Decimal::ZERO;
+    }
+    round_to_step(risk / loss, filter.step_size)
+}
+
+// 校验按风险算出的仓位：所需杠杆不超过请求杠杆，名义价值不超过分层和市价单上限
+pub fn check_risk_limits(
+    quantity: Decimal,
+    price: Decimal,
+    leverage: Decimal,
+    context: &RiskContext,
+    filter: &SymbolFilter,
+) -> Result<(), String> {
+    let notional = quantity * price;
+    if context.available_balance <= Decimal::ZERO {
+        return Err("No available balance".to_string());
+    }
+    let required_leverage = notional / context.available_balance;
+    if required_leverage > leverage {
+        return Err(format!(
+            "Required leverage {} exceeds {}",
+            required_leverage.round_dp(2),
+            leverage
+        ));
+    }
+    if let Some(max_notional) = context.max_notional {
+        if notional > max_notional {
+            return Err(format!(
+                "Notional {} exceeds limit {} at {}x",
+                notional.round_dp(2),
+                max_notional,
+                leverage
+            ));
+        }
+    }
+    if filter.max_market_qty > Decimal::ZERO && quantity > filter.max_market_qty {
+        return Err(format!(
+            "Quantity {} exceeds market order limit {}",
+            quantity, filter.max_market_qty
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_risk_quantity_and_limits() {
+        let filter = SymbolFilter {
+            tick_size: dec!(0.001),
+            step_size: dec!(0.1),
+            max_market_qty: dec!(1000),
+            ..Default::default()
+        };
+        // 入场 5，10 倍杠杆，止损比例 0.5：止损价 4.75，单位亏损 0.25 + 0.0005 * 9.75
+        let long = TradeDirection::Long;
+        let quantity = risk_quantity(
+            &long,
+            dec!(5),
+            dec!(10),
+            dec!(0.5),
+            dec!(10),
+            dec!(0.0005),
+            &filter,
+        );
+        assert_eq!(quantity, dec!(39.2));
+
+        let mut context = RiskContext {
+            equity: dec!(100),
+            available_balance: dec!(100),
+            fee_rate: dec!(0.0005),
+            max_notional: Some(dec!(50000)),
+        };
+        // 名义价值 196，所需杠杆 1.96
+        assert!(check_risk_limits(quantity, dec!(5), dec!(10), &context, &filter).is_ok());
+
+        context.available_balance = dec!(10);
+        assert!(check_risk_limits(quantity, dec!(5), dec!(10), &context, &filter).is_err());
+
+        context.available_balance = dec!(100);
+        context.max_notional = Some(dec!(150));
+        assert!(check_risk_limits(quantity, dec!(5), dec!(10), &context, &filter).is_err());
+    }
+}
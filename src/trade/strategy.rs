@@ -0,0 +1,538 @@
+use std::fmt;
+
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize, Serializer};
+
+use super::{get_adjustment, Adjustment, TradeDirection};
+use crate::binance::market::Kline;
+
+// 计算止损时的交易快照
+pub struct StopContext<'a> {
+    pub direction: &'a TradeDirection,
+    pub entry_price: Decimal,
+    pub highest_price: Decimal,
+    pub lowest_price: Decimal,
+    pub leverage: Decimal,
+    pub stop_loss: Decimal,
+    pub profit_percentage: Decimal, // 未乘杠杆的价格变动比例
+}
+
+impl StopContext<'_> {
+    // 当前极值价格：做多取最高价，做空取最低价
+    fn extreme_price(&self) -> Decimal {
+        match self.direction {
+            TradeDirection::Long => self.highest_price,
+            TradeDirection::Short => self.lowest_price,
+        }
+    }
+
+    // 只允许止损向有利方向移动
+    fn tighten(&self, candidate: Decimal) -> Option<Decimal> {
+        let better = match self.direction {
+            TradeDirection::Long => candidate > self.stop_loss,
+            TradeDirection::Short => candidate < self.stop_loss,
+        };
+        better.then_some(candidate)
+    }
+}
+
+// 止损策略：价格创新高（做多）或新低（做空）时计算新的止损价
+pub trait StopStrategy: fmt::Debug + Send + Sync {
+    // 返回新的止损价，None 表示保持不变
+    fn next_stop(&mut self, ctx: &StopContext) -> Option<Decimal>;
+
+    // 止损是否由交易所托管，托管时本地只保留初始保护止损
+    fn is_exchange_managed(&self) -> bool {
+        false
+    }
+
+    // 最近一次计算命中的阶梯档位，仅阶梯策略提供
+    fn fired_rung(&self) -> Option<Adjustment> {
+        None
+    }
+
+    // 重新锚定止损时恢复初始状态
+    fn reset(&mut self) {}
+
+    // 策略依赖的 K 线周期，实盘据此拉取收盘 K 线
+    fn kline_interval(&self) -> Option<&str> {
+        None
+    }
+
+    // K 线收盘时更新策略状态，之后的 next_stop 使用新数据
+    fn on_kline_close(&mut self, _kline: &Kline) {}
+
+    // 当前策略的参数，用于接口展示
+    fn config(&self) -> StopStrategyConfig;
+
+    fn box_clone(&self) -> Box<dyn StopStrategy>;
+}
+
+impl Clone for Box<dyn StopStrategy> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
+}
+
+#[allow(clippy::borrowed_box)]
+pub fn serialize_strategy<S: Serializer>(
+    strategy: &Box<dyn StopStrategy>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    strategy.config().serialize(serializer)
+}
+
+// 接口中选择止损策略及其参数，百分比均为乘杠杆后的收益率（与 stop_loss_percent 一致）
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub enum StopStrategyConfig {
+    // 阶梯调整，使用 adjustment_id 对应的配置
+    #[default]
+    Ladder,
+    // 与极值价格保持固定距离
+    FixedTrail {
+        percent: Decimal,
+    },
+    // 收益达到 trigger 后将止损移动到开仓价 + offset
+    BreakEven {
+        trigger: Decimal,
+        offset: Decimal,
+    },
+    // 吊灯止损：极值价格 -/+ multiplier * ATR，ATR 在每根 interval 周期的 K 线收盘时更新
+    Chandelier {
+        interval: String,
+        period: usize,
+        multiplier: Decimal,
+    },
+    // 币安原生 TRAILING_STOP_MARKET，callback_rate 为价格回调百分比
+    ExchangeTrailing {
+        callback_rate: Decimal,
+        activation_price: Option<Decimal>,
+    },
+}
+
+impl StopStrategyConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            StopStrategyConfig::Ladder => Ok(()),
+            StopStrategyConfig::FixedTrail { percent } if *percent > Decimal::ZERO => Ok(()),
+            StopStrategyConfig::FixedTrail { .. } => {
+                Err("FixedTrail percent must be positive".to_string())
+            }
+            StopStrategyConfig::BreakEven { trigger, offset }
+                if *trigger > Decimal::ZERO && *offset >= Decimal::ZERO && offset < trigger =>
+            {
+                Ok(())
+            }
+            StopStrategyConfig::BreakEven { .. } => {
+                Err("BreakEven requires trigger > offset >= 0".to_string())
+            }
+            StopStrategyConfig::Chandelier {
+                period, multiplier, ..
+            } if *period > 0 && *multiplier > Decimal::ZERO => Ok(()),
+            StopStrategyConfig::Chandelier { .. } => {
+                Err("Chandelier requires period > 0 and multiplier > 0".to_string())
+            }
+            StopStrategyConfig::ExchangeTrailing { callback_rate, .. }
+                if (dec!(0.1)..=dec!(10)).contains(callback_rate) =>
+            {
+                Ok(())
+            }
+            StopStrategyConfig::ExchangeTrailing { .. } => {
+                Err("ExchangeTrailing callback_rate must be within [0.1, 10]".to_string())
+            }
+        }
+    }
+}
+
+// 阶梯调整策略（原有逻辑）
+#[derive(Debug, Clone)]
+pub struct LadderStop {
+    adjustments: Vec<Adjustment>,
+    initial: Vec<Adjustment>, // 未被移除档位的原始配置
+    fired: Option<Adjustment>,
+}
+
+impl LadderStop {
+    pub fn new(adjustments: Vec<Adjustment>) -> Self {
+        Self {
+            initial: adjustments.clone(),
+            adjustments,
+            fired: None,
+        }
+    }
+}
+
+impl StopStrategy for LadderStop {
+    fn next_stop(&mut self, ctx: &StopContext) -> Option<Decimal> {
+        let actual_price_change_percentage = ctx.profit_percentage * ctx.leverage;
+
+        self.fired = get_adjustment(actual_price_change_percentage, &mut self.adjustments)
+            .filter(|rung| !rung.adjustment.is_zero());
+        let adjustment = self.fired.as_ref()?.adjustment;
+
+        // 收益超过 109% 后改为从极值价格回撤
+        let stop = match ctx.direction {
+            TradeDirection::Long if actual_price_change_percentage >= dec!(1.09) => {
+                ctx.highest_price * (Decimal::ONE - adjustment / ctx.leverage)
+            }
+            TradeDirection::Long => ctx.entry_price * (Decimal::ONE + adjustment / ctx.leverage),
+            TradeDirection::Short if actual_price_change_percentage >= dec!(1.09) => {
+                ctx.lowest_price * (Decimal::ONE + adjustment / ctx.leverage)
+            }
+            TradeDirection::Short => ctx.entry_price * (Decimal::ONE - adjustment / ctx.leverage),
+        };
+        Some(stop)
+    }
+
+    fn fired_rung(&self) -> Option<Adjustment> {
+        self.fired.clone()
+    }
+
+    fn reset(&mut self) {
+        self.adjustments = self.initial.clone();
+        self.fired = None;
+    }
+
+    fn config(&self) -> StopStrategyConfig {
+        StopStrategyConfig::Ladder
+    }
+
+    fn box_clone(&self) -> Box<dyn StopStrategy> {
+        Box::new(self.clone())
+    }
+}
+
+// 固定比例追踪止损
+#[derive(Debug, Clone)]
+pub struct FixedTrailStop {
+    percent: Decimal,
+}
+
+impl StopStrategy for FixedTrailStop {
+    fn next_stop(&mut self, ctx: &StopContext) -> Option<Decimal> {
+        let distance = self.percent / ctx.leverage;
+        let candidate = match ctx.direction {
+            TradeDirection::Long => ctx.extreme_price() * (Decimal::ONE - distance),
+            TradeDirection::Short => ctx.extreme_price() * (Decimal::ONE + distance),
+        };
+        ctx.tighten(candidate)
+    }
+
+    fn config(&self) -> StopStrategyConfig {
+        StopStrategyConfig::FixedTrail {
+            percent: self.percent,
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn StopStrategy> {
+        Box::new(self.clone())
+    }
+}
+
+// 保本止损：只移动一次
+#[derive(Debug, Clone)]
+pub struct BreakEvenStop {
+    trigger: Decimal,
+    offset: Decimal,
+}
+
+impl StopStrategy for BreakEvenStop {
+    fn next_stop(&mut self, ctx: &StopContext) -> Option<Decimal> {
+        if ctx.profit_percentage * ctx.leverage < self.trigger {
+            return None;
+        }
+        let offset = self.offset / ctx.leverage;
+        let candidate = match ctx.direction {
+            TradeDirection::Long => ctx.entry_price * (Decimal::ONE + offset),
+            TradeDirection::Short => ctx.entry_price * (Decimal::ONE - offset),
+        };
+        ctx.tighten(candidate)
+    }
+
+    fn config(&self) -> StopStrategyConfig {
+        StopStrategyConfig::BreakEven {
+            trigger: self.trigger,
+            offset: self.offset,
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn StopStrategy> {
+        Box::new(self.clone())
+    }
+}
+
+// 吊灯止损：开仓时由 K 线计算 ATR，之后每根收盘 K 线按 Wilder 平滑更新
+#[derive(Debug, Clone)]
+pub struct ChandelierStop {
+    interval: String,
+    period: usize,
+    multiplier: Decimal,
+    atr: Decimal,
+    last: Option<Kline>, // 最近一根已计入的 K 线
+}
+
+impl ChandelierStop {
+    pub fn new(interval: String, period: usize, multiplier: Decimal, klines: &[Kline]) -> Self {
+        Self {
+            interval,
+            period,
+            multiplier,
+            atr: average_true_range(klines, period),
+            last: klines.last().cloned(),
+        }
+    }
+}
+
+impl StopStrategy for ChandelierStop {
+    fn next_stop(&mut self, ctx: &StopContext) -> Option<Decimal> {
+        if self.atr <= Decimal::ZERO {
+            return None;
+        }
+        let distance = self.multiplier * self.atr;
+        let candidate = match ctx.direction {
+            TradeDirection::Long => ctx.extreme_price() - distance,
+            TradeDirection::Short => ctx.extreme_price() + distance,
+        };
+        ctx.tighten(candidate)
+    }
+
+    fn kline_interval(&self) -> Option<&str> {
+        Some(&self.interval)
+    }
+
+    fn on_kline_close(&mut self, kline: &Kline) {
+        // 同一根 K 线可能被重复推送，只计入更新的 K 线
+        let tr = match &self.last {
+            Some(last) if kline.open_time <= last.open_time => return,
+            Some(last) => true_range(last.close, kline),
+            None => kline.high - kline.low,
+        };
+        let period = Decimal::from(self.period);
+        self.atr = if self.atr.is_zero() {
+            tr
+        } else {
+            (self.atr * (period - Decimal::ONE) + tr) / period
+        };
+        self.last = Some(kline.clone());
+    }
+
+    fn config(&self) -> StopStrategyConfig {
+        StopStrategyConfig::Chandelier {
+            interval: self.interval.clone(),
+            period: self.period,
+            multiplier: self.multiplier,
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn StopStrategy> {
+        Box::new(self.clone())
+    }
+}
+
+// 交易所托管的跟踪止损，本地不移动止损
+#[derive(Debug, Clone)]
+pub struct ExchangeTrailingStop {
+    callback_rate: Decimal,
+    activation_price: Option<Decimal>,
+}
+
+impl StopStrategy for ExchangeTrailingStop {
+    fn next_stop(&mut self, _ctx: &StopContext) -> Option<Decimal> {
+        None
+    }
+
+    fn is_exchange_managed(&self) -> bool {
+        true
+    }
+
+    fn config(&self) -> StopStrategyConfig {
+        StopStrategyConfig::ExchangeTrailing {
+            callback_rate: self.callback_rate,
+            activation_price: self.activation_price,
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn StopStrategy> {
+        Box::new(self.clone())
+    }
+}
+
+// 根据配置创建策略；阶梯策略需要 adjustment 配置，吊灯策略需要 K 线
+pub fn build_stop_strategy(
+    config: &StopStrategyConfig,
+    mut adjustments: Vec<Adjustment>,
+    klines: &[Kline],
+) -> Box<dyn StopStrategy> {
+    match config.clone() {
+        StopStrategyConfig::Ladder => {
+            // 追加收益超过 110% 后的默认档位；预设自身已覆盖该区间时不再追加
+            let tail = dec!(1.1);
+            if matches!(adjustments.last(), Some(last) if last.max.is_some_and(|max| max <= tail)) {
+                adjustments.push(Adjustment {
+                    min: tail,
+                    max: None,
+                    adjustment: dec!(0.1),
+                });
+            }
+            Box::new(LadderStop::new(adjustments))
+        }
+        StopStrategyConfig::FixedTrail { percent } => Box::new(FixedTrailStop { percent }),
+        StopStrategyConfig::BreakEven { trigger, offset } => {
+            Box::new(BreakEvenStop { trigger, offset })
+        }
+        StopStrategyConfig::Chandelier {
+            interval,
+            period,
+            multiplier,
+        } => Box::new(ChandelierStop::new(interval, period, multiplier, klines)),
+        StopStrategyConfig::ExchangeTrailing {
+            callback_rate,
+            activation_price,
+        } => Box::new(ExchangeTrailingStop {
+            callback_rate,
+            activation_price,
+        }),
+    }
+}
+
+// Wilder 平均真实波幅，取最近 period 根 K 线
+pub fn average_true_range(klines: &[Kline], period: usize) -> Decimal {
+    if klines.len() < 2 || period == 0 {
+        return Decimal::ZERO;
+    }
+    let ranges: Vec<Decimal> = klines
+        .windows(2)
+        .map(|w| true_range(w[0].close, &w[1]))
+        .collect();
+
+    let n = period.min(ranges.len());
+    let period = Decimal::from(period);
+    let mut atr = ranges[..n].iter().sum::<Decimal>() / Decimal::from(n);
+    for tr in &ranges[n..] {
+        atr = (atr * (period - Decimal::ONE) + tr) / period;
+    }
+    atr
+}
+
+fn true_range(prev_close: Decimal, k: &Kline) -> Decimal {
+    (k.high - k.low)
+        .max((k.high - prev_close).abs())
+        .max((k.low - prev_close).abs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(
+        direction: &TradeDirection,
+        extreme: Decimal,
+        stop_loss: Decimal,
+    ) -> StopContext<'_> {
+        StopContext {
+            direction,
+            entry_price: dec!(100),
+            highest_price: extreme,
+            lowest_price: extreme,
+            leverage: dec!(10),
+            stop_loss,
+            profit_percentage: (extreme - dec!(100)).abs() / dec!(100),
+        }
+    }
+
+    #[test]
+    fn test_fixed_trail_only_tightens() {
+        let mut strategy = FixedTrailStop { percent: dec!(0.2) };
+        let long = TradeDirection::Long;
+
+        let stop = strategy
+            .next_stop(&context(&long, dec!(110.0), dec!(95.0)))
+            .unwrap();
+        assert_eq!(stop, dec!(107.8));
+        assert!(strategy
+            .next_stop(&context(&long, dec!(110.0), dec!(108.0)))
+            .is_none());
+
+        let short = TradeDirection::Short;
+        let stop = strategy
+            .next_stop(&context(&short, dec!(90.0), dec!(105.0)))
+            .unwrap();
+        assert_eq!(stop, dec!(91.8));
+    }
+
+    #[test]
+    fn test_break_even_after_trigger() {
+        let mut strategy = BreakEvenStop {
+            trigger: dec!(0.3),
+            offset: dec!(0.01),
+        };
+        let long = TradeDirection::Long;
+
+        assert!(strategy
+            .next_stop(&context(&long, dec!(102.0), dec!(95.0)))
+            .is_none());
+        let stop = strategy
+            .next_stop(&context(&long, dec!(103.0), dec!(95.0)))
+            .unwrap();
+        assert_eq!(stop, dec!(100.1));
+        assert!(strategy
+            .next_stop(&context(&long, dec!(104.0), stop))
+            .is_none());
+    }
+
+    #[test]
+    fn test_average_true_range() {
+        let klines: Vec<Kline> = [(11, 9, 10), (12, 10, 11), (13, 10, 12)]
+            .iter()
+            .map(|&(high, low, close)| Kline {
+                open_time: 0,
+                open: Decimal::from(close),
+                high: Decimal::from(high),
+                low: Decimal::from(low),
+                close: Decimal::from(close),
+            })
+            .collect();
+        // 真实波幅: 2, 3
+        assert_eq!(average_true_range(&klines, 2), dec!(2.5));
+
+        let mut strategy = ChandelierStop::new("1h".to_string(), 2, dec!(2.0), &klines);
+        let long = TradeDirection::Long;
+        let stop = strategy
+            .next_stop(&context(&long, dec!(110.0), dec!(95.0)))
+            .unwrap();
+        assert_eq!(stop, dec!(105.0));
+    }
+
+    #[test]
+    fn test_chandelier_updates_atr_on_kline_close() {
+        let kline = |open_time, high, low, close| Kline {
+            open_time,
+            open: Decimal::from(close),
+            high: Decimal::from(high),
+            low: Decimal::from(low),
+            close: Decimal::from(close),
+        };
+        let klines = [
+            kline(0, 11, 9, 10),
+            kline(1, 12, 10, 11),
+            kline(2, 13, 10, 12),
+        ];
+        let mut strategy = ChandelierStop::new("1h".to_string(), 2, dec!(2.0), &klines);
+        assert_eq!(strategy.kline_interval(), Some("1h"));
+
+        // 波动收窄后 ATR 下降，止损随之收紧：真实波幅 1，ATR = (2.5 + 1) / 2
+        strategy.on_kline_close(&kline(3, 12, 11, 12));
+        assert_eq!(strategy.atr, dec!(1.75));
+        // 重复推送的 K 线不重复计入
+        strategy.on_kline_close(&kline(3, 12, 11, 12));
+        assert_eq!(strategy.atr, dec!(1.75));
+
+        let long = TradeDirection::Long;
+        let stop = strategy
+            .next_stop(&context(&long, dec!(110.0), dec!(105.0)))
+            .unwrap();
+        assert_eq!(stop, dec!(106.5));
+    }
+}
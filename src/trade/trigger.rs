@@ -0,0 +1,358 @@
+use std::{fmt, sync::Arc};
+
+use rust_decimal::Decimal;
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, mpsc, Mutex};
+
+use super::price::{PriceBook, PriceEvent};
+use crate::{orm::triggers, symbol::SymbolMap, utils::parse_decimal};
+
+// 通知缓冲区大小，订阅者落后超过该数量时会丢失最早的通知
+const NOTICE_CAPACITY: usize = 256;
+
+// 触发器比较的价格
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub enum TriggerSource {
+    Ask,
+    Bid,
+    // 买一卖一中间价
+    #[default]
+    Mid,
+    // 最新成交价（aggTrade）
+    Last,
+    // 标记价格
+    Mark,
+}
+
+impl fmt::Display for TriggerSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl TriggerSource {
+    pub fn follows(&self, event: &PriceEvent) -> bool {
+        matches!(
+            (self, event),
+            (
+                TriggerSource::Ask | TriggerSource::Bid | TriggerSource::Mid,
+                PriceEvent::Book { .. }
+            ) | (TriggerSource::Last, PriceEvent::Last(_))
+                | (TriggerSource::Mark, PriceEvent::Mark(_))
+        )
+    }
+
+    // 尚无有效价格时返回 None
+    pub fn price(&self, book: &PriceBook) -> Option<Decimal> {
+        let price = match self {
+            TriggerSource::Ask => book.ask,
+            TriggerSource::Bid => book.bid,
+            TriggerSource::Mid if book.ask > Decimal::ZERO && book.bid > Decimal::ZERO => {
+                (book.ask + book.bid) / Decimal::TWO
+            }
+            TriggerSource::Mid => Decimal::ZERO,
+            TriggerSource::Last => book.last,
+            TriggerSource::Mark => book.mark,
+        };
+        (price > Decimal::ZERO).then_some(price)
+    }
+
+    pub fn parse(source: &str) -> Option<Self> {
+        match source {
+            "Ask" => Some(TriggerSource::Ask),
+            "Bid" => Some(TriggerSource::Bid),
+            "Mid" => Some(TriggerSource::Mid),
+            "Last" => Some(TriggerSource::Last),
+            "Mark" => Some(TriggerSource::Mark),
+            _ => None,
+        }
+    }
+}
+
+// 触发条件
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub enum TriggerCondition {
+    // 价格由下向上穿过 price
+    CrossAbove { price: Decimal },
+    // 价格由上向下穿过 price
+    CrossBelow { price: Decimal },
+    // 相对创建时价格的涨跌幅达到 percent，例如 0.05 为上涨 5%，-0.05 为下跌 5%
+    PercentMove { percent: Decimal },
+}
+
+impl TriggerCondition {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            TriggerCondition::CrossAbove { price } | TriggerCondition::CrossBelow { price }
+                if *price <= Decimal::ZERO =>
+            {
+                Err("Trigger price must be positive".to_string())
+            }
+            TriggerCondition::PercentMove { percent }
+                if percent.is_zero() || *percent <= -Decimal::ONE =>
+            {
+                Err("percent must be non-zero and greater than -1".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // 穿越条件需要上一次价格，首个价格只用于建立基准
+    pub fn is_met(&self, last: Option<Decimal>, price: Decimal, reference: Decimal) -> bool {
+        match self {
+            TriggerCondition::CrossAbove { price: level } => {
+                last.is_some_and(|l| l < *level) && price >= *level
+            }
+            TriggerCondition::CrossBelow { price: level } => {
+                last.is_some_and(|l| l > *level) && price <= *level
+            }
+            TriggerCondition::PercentMove { percent } => {
+                if reference <= Decimal::ZERO {
+                    return false;
+                }
+                let change = (price - reference) / reference;
+                if percent.is_sign_positive() {
+                    change >= *percent
+                } else {
+                    change <= *percent
+                }
+            }
+        }
+    }
+}
+
+// 触发动作
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub enum TriggerAction {
+    // 按保存的开仓请求开仓，字段与 /trade/create_trade 相同
+    OpenTrade { trade: serde_json::Value },
+    // 只推送通知
+    Notify { message: Option<String> },
+}
+
+// 已布防的触发器
+#[derive(Debug, Clone)]
+pub struct Trigger {
+    pub id: i64,
+    pub owner_id: String,
+    pub symbol: String,
+    pub source: TriggerSource,
+    pub condition: TriggerCondition,
+    pub action: TriggerAction,
+    pub reference_price: Decimal,
+    pub expires_at: Option<u32>,
+    last_price: Option<Decimal>,
+}
+
+impl Trigger {
+    // 以创建时价格作为上一次价格，重启期间发生的穿越在首个行情时触发
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        id: i64,
+        owner_id: String,
+        symbol: String,
+        source: TriggerSource,
+        condition: TriggerCondition,
+        action: TriggerAction,
+        reference_price: Decimal,
+        expires_at: Option<u32>,
+    ) -> Self {
+        Self {
+            id,
+            owner_id,
+            symbol,
+            source,
+            condition,
+            action,
+            reference_price,
+            expires_at,
+            last_price: Some(reference_price),
+        }
+    }
+
+    pub fn from_model(model: &triggers::Model) -> Result<Self, String> {
+        Ok(Self::new(
+            model.id,
+            model.owner_id.clone(),
+            model.symbol.clone(),
+            TriggerSource::parse(&model.source)
+                .ok_or(format!("Invalid trigger source {}", model.source))?,
+            serde_json::from_str(&model.condition)
+                .map_err(|e| format!("Invalid trigger condition: {}", e))?,
+            serde_json::from_str(&model.action)
+                .map_err(|e| format!("Invalid trigger action: {}", e))?,
+            parse_decimal(&model.reference_price).unwrap_or_default(),
+            model.expires_at,
+        ))
+    }
+}
+
+// 价格循环交给执行任务的触发结果
+#[derive(Debug)]
+pub enum TriggerFire {
+    Fired { trigger: Trigger, price: Decimal },
+    Expired { trigger: Trigger },
+}
+
+// 推送给用户的触发器通知
+#[derive(Debug, Clone, Serialize)]
+pub struct TriggerNotice {
+    pub trigger_id: i64,
+    #[serde(skip)]
+    pub owner_id: String,
+    pub symbol: String,
+    pub status: String, // 'Fired', 'Failed', 'Expired'
+    pub price: Option<Decimal>,
+    pub message: Option<String>,
+    pub timestamp: u32,
+}
+
+// 各品种已布防的触发器，由价格循环检查，触发后移出并交给执行任务
+#[derive(Debug)]
+pub struct Triggers {
+    armed: Arc<SymbolMap<Mutex<Vec<Trigger>>>>,
+    sender: mpsc::UnboundedSender<TriggerFire>,
+    notices: broadcast::Sender<TriggerNotice>,
+}
+
+impl Triggers {
+    pub fn new(symbols: &[String]) -> (Arc<Self>, mpsc::UnboundedReceiver<TriggerFire>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let (notices, _) = broadcast::channel(NOTICE_CAPACITY);
+        let armed = SymbolMap::new(symbols, || Mutex::new(Vec::new()));
+        (
+            Arc::new(Self {
+                armed,
+                sender,
+                notices,
+            }),
+            receiver,
+        )
+    }
+
+    pub fn has_symbol(&self, symbol: &str) -> bool {
+        self.armed.contains_key(symbol)
+    }
+
+    // 新启用的交易对。停用时保留列表，已布防的触发器在重新启用后继续生效
+    pub fn add_symbol(&self, symbol: &str) {
+        self.armed.insert(symbol, Mutex::new(Vec::new()));
+    }
+
+    pub async fn arm(&self, trigger: Trigger) {
+        if let Some(mutex_vec) = self.armed.get(&trigger.symbol) {
+            mutex_vec.lock().await.push(trigger);
+        }
+    }
+
+    // 撤销未触发的触发器，已触发或不存在时返回 false
+    pub async fn disarm(&self, symbol: &str, id: i64, owner_id: &str) -> bool {
+        let Some(mutex_vec) = self.armed.get(symbol) else {
+            return false;
+        };
+        let mut vec = mutex_vec.lock().await;
+        let before = vec.len();
+        vec.retain(|t| !(t.id == id && t.owner_id == owner_id));
+        vec.len() != before
+    }
+
+    pub async fn armed_count(&self, owner_id: &str) -> usize {
+        let mut count = 0;
+        for (_, mutex_vec) in self.armed.entries() {
+            count += mutex_vec
+                .lock()
+                .await
+                .iter()
+                .filter(|t| t.owner_id == owner_id)
+                .count();
+        }
+        count
+    }
+
+    // 价格循环调用：只检查受该事件影响的触发器，触发或过期的移出列表
+    pub async fn evaluate(&self, symbol: &str, book: &PriceBook, event: &PriceEvent, now: u32) {
+        let Some(mutex_vec) = self.armed.get(symbol) else {
+            return;
+        };
+        let mut vec = mutex_vec.lock().await;
+        let mut index = 0;
+        while index < vec.len() {
+            let trigger = &mut vec[index];
+            if trigger.expires_at.is_some_and(|at| now >= at) {
+                let trigger = vec.remove(index);
+                let _ = self.sender.send(TriggerFire::Expired { trigger });
+                continue;
+            }
+            if trigger.source.follows(event) {
+                if let Some(price) = trigger.source.price(book) {
+                    if trigger
+                        .condition
+                        .is_met(trigger.last_price, price, trigger.reference_price)
+                    {
+                        let trigger = vec.remove(index);
+                        let _ = self.sender.send(TriggerFire::Fired { trigger, price });
+                        continue;
+                    }
+                    trigger.last_price = Some(price);
+                }
+            }
+            index += 1;
+        }
+    }
+
+    // 没有订阅者时通知直接丢弃
+    pub fn notify(&self, notice: TriggerNotice) {
+        let _ = self.notices.send(notice);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<TriggerNotice> {
+        self.notices.subscribe()
+    }
+}
+
+// 启动时读取仍在布防的触发器
+pub async fn find_armed_triggers(db: &DatabaseConnection) -> Result<Vec<triggers::Model>, DbErr> {
+    triggers::Entity::find()
+        .filter(triggers::Column::Status.eq("Armed"))
+        .all(db)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_trigger_conditions() {
+        let above = TriggerCondition::CrossAbove { price: dec!(1.05) };
+        // 没有上一次价格时不判断穿越
+        assert!(!above.is_met(None, dec!(1.06), dec!(1)));
+        assert!(!above.is_met(Some(dec!(1.04)), dec!(1.049), dec!(1)));
+        assert!(above.is_met(Some(dec!(1.04)), dec!(1.05), dec!(1)));
+        // 创建时已在上方，需要先回到下方
+        assert!(!above.is_met(Some(dec!(1.06)), dec!(1.07), dec!(1)));
+
+        let below = TriggerCondition::CrossBelow { price: dec!(0.3) };
+        assert!(below.is_met(Some(dec!(0.31)), dec!(0.29), dec!(0.31)));
+        assert!(!below.is_met(Some(dec!(0.29)), dec!(0.28), dec!(0.31)));
+
+        let drop = TriggerCondition::PercentMove {
+            percent: dec!(-0.05),
+        };
+        assert!(!drop.is_met(None, dec!(96), dec!(100)));
+        assert!(drop.is_met(None, dec!(95), dec!(100)));
+        let rise = TriggerCondition::PercentMove {
+            percent: dec!(0.05),
+        };
+        assert!(!rise.is_met(None, dec!(95), dec!(100)));
+        assert!(rise.is_met(None, dec!(105), dec!(100)));
+        assert!(rise.validate().is_ok());
+        assert!(TriggerCondition::PercentMove { percent: dec!(0) }
+            .validate()
+            .is_err());
+    }
+}
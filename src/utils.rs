@@ -1,18 +1,36 @@
-use crate::{
-    error::{Error, Result},
-    trade::{Adjustment, AdjustmentConfig},
-};
+use crate::trade::price::PriceEvent;
+use rust_decimal::Decimal;
 use serde::{de, Deserialize, Deserializer};
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// 组合流消息：{"stream": "<symbol>@<channel>", "data": {...}}
+#[derive(Deserialize, Debug)]
+struct StreamMessage {
+    data: StreamData,
+}
 
-#[derive(Deserialize, Debug, Clone)]
-pub struct Book {
-    pub a: String,
-    pub b: String,
+#[derive(Deserialize, Debug)]
+#[serde(tag = "e")]
+enum StreamData {
+    #[serde(rename = "bookTicker")]
+    BookTicker { a: Decimal, b: Decimal },
+    #[serde(rename = "aggTrade")]
+    AggTrade { p: Decimal },
+    #[serde(rename = "markPriceUpdate")]
+    MarkPrice { p: Decimal },
 }
 
-pub fn parse_trade_json(json_text: &str) -> Result<Book> {
-    serde_json::from_str(json_text).map_err(Error::JsonError) // 使用 map_err 将 serde_json::Error 转换为 Error::JsonError
+pub fn parse_stream_json(json_text: &str) -> serde_json::Result<PriceEvent> {
+    let message: StreamMessage = serde_json::from_str(json_text)?;
+    Ok(match message.data {
+        StreamData::BookTicker { a, b } => PriceEvent::Book { ask: a, bid: b },
+        StreamData::AggTrade { p } => PriceEvent::Last(p),
+        StreamData::MarkPrice { p } => PriceEvent::Mark(p),
+    })
 }
 
 // 自定义转换函数，将字符串转换为 f64
@@ -25,9 +43,12 @@ pub fn parse_trade_json(json_text: &str) -> Result<Book> {
 // }
 
 pub fn format_url(symbol: &str) -> String {
-    // format!("wss://stream.binance.com:443/ws/{}@miniTicker", symbol)
-    // format!("wss://stream.binance.com:443/ws/{}@trade", symbol)
-    format!("wss://stream.binance.com:443/ws/{}@bookTicker", symbol)
+    // format!("wss://stream.binance.com:443/ws/{}@bookTicker", symbol)
+    // 合约组合流：盘口、归集成交、标记价格
+    format!(
+        "wss://fstream.binance.com/stream?streams={0}@bookTicker/{0}@aggTrade/{0}@markPrice@1s",
+        symbol
+    )
 }
 
 pub struct TradeIdGenerator {
@@ -48,48 +69,80 @@ impl TradeIdGenerator {
     }
 }
 
-pub fn trim_trailing_zeros(input: &str) -> String {
-    if input.contains('.') {
-        // 如果包含小数点，去掉末尾的零
-        input
-            .trim_end_matches('0')
-            .trim_end_matches('.')
-            .to_string()
-    } else {
-        // 如果是整数，直接返回
-        input.to_string()
+// 按步长向下取整，避免下单数量超过可用数量
+pub fn round_to_step(value: Decimal, step: Decimal) -> Decimal {
+    if step <= Decimal::ZERO {
+        return value;
     }
+    ((value / step).floor() * step).normalize()
 }
 
-pub fn create_adjustment_config(mut adjustments: Vec<Adjustment>) -> AdjustmentConfig {
-    // 添加最后一个默认 Adjustment
-    adjustments.push(Adjustment {
-        min: 1.1,
-        max: None,
-        adjustment: 0.1,
-    });
+// 按最小价格单位取整，向下或向上
+pub fn round_to_tick(value: Decimal, tick: Decimal, round_up: bool) -> Decimal {
+    if tick <= Decimal::ZERO {
+        return value;
+    }
+    let ticks = value / tick;
+    let ticks = if round_up {
+        ticks.ceil()
+    } else {
+        ticks.floor()
+    };
+    (ticks * tick).normalize()
+}
 
-    AdjustmentConfig { adjustments }
+// 解析价格字符串，失败时返回 None 而不是 panic
+pub fn parse_decimal(input: &str) -> Option<Decimal> {
+    Decimal::from_str(input.trim()).ok()
 }
 
-pub fn create_adjustment_config_raw(mut adjustments: Vec<Adjustment>) -> AdjustmentConfig {
-    AdjustmentConfig { adjustments }
+// 当前 UNIX 时间戳（秒）
+pub fn unix_timestamp() -> u32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as u32)
+        .unwrap_or_default()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_round_to_step_and_tick() {
+        assert_eq!(round_to_step(dec!(1.239), dec!(0.01)), dec!(1.23));
+        assert_eq!(round_to_step(dec!(12.7), dec!(1)), dec!(12));
+        assert_eq!(round_to_step(dec!(0.9), dec!(0.1)), dec!(0.9));
+        assert_eq!(
+            round_to_tick(dec!(4.50912), dec!(0.0001), false),
+            dec!(4.5091)
+        );
+        assert_eq!(
+            round_to_tick(dec!(4.50912), dec!(0.0001), true),
+            dec!(4.5092)
+        );
+        assert_eq!(round_to_tick(dec!(4.509), dec!(0.0001), true), dec!(4.509));
+        assert_eq!(parse_decimal("0.123"), Some(dec!(0.123)));
+        assert_eq!(parse_decimal("abc"), None);
+    }
 
     #[test]
-    fn test_trim_trailing_zeros() {
-        assert_eq!(trim_trailing_zeros("1.23230000"), "1.2323");
-        assert_eq!(trim_trailing_zeros("1.3000"), "1.3");
-        assert_eq!(trim_trailing_zeros("123.000"), "123");
-        assert_eq!(trim_trailing_zeros("1.0"), "1");
-        assert_eq!(trim_trailing_zeros("0.000"), "0");
-        assert_eq!(trim_trailing_zeros("100"), "100");
-        assert_eq!(trim_trailing_zeros("0"), "0");
-        assert_eq!(trim_trailing_zeros("0.0"), "0");
-        assert_eq!(trim_trailing_zeros(".0000"), ""); // Edge case: only zeros
+    fn test_parse_stream_json() {
+        let book = r#"{"stream":"adausdt@bookTicker","data":{"e":"bookTicker","u":1,"s":"ADAUSDT","b":"1.0100","B":"10","a":"1.0200","A":"5","T":1,"E":1}}"#;
+        match parse_stream_json(book).unwrap() {
+            PriceEvent::Book { ask, bid } => {
+                assert_eq!(ask, dec!(1.02));
+                assert_eq!(bid, dec!(1.01));
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+
+        let mark = r#"{"stream":"adausdt@markPrice@1s","data":{"e":"markPriceUpdate","E":1,"s":"ADAUSDT","p":"1.0150","i":"1.0149","P":"1.0","r":"0.0001","T":1}}"#;
+        assert!(matches!(
+            parse_stream_json(mark).unwrap(),
+            PriceEvent::Mark(p) if p == dec!(1.015)
+        ));
+        assert!(parse_stream_json(r#"{"result":null,"id":1}"#).is_err());
     }
 }
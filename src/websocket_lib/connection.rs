@@ -1,10 +1,19 @@
 use crate::{
-    trade::Trade,
-    utils::{self, format_url, trim_trailing_zeros},
+    symbol::SymbolMap,
+    trade::{
+        close_take_profit, close_with_retry,
+        event::EventBus,
+        exchange::{Exchange, TradeExchange},
+        price::PriceBook,
+        trigger::Triggers,
+        CloseReason, Trade,
+    },
+    utils::{self, format_url},
 };
+
+use super::recorder::MarketRecorder;
 use futures_util::{SinkExt, StreamExt};
-use sea_orm::DatabaseConnection;
-use std::{collections::HashMap, sync::Arc};
+use std::sync::Arc;
 use tokio::{
     sync::Mutex,
     time::{self, timeout, Duration},
@@ -14,9 +23,11 @@ use url::Url;
 
 pub async fn connect_to_websocket(
     symbol: String,
-    trades: Arc<HashMap<String, Mutex<Vec<Trade>>>>,
-    prices: Arc<HashMap<String, Mutex<(String, String)>>>,
-    database: DatabaseConnection,
+    trades: Arc<SymbolMap<Mutex<Vec<Trade>>>>,
+    prices: Arc<SymbolMap<Mutex<PriceBook>>>,
+    events: EventBus,
+    triggers: Arc<Triggers>,
+    recorder: Option<MarketRecorder>,
 ) {
     let url = format_url(&symbol);
     // let key = symbol.to_string();
@@ -27,48 +38,15 @@ pub async fn connect_to_websocket(
                     let msg = timeout(Duration::from_secs(30), socket.next()).await;
                     match msg {
                         Ok(Some(inner_msg)) => match inner_msg {
-                            Ok(Message::Text(text)) => match utils::parse_trade_json(&text) {
-                                Ok(data) => {
-                                    // println!("{:?}", data);
-                                    let book_price = (
-                                        trim_trailing_zeros(&data.a),
-                                        trim_trailing_zeros(&data.b),
-                                    );
-                                    if let Some(mutex_f64) = prices.get(&symbol) {
-                                        let mut book = mutex_f64.lock().await;
-                                        *book = book_price.clone();
-                                    } else {
-                                        // eprintln!("failed symbol: {:?}", data.s);
-                                    }
-
-                                    // Access the `Mutex<Vec<Trade>>` for the given key
-                                    if let Some(mutex_vec) = trades.get(&symbol) {
-                                        let mut vec = mutex_vec.lock().await;
-
-                                        vec.retain(|t| {
-                                            if t.is_closed {
-                                                false // 如果 t.is_closed 为 true，则从 vec 中移除
-                                            } else {
-                                                true // 保留元素，并在后续的 for 循环中处理
-                                            }
-                                        });
-
-                                        // Update the price for each trade in the vector
-                                        for t in vec.iter_mut() {
-                                            t.update_price(book_price.clone(), &database).await;
-                                        }
-
-                                        // Alternatively, you may want to add a new trade instead
-                                        // vec.push(trade);
-                                    } else {
-                                        // eprintln!("failed symbol: {:?}", data.s);
-                                    }
+                            Ok(Message::Text(text)) => {
+                                if let Some(recorder) = &recorder {
+                                    recorder.record(&text);
                                 }
-                                Err(_e) => {
-                                    // println!("price: {}", text);
-                                    // eprintln!("failed to parse JSON: {:?}", e);
-                                }
-                            },
+                                handle_message(
+                                    &symbol, &text, &trades, &prices, &events, &triggers,
+                                )
+                                .await;
+                            }
                             Ok(Message::Ping(ping)) => {
                                 // println!("Received Ping from {}: {:?}", url, ping);
                                 socket
@@ -115,3 +93,73 @@ pub async fn connect_to_websocket(
         println!("Reconnecting to {}...", url);
     }
 }
+
+// 处理一条组合流消息：更新盘口，再驱动该品种的交易。实盘和行情回放共用
+pub async fn handle_message(
+    symbol: &str,
+    text: &str,
+    trades: &Arc<SymbolMap<Mutex<Vec<Trade>>>>,
+    prices: &SymbolMap<Mutex<PriceBook>>,
+    events: &EventBus,
+    triggers: &Triggers,
+) {
+    let Ok(event) = utils::parse_stream_json(text) else {
+        return;
+    };
+    let book = match prices.get(symbol) {
+        Some(mutex_book) => {
+            let mut book = mutex_book.lock().await;
+            book.apply(&event);
+            book.clone()
+        }
+        None => return,
+    };
+    triggers
+        .evaluate(symbol, &book, &event, utils::unix_timestamp())
+        .await;
+
+    if let Some(mutex_vec) = trades.get(symbol) {
+        let mut vec = mutex_vec.lock().await;
+
+        // 已平仓的交易从 vec 中移除
+        vec.retain(|t| !t.is_closed());
+
+        // 只有该事件改变了交易所选价格来源时才更新
+        for t in vec.iter_mut() {
+            if !t.price_source.follows(&event) {
+                continue;
+            }
+            // 止盈和止损在独立任务中下单，不占用交易列表锁，失败时重试
+            let actions = t.update_price(&book, events);
+            for order in actions.take_profits {
+                tokio::spawn(close_take_profit(
+                    trades.clone(),
+                    events.clone(),
+                    symbol.to_string(),
+                    t.id,
+                    order,
+                ));
+            }
+            if let Some(price) = actions.stop {
+                let trade = t.clone();
+                let trades = trades.clone();
+                let events = events.clone();
+                let symbol = symbol.to_string();
+                tokio::spawn(async move {
+                    if let Some(order_id) = actions.cancel_stop {
+                        TradeExchange.cancel_stop(&trade, order_id).await;
+                    }
+                    close_with_retry(
+                        trades,
+                        events,
+                        symbol,
+                        trade.id,
+                        price,
+                        CloseReason::StopLoss,
+                    )
+                    .await;
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use std::{
+    collections::HashMap,
+    env,
+    path::PathBuf,
+    sync::{Arc, Mutex as StdMutex},
+};
+
+use tokio::{sync::Mutex, task::JoinHandle};
+
+use super::{
+    connection::connect_to_websocket,
+    recorder::{MarketRecorder, DEFAULT_ROTATE_SECS},
+    replay::replay_market,
+};
+use crate::{
+    symbol::SymbolMap,
+    trade::{event::EventBus, price::PriceBook, trigger::Triggers, Trade},
+};
+
+// 行情任务管理：实时 websocket（可选录制）或回放录制文件，每个交易对一个任务
+pub struct MarketFeed {
+    trades: Arc<SymbolMap<Mutex<Vec<Trade>>>>,
+    prices: Arc<SymbolMap<Mutex<PriceBook>>>,
+    events: EventBus,
+    triggers: Arc<Triggers>,
+    replay_dir: Option<PathBuf>,
+    replay_speed: f64,
+    record_dir: Option<PathBuf>,
+    rotate_secs: u64,
+    tasks: StdMutex<HashMap<String, JoinHandle<()>>>,
+}
+
+impl MarketFeed {
+    // 设置 MARKET_REPLAY_DIR 时用录制的行情代替实时 websocket
+    pub fn from_env(
+        trades: Arc<SymbolMap<Mutex<Vec<Trade>>>>,
+        prices: Arc<SymbolMap<Mutex<PriceBook>>>,
+        events: EventBus,
+        triggers: Arc<Triggers>,
+    ) -> Arc<Self> {
+        // 回放倍速，1 为实时，0 为不等待
+        let replay_speed = env::var("MARKET_REPLAY_SPEED")
+            .ok()
+            .and_then(|s| s.parse::<f64>().ok())
+            .unwrap_or(1.0);
+        let rotate_secs = env::var("MARKET_RECORD_ROTATE_SECS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_ROTATE_SECS);
+        Arc::new(MarketFeed {
+            trades,
+            prices,
+            events,
+            triggers,
+            replay_dir: env::var("MARKET_REPLAY_DIR").ok().map(PathBuf::from),
+            replay_speed,
+            record_dir: env::var("MARKET_RECORD_DIR").ok().map(PathBuf::from),
+            rotate_secs,
+            tasks: StdMutex::new(HashMap::new()),
+        })
+    }
+
+    // 登记交易对的共享状态并启动行情任务，已在运行时不重复启动
+    pub fn start(&self, symbol: &str) {
+        let mut tasks = self.tasks.lock().unwrap();
+        if tasks.contains_key(symbol) {
+            return;
+        }
+        self.trades.insert(symbol, Mutex::new(Vec::new()));
+        self.prices.insert(symbol, Mutex::new(PriceBook::default()));
+        self.triggers.add_symbol(symbol);
+
+        let symbol_clone = symbol.to_string();
+        let trades_clone = self.trades.clone();
+        let prices_clone = self.prices.clone();
+        let events_clone = self.events.clone();
+        let triggers_clone = self.triggers.clone();
+        let task = match &self.replay_dir {
+            Some(dir) => tokio::spawn(replay_market(
+                symbol_clone,
+                dir.clone(),
+                self.replay_speed,
+                trades_clone,
+                prices_clone,
+                events_clone,
+                triggers_clone,
+            )),
+            None => {
+                let recorder = self
+                    .record_dir
+                    .as_ref()
+                    .map(|dir| MarketRecorder::start(dir, symbol, self.rotate_secs));
+                tokio::spawn(connect_to_websocket(
+                    symbol_clone,
+                    trades_clone,
+                    prices_clone,
+                    events_clone,
+                    triggers_clone,
+                    recorder,
+                ))
+            }
+        };
+        tasks.insert(symbol.to_string(), task);
+    }
+
+    // 停止行情任务并移除交易对的交易和价格，调用方需先确认没有进行中的交易
+    pub fn stop(&self, symbol: &str) {
+        if let Some(task) = self.tasks.lock().unwrap().remove(symbol) {
+            task.abort();
+        }
+        self.trades.remove(symbol);
+        self.prices.remove(symbol);
+    }
+}
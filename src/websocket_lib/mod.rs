@@ -1 +1,4 @@
 pub(crate) mod connection;
+pub(crate) mod feed;
+pub(crate) mod recorder;
+pub(crate) mod replay;
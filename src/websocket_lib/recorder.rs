@@ -0,0 +1,130 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use flate2::{write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+
+// 默认每小时切换一个文件
+pub const DEFAULT_ROTATE_SECS: u64 = 3600;
+
+// 录制的一行：接收时间（毫秒）和原始组合流消息
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedMessage {
+    pub time: u64,
+    pub message: String,
+}
+
+// 行情录制器：每个品种一个写入线程，websocket 任务只负责发送，不阻塞行情处理
+#[derive(Debug, Clone)]
+pub struct MarketRecorder {
+    sender: Sender<RecordedMessage>,
+}
+
+impl MarketRecorder {
+    // 文件写入 <dir>/<symbol>/<symbol>-<首条消息毫秒>.jsonl.gz
+    pub fn start(dir: &Path, symbol: &str, rotate_secs: u64) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let writer = RecordingWriter::new(dir.join(symbol), symbol, rotate_secs * 1000);
+        std::thread::spawn(move || run_writer(receiver, writer));
+        Self { sender }
+    }
+
+    pub fn record(&self, message: &str) {
+        let _ = self.sender.send(RecordedMessage {
+            time: now_millis(),
+            message: message.to_string(),
+        });
+    }
+}
+
+fn run_writer(receiver: Receiver<RecordedMessage>, mut writer: RecordingWriter) {
+    loop {
+        // 队列清空后刷新，进程异常退出时最多丢失最后一批
+        let record = match receiver.try_recv() {
+            Ok(record) => record,
+            Err(TryRecvError::Empty) => {
+                if let Err(e) = writer.flush() {
+                    eprintln!("行情录制刷新失败 {}：{}", writer.symbol, e);
+                }
+                match receiver.recv() {
+                    Ok(record) => record,
+                    Err(_) => break,
+                }
+            }
+            Err(TryRecvError::Disconnected) => break,
+        };
+        if let Err(e) = writer.write(&record) {
+            eprintln!("行情录制写入失败 {}：{}", writer.symbol, e);
+        }
+    }
+    let _ = writer.finish();
+}
+
+// 按时间轮转的 gzip JSONL 写入器
+pub struct RecordingWriter {
+    dir: PathBuf,
+    symbol: String,
+    rotate_ms: u64,
+    current: Option<(u64, GzEncoder<BufWriter<File>>)>,
+}
+
+impl RecordingWriter {
+    pub fn new(dir: PathBuf, symbol: &str, rotate_ms: u64) -> Self {
+        Self {
+            dir,
+            symbol: symbol.to_string(),
+            rotate_ms,
+            current: None,
+        }
+    }
+
+    pub fn write(&mut self, record: &RecordedMessage) -> io::Result<()> {
+        let expired = self
+            .current
+            .as_ref()
+            .is_some_and(|(start, _)| record.time >= start + self.rotate_ms);
+        if expired {
+            self.finish()?;
+        }
+        if self.current.is_none() {
+            fs::create_dir_all(&self.dir)?;
+            let path = self
+                .dir
+                .join(format!("{}-{}.jsonl.gz", self.symbol, record.time));
+            let file = BufWriter::new(File::create(path)?);
+            self.current = Some((record.time, GzEncoder::new(file, Compression::default())));
+        }
+        if let Some((_, encoder)) = &mut self.current {
+            serde_json::to_writer(&mut *encoder, record)?;
+            encoder.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        if let Some((_, encoder)) = &mut self.current {
+            encoder.flush()?;
+        }
+        Ok(())
+    }
+
+    // 写入 gzip 结尾并关闭当前文件
+    pub fn finish(&mut self) -> io::Result<()> {
+        if let Some((_, encoder)) = self.current.take() {
+            encoder.finish()?.flush()?;
+        }
+        Ok(())
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
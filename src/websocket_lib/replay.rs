@@ -0,0 +1,144 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use flate2::read::MultiGzDecoder;
+use tokio::{
+    sync::{mpsc, Mutex},
+    time::{sleep_until, Duration, Instant},
+};
+
+use super::{connection::handle_message, recorder::RecordedMessage};
+use crate::symbol::SymbolMap;
+use crate::trade::{event::EventBus, price::PriceBook, trigger::Triggers, Trade};
+
+// 读取线程与回放任务之间的缓冲
+const REPLAY_BUFFER: usize = 1024;
+
+// 回放 <dir>/<symbol> 下的录制文件，按录制时的间隔送入与实盘相同的处理流程。
+// speed 为倍速，1 为实时，0 表示不等待、尽快回放
+pub async fn replay_market(
+    symbol: String,
+    dir: PathBuf,
+    speed: f64,
+    trades: Arc<SymbolMap<Mutex<Vec<Trade>>>>,
+    prices: Arc<SymbolMap<Mutex<PriceBook>>>,
+    events: EventBus,
+    triggers: Arc<Triggers>,
+) {
+    let files = match recording_files(&dir.join(&symbol)) {
+        Ok(files) if !files.is_empty() => files,
+        Ok(_) => {
+            eprintln!("没有 {} 的录制文件：{}", symbol, dir.display());
+            return;
+        }
+        Err(e) => {
+            eprintln!("读取 {} 的录制目录失败：{}", symbol, e);
+            return;
+        }
+    };
+
+    let (sender, mut receiver) = mpsc::channel(REPLAY_BUFFER);
+    tokio::task::spawn_blocking(move || {
+        for path in files {
+            let records = match read_recording(&path) {
+                Ok(records) => records,
+                Err(e) => {
+                    eprintln!("打开录制文件失败 {}：{}", path.display(), e);
+                    continue;
+                }
+            };
+            for record in records {
+                if sender.blocking_send(record).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    // 按首条消息对齐时钟，避免逐条等待累积误差
+    let mut origin: Option<(u64, Instant)> = None;
+    let mut count = 0u64;
+    while let Some(record) = receiver.recv().await {
+        if speed > 0.0 {
+            let (first_time, started) = *origin.get_or_insert((record.time, Instant::now()));
+            let elapsed = record.time.saturating_sub(first_time) as f64 / speed;
+            sleep_until(started + Duration::from_millis(elapsed as u64)).await;
+        }
+        handle_message(
+            &symbol,
+            &record.message,
+            &trades,
+            &prices,
+            &events,
+            &triggers,
+        )
+        .await;
+        count += 1;
+    }
+    println!("{} 行情回放结束，共 {} 条消息", symbol, count);
+}
+
+// 按文件名中的起始时间排序
+fn recording_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files: Vec<(u64, PathBuf)> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter_map(|path| {
+            let name = path.file_name()?.to_str()?;
+            let start = name
+                .strip_suffix(".jsonl.gz")?
+                .rsplit('-')
+                .next()?
+                .parse()
+                .ok()?;
+            Some((start, path))
+        })
+        .collect();
+    files.sort();
+    Ok(files.into_iter().map(|(_, path)| path).collect())
+}
+
+// 逐行读取录制文件；进程异常退出时文件末尾可能不完整，读到损坏的行即停止
+pub fn read_recording(path: &Path) -> io::Result<impl Iterator<Item = RecordedMessage>> {
+    let reader = BufReader::new(MultiGzDecoder::new(File::open(path)?));
+    Ok(reader
+        .lines()
+        .map_while(|line| serde_json::from_str(&line.ok()?).ok()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::websocket_lib::recorder::RecordingWriter;
+
+    #[test]
+    fn test_record_and_read_back() {
+        let dir = std::env::temp_dir().join(format!("recording-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut writer = RecordingWriter::new(dir.clone(), "filusdt", 1000);
+        let messages: Vec<RecordedMessage> = [1000, 1500, 2100, 2200]
+            .iter()
+            .map(|&time| RecordedMessage {
+                time,
+                message: format!("{{\"t\":{}}}", time),
+            })
+            .collect();
+        for m in &messages {
+            writer.write(m).unwrap();
+        }
+        writer.finish().unwrap();
+
+        // 超过 1 秒切换文件，按起始时间排序后顺序不变
+        let files = recording_files(&dir).unwrap();
+        assert_eq!(files.len(), 2);
+        let replayed: Vec<RecordedMessage> = files
+            .iter()
+            .flat_map(|f| read_recording(f).unwrap())
+            .collect();
+        assert_eq!(replayed, messages);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}